@@ -129,6 +129,14 @@ if [ -f topic-taxonomy-detailed.json ]; then
     mv topic-taxonomy-detailed.json "$OUTPUT_DIR/"
     echo "   ✓ topic-taxonomy-detailed.json"
 fi
+if [ -f filtered-topics.json ]; then
+    mv filtered-topics.json "$OUTPUT_DIR/"
+    echo "   ✓ filtered-topics.json"
+fi
+if [ -f topic-coords.json ]; then
+    mv topic-coords.json "$OUTPUT_DIR/"
+    echo "   ✓ topic-coords.json"
+fi
 
 # Step 3: Generate derived visualizations
 echo ""
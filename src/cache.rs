@@ -1,11 +1,18 @@
-use std::{collections::HashMap, path::Path, path::PathBuf, sync::Arc, time::SystemTime};
+use std::{collections::{HashMap, HashSet}, path::Path, path::PathBuf, sync::Arc, time::{Duration, SystemTime}};
 
 use anyhow::{anyhow, Context, Result};
-use futures::future;
+use futures::{future, stream, Future, StreamExt};
 use serde::Deserialize;
 
 use crate::config::AppState;
+use crate::gzip::prefer_gz;
 use crate::rag::RagIndex;
+use crate::rag::embeddings::embed_texts;
+use crate::rag::retrieval::{RagItem, TitleEmbedding, TitleEmbeddings};
+
+/// Topics per episode, keyed by (podcast_id, episode_number) so maps from
+/// several podcasts can be merged without episode numbers colliding.
+pub type EpisodeTopicsMap = HashMap<(String, u32), HashSet<String>>;
 
 // Cache entry structures
 #[derive(Clone)]
@@ -47,11 +54,19 @@ pub struct CachedSpeakerMeta {
 
 #[derive(Clone)]
 pub struct CachedEpisodeTopicsMap {
-    pub topics_map: HashMap<u32, std::collections::HashSet<String>>,
+    pub topics_map: EpisodeTopicsMap,
     pub loaded_at: SystemTime,
     pub rag_db_path: PathBuf,
 }
 
+#[derive(Clone)]
+pub struct CachedTaxonomy {
+    pub taxonomy: Arc<serde_json::Value>,
+    pub file_path: PathBuf,
+    /// Modification time of the file when it was read; a newer file is reloaded
+    pub mtime: Option<SystemTime>,
+}
+
 #[derive(Clone)]
 pub struct CachedEpisodeFiles {
     pub has_image: bool,
@@ -108,44 +123,73 @@ async fn is_cache_valid(_cached_time: SystemTime, _file_path: &Path) -> bool {
     true
 }
 
+/// A cached RAG index is reused while it was loaded from `path` and, if `max_age` is set,
+/// is not older than that. The age check is a safety net for unreliable file timestamps.
+fn is_rag_entry_fresh(cached: &CachedRagIndex, path: &Path, max_age: Option<Duration>, now: SystemTime) -> bool {
+    if cached.file_path != path {
+        return false;
+    }
+    match max_age {
+        // A clock that went backwards counts as fresh
+        Some(max_age) => now.duration_since(cached.loaded_at).map_or(true, |age| age <= max_age),
+        None => true,
+    }
+}
+
+/// RAG database of `podcast_id` below `db_dir`: its `indices.json` manifest, else its
+/// `rag-embeddings.json(.gz)`, else the shared `rag-embeddings.json(.gz)`
+async fn resolve_rag_db_path(db_dir: &Path, podcast_id: &str) -> Option<PathBuf> {
+    let manifest_path = db_dir.join(podcast_id).join("indices.json");
+    if tokio::fs::metadata(&manifest_path).await.is_ok() {
+        return Some(manifest_path);
+    }
+    for rag_db_path in [db_dir.join(podcast_id).join("rag-embeddings.json"), db_dir.join("rag-embeddings.json")] {
+        let rag_db_path = prefer_gz(rag_db_path).await;
+        if tokio::fs::metadata(&rag_db_path).await.is_ok() {
+            return Some(rag_db_path);
+        }
+    }
+    None
+}
+
 // Cache loading functions
 pub async fn load_rag_index_cached(
     st: &AppState,
     podcast_id: &str,
 ) -> Result<Arc<RagIndex>> {
-    // Determine RAG database path
-    let rag_db_path = PathBuf::from(format!("db/{}/rag-embeddings.json", podcast_id));
-    let rag_db_path = if tokio::fs::metadata(&rag_db_path).await.is_ok() {
-        rag_db_path
-    } else {
-        let fallback = PathBuf::from("db/rag-embeddings.json");
-        if tokio::fs::metadata(&fallback).await.is_ok() {
-            fallback
-        } else {
-            return Err(anyhow!("RAG database not found for podcast '{}'", podcast_id));
-        }
+    let Some(rag_db_path) = resolve_rag_db_path(Path::new("db"), podcast_id).await else {
+        return Err(anyhow!("RAG database not found for podcast '{}'", podcast_id));
     };
 
     // Check cache (moka handles TTL and LRU automatically)
-    // Note: Cache validation is disabled - embeddings never expire once loaded
+    // Note: Cache validation is disabled - embeddings never expire once loaded,
+    // unless RAG_CACHE_MAX_AGE_SECS forces a periodic reload
     if let Some(cached) = st.rag_cache.get(podcast_id).await {
-        if cached.file_path == rag_db_path {
+        if is_rag_entry_fresh(&cached, &rag_db_path, st.cfg.rag_cache_max_age, SystemTime::now()) {
+            st.metrics.rag_cache_hit();
             return Ok(cached.rag.clone());
         }
     }
+    st.metrics.rag_cache_miss();
 
     // Load and cache - use streaming deserialization for large files
     // Open file directly in blocking task to enable true streaming
     let rag_db_path_for_cache = rag_db_path.clone();
     let rag_db_path_for_load = rag_db_path.clone();
     let display_path = rag_db_path_for_load.display().to_string();
+    let dedup = st.cfg.dedup_items;
+    let ann = st.cfg.ann_enabled;
+    let normalize = st.cfg.normalize_on_load;
     let rag = tokio::task::spawn_blocking(move || {
-        RagIndex::load_from_path(&rag_db_path_for_load)
+        let rag = RagIndex::load(&rag_db_path_for_load, dedup)?;
+        let rag = if normalize { rag.with_normalized_embeddings() } else { rag };
+        anyhow::Ok(if ann { rag.with_ann() } else { rag })
     }).await
         .with_context(|| "Failed to spawn blocking task")?
         .with_context(|| format!("Failed to parse RAG database: {}", display_path))?;
     
     let rag = Arc::new(rag);
+    check_index_embedding_model(st, podcast_id, &rag).await;
     
     // Insert into cache
     st.rag_cache.insert(
@@ -160,38 +204,131 @@ pub async fn load_rag_index_cached(
     Ok(rag)
 }
 
+/// Warn (once per podcast and model) when the index was built with a different embedding model
+/// than `EMBEDDING_MODEL`; mismatches are reported by `/api/health/embeddings`.
+/// Returns whether a new warning was logged.
+pub async fn check_index_embedding_model(st: &AppState, podcast_id: &str, rag: &RagIndex) -> bool {
+    let Some(index_model) = rag.embedding_model.as_deref() else {
+        return false;
+    };
+    if index_model == st.cfg.embedding_model {
+        st.embedding_model_mismatches.invalidate(podcast_id).await;
+        return false;
+    }
+    if st.embedding_model_mismatches.get(podcast_id).await.as_deref() == Some(index_model) {
+        return false;
+    }
+    tracing::warn!(
+        "⚠️  Embedding model mismatch for {}: index was built with '{}', but EMBEDDING_MODEL is '{}'. \
+         Query embeddings won't match the index; retrieval quality will suffer.",
+        podcast_id,
+        index_model,
+        st.cfg.embedding_model
+    );
+    st.embedding_model_mismatches.insert(podcast_id.to_string(), index_model.to_string()).await;
+    true
+}
+
+/// Titles embedded per API request when filling the title sidecar
+const TITLE_EMBED_BATCH: usize = 64;
+
+/// Episode title embeddings for `podcast_id`, read from `db/<podcast>/episode-title-embeddings.json`.
+/// Titles missing from the sidecar (new episodes, other embedding model) are embedded and written back.
+pub async fn load_title_embeddings_cached(
+    st: &AppState,
+    podcast_id: &str,
+    rag: &RagIndex,
+) -> Result<Arc<TitleEmbeddings>> {
+    let wanted = TitleEmbeddings::episode_titles(&rag.items);
+    if let Some(cached) = st.title_embedding_cache.get(podcast_id).await {
+        if cached.embedding_model == st.cfg.embedding_model && cached.titles.len() == wanted.len() {
+            return Ok(cached);
+        }
+    }
+
+    let sidecar_path = PathBuf::from(format!("db/{}/episode-title-embeddings.json", podcast_id));
+    let mut titles: TitleEmbeddings = match tokio::fs::read(&sidecar_path).await {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring unreadable {}: {}", sidecar_path.display(), e);
+            TitleEmbeddings::default()
+        }),
+        Err(_) => TitleEmbeddings::default(),
+    };
+
+    let missing = titles.retain_current(&st.cfg.embedding_model, &wanted);
+    if !missing.is_empty() {
+        tracing::info!("Embedding {} episode titles for {}", missing.len(), podcast_id);
+        for chunk in missing.chunks(TITLE_EMBED_BATCH) {
+            let texts: Vec<&str> = chunk.iter().map(|(_, t)| t.as_str()).collect();
+            let vectors = embed_texts(st, &texts).await?;
+            titles.titles.extend(chunk.iter().zip(vectors).map(|((ep, title), embedding)| TitleEmbedding {
+                episode_number: *ep,
+                title: title.clone(),
+                embedding,
+            }));
+        }
+        titles.titles.sort_by_key(|t| t.episode_number);
+        match serde_json::to_vec(&titles) {
+            Ok(bytes) => {
+                if let Err(e) = tokio::fs::write(&sidecar_path, bytes).await {
+                    tracing::warn!("Failed to write {}: {}", sidecar_path.display(), e);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to serialize title embeddings for {}: {}", podcast_id, e),
+        }
+    }
+
+    let titles = Arc::new(titles);
+    st.title_embedding_cache.insert(podcast_id.to_string(), titles.clone()).await;
+    Ok(titles)
+}
+
 pub async fn load_episode_metadata_batch_cached(
     st: &AppState,
     podcast_id: &str,
     episode_numbers: &[u32],
 ) -> Result<HashMap<u32, EpisodeMetadata>> {
     let mut results = HashMap::new();
-    
-    // Create futures for all episodes
-    let futures: Vec<_> = episode_numbers.iter()
-        .map(|&ep_num| load_episode_metadata_cached(st, podcast_id, ep_num))
-        .collect();
-    
-    // Execute all in parallel
-    let metadata_results = future::join_all(futures).await;
-    
-    // Collect results
-    for (ep_num, result) in episode_numbers.iter().zip(metadata_results) {
+
+    // Bounded parallelism so large pages don't open hundreds of files at once
+    let metadata_results = load_bounded(episode_numbers, st.cfg.metadata_concurrency, |ep_num| {
+        load_episode_metadata_cached(st, podcast_id, ep_num)
+    })
+    .await;
+
+    // Collect results (completion order is arbitrary, so keep the episode number with each result)
+    for (ep_num, result) in metadata_results {
         if let Ok(Some(meta)) = result {
-            results.insert(*ep_num, meta);
+            results.insert(ep_num, meta);
         }
     }
-    
+
     Ok(results)
 }
 
+/// Run `load` for every episode with at most `concurrency` loads in flight
+async fn load_bounded<T, F, Fut>(episode_numbers: &[u32], concurrency: usize, load: F) -> Vec<(u32, T)>
+where
+    F: Fn(u32) -> Fut,
+    Fut: Future<Output = T>,
+{
+    stream::iter(episode_numbers.iter().copied())
+        .map(|ep_num| {
+            let fut = load(ep_num);
+            async move { (ep_num, fut.await) }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await
+}
+
 pub async fn load_episode_metadata_cached(
     st: &AppState,
     podcast_id: &str,
     episode_number: u32,
 ) -> Result<Option<EpisodeMetadata>> {
     let cache_key = (podcast_id.to_string(), episode_number);
-    let ep_file = PathBuf::from(format!("podcasts/{}/episodes/{}.json", podcast_id, episode_number));
+    let ep_file = st.cfg.episodes_dir_of(podcast_id).join(format!("{}.json", episode_number));
     
     // Check cache (moka handles TTL and LRU automatically)
     if let Some(cached) = st.episode_metadata_cache.get(&cache_key).await {
@@ -237,7 +374,7 @@ pub async fn load_episode_list_cached(
     st: &AppState,
     podcast_id: &str,
 ) -> Result<Vec<u32>> {
-    let episodes_dir = PathBuf::from(format!("podcasts/{}/episodes", podcast_id));
+    let episodes_dir = st.cfg.episodes_dir_of(podcast_id);
     
     // Check cache (use directory mtime for invalidation)
     if let Some(cached) = st.episode_list_cache.get(podcast_id).await {
@@ -290,7 +427,7 @@ pub async fn load_speaker_profile_cached(
     slug: &str,
 ) -> Result<String> {
     let cache_key = (podcast_id.to_string(), slug.to_string());
-    let profile_path = PathBuf::from(format!("podcasts/{}/speakers/{}.md", podcast_id, slug));
+    let profile_path = st.cfg.speakers_dir_of(podcast_id).join(format!("{}.md", slug));
     
     if tokio::fs::metadata(&profile_path).await.is_err() {
         return Err(anyhow!("Speaker profile not found: {}", slug));
@@ -322,7 +459,7 @@ pub async fn load_speakers_index_cached(
     st: &AppState,
     podcast_id: &str,
 ) -> Result<Vec<SpeakerInfo>> {
-    let speakers_dir = PathBuf::from(format!("podcasts/{}/speakers", podcast_id));
+    let speakers_dir = st.cfg.speakers_dir_of(podcast_id);
     let index_path = speakers_dir.join("index.json");
     
     if tokio::fs::metadata(&index_path).await.is_err() {
@@ -357,13 +494,45 @@ pub async fn load_speakers_index_cached(
     Ok(speakers)
 }
 
+/// Topic taxonomy JSON of a podcast, reloaded when the file at `path` changes (mtime)
+pub async fn load_taxonomy_cached(
+    st: &AppState,
+    podcast_id: &str,
+    path: &Path,
+) -> Result<Arc<serde_json::Value>> {
+    let mtime = get_file_mtime(path).await;
+    if let Some(cached) = st.taxonomy_cache.get(podcast_id).await {
+        if cached.file_path == path && mtime.is_some() && cached.mtime == mtime {
+            return Ok(cached.taxonomy);
+        }
+    }
+
+    let bytes = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let taxonomy: serde_json::Value = serde_json::from_slice(&bytes)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let taxonomy = Arc::new(taxonomy);
+
+    st.taxonomy_cache.insert(
+        podcast_id.to_string(),
+        CachedTaxonomy {
+            taxonomy: taxonomy.clone(),
+            file_path: path.to_path_buf(),
+            mtime,
+        }
+    ).await;
+
+    Ok(taxonomy)
+}
+
 pub async fn load_speaker_meta_cached(
     st: &AppState,
     podcast_id: &str,
     slug: &str,
 ) -> Result<Option<SpeakerMeta>> {
     let cache_key = (podcast_id.to_string(), slug.to_string());
-    let meta_path = PathBuf::from(format!("podcasts/{}/speakers/{}-meta.json", podcast_id, slug));
+    let meta_path = st.cfg.speakers_dir_of(podcast_id).join(format!("{}-meta.json", slug));
     
     if tokio::fs::metadata(&meta_path).await.is_err() {
         return Ok(None);
@@ -406,39 +575,23 @@ pub async fn load_speaker_meta_cached(
 pub async fn load_episode_topics_map_cached(
     st: &AppState,
     podcast_id: &str,
-) -> Result<HashMap<u32, std::collections::HashSet<String>>> {
-    // Determine RAG database path
-    let rag_db_path = PathBuf::from(format!("db/{}/rag-embeddings.json", podcast_id));
-    let rag_db_path = if tokio::fs::metadata(&rag_db_path).await.is_ok() {
-        rag_db_path
-    } else {
-        PathBuf::from("db/rag-embeddings.json")
-    };
-
-    if tokio::fs::metadata(&rag_db_path).await.is_err() {
+) -> Result<EpisodeTopicsMap> {
+    // Same database (or manifest) as load_rag_index_cached; no database means no topics
+    let Some(rag_db_path) = resolve_rag_db_path(Path::new("db"), podcast_id).await else {
         return Ok(HashMap::new());
-    }
+    };
 
     // Check cache (moka handles TTL and LRU automatically)
     // Note: Cache validation is disabled - embeddings never expire once loaded
     if let Some(cached) = st.episode_topics_map_cache.get(podcast_id).await {
-        if cached.rag_db_path == rag_db_path {
+        if cached.rag_db_path == rag_db_path && is_cache_valid(cached.loaded_at, &rag_db_path).await {
             return Ok(cached.topics_map.clone());
         }
     }
 
     // Load RAG database and build topics map
     let rag = load_rag_index_cached(st, podcast_id).await?;
-    let mut topics_map: HashMap<u32, std::collections::HashSet<String>> = HashMap::new();
-    
-    for item in &rag.items {
-        if let Some(topic) = &item.topic {
-            topics_map
-                .entry(item.episode_number)
-                .or_insert_with(|| std::collections::HashSet::new())
-                .insert(topic.clone());
-        }
-    }
+    let topics_map = build_episode_topics_map(podcast_id, &rag.items, st.cfg.max_topics_per_episode);
 
     // Cache result
     st.episode_topics_map_cache.insert(
@@ -453,20 +606,59 @@ pub async fn load_episode_topics_map_cached(
     Ok(topics_map)
 }
 
+/// Collect the distinct topics of each episode in `items`, keyed by (podcast_id, episode_number).
+/// With `max_topics`, only that many topics per episode are kept: those covering the most
+/// segments, then the most seconds.
+pub fn build_episode_topics_map(podcast_id: &str, items: &[RagItem], max_topics: Option<usize>) -> EpisodeTopicsMap {
+    // (segments, seconds) per topic and episode
+    let mut weights: HashMap<u32, HashMap<&str, (usize, f64)>> = HashMap::new();
+    for item in items {
+        if let Some(topic) = &item.topic {
+            let w = weights
+                .entry(item.episode_number)
+                .or_default()
+                .entry(topic.as_str())
+                .or_insert((0, 0.0));
+            w.0 += 1;
+            w.1 += (item.end_sec - item.start_sec).max(0.0);
+        }
+    }
+
+    weights
+        .into_iter()
+        .map(|(ep_num, topics)| {
+            let mut topics: Vec<(&str, (usize, f64))> = topics.into_iter().collect();
+            if let Some(max) = max_topics {
+                topics.sort_by(|a, b| {
+                    b.1 .0
+                        .cmp(&a.1 .0)
+                        .then(b.1 .1.total_cmp(&a.1 .1))
+                        .then(a.0.cmp(b.0))
+                });
+                topics.truncate(max);
+            }
+            let topics: HashSet<String> = topics.into_iter().map(|(t, _)| t.to_string()).collect();
+            ((podcast_id.to_string(), ep_num), topics)
+        })
+        .collect()
+}
+
 pub async fn check_episode_files_cached(
     st: &AppState,
     podcast_id: &str,
     episode_number: u32,
 ) -> Result<(bool, bool)> {
     let cache_key = (podcast_id.to_string(), episode_number);
+    let episodes_dir = st.cfg.episodes_dir_of(podcast_id);
     
     // Check cache
     if let Some(cached) = st.episode_files_cache.get(&cache_key).await {
-        return Ok((cached.has_image, cached.has_transcript));
+        if is_cache_valid(cached.loaded_at, &episodes_dir).await {
+            return Ok((cached.has_image, cached.has_transcript));
+        }
     }
     
     // Check files
-    let episodes_dir = PathBuf::from(format!("podcasts/{}/episodes", podcast_id));
     
     // Check for image (try common extensions)
     let mut has_image = false;
@@ -479,7 +671,7 @@ pub async fn check_episode_files_cached(
     }
     
     // Check for transcript
-    let transcript_path = episodes_dir.join(format!("{}-ts.json", episode_number));
+    let transcript_path = prefer_gz(episodes_dir.join(format!("{}-ts.json", episode_number))).await;
     let has_transcript = tokio::fs::metadata(&transcript_path).await.is_ok();
     
     // Cache result
@@ -547,7 +739,158 @@ pub async fn load_speakers_index(speakers_dir: &Path) -> Result<Vec<SpeakerInfo>
         let profile_path = speakers_dir.join(format!("{}.md", speaker.slug));
         speaker.has_profile = tokio::fs::metadata(&profile_path).await.is_ok();
     }
+    sort_speakers(&mut speakers);
     
     Ok(speakers)
 }
 
+/// Stable listing order: most utterances first, then by name (slug breaks remaining ties)
+fn sort_speakers(speakers: &mut [SpeakerInfo]) {
+    speakers.sort_by(|a, b| {
+        b.utterances_count
+            .cmp(&a.utterances_count)
+            .then_with(|| a.speaker.cmp(&b.speaker))
+            .then_with(|| a.slug.cmp(&b.slug))
+    });
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(episode_number: u32, topic: &str) -> RagItem {
+        RagItem {
+            topic: Some(topic.to_string()),
+            ..RagItem::test_item(episode_number, 0.0)
+        }
+    }
+
+    #[test]
+    fn test_topics_map_keeps_podcasts_apart() {
+        // Both podcasts have an episode 42 with different topics
+        let mut merged = build_episode_topics_map("freakshow", &[item(42, "Apple"), item(42, "Podcasting")], None);
+        merged.extend(build_episode_topics_map("lnp", &[item(42, "Netzpolitik")], None));
+
+        let freakshow = &merged[&("freakshow".to_string(), 42)];
+        let lnp = &merged[&("lnp".to_string(), 42)];
+
+        assert_eq!(freakshow.len(), 2);
+        assert!(freakshow.contains("Apple") && freakshow.contains("Podcasting"));
+        assert!(!freakshow.contains("Netzpolitik"));
+        assert_eq!(lnp.len(), 1);
+        assert!(lnp.contains("Netzpolitik"));
+    }
+
+    #[test]
+    fn test_speakers_sorted_by_utterances_then_name() {
+        let speaker = |name: &str, utterances_count: u32| SpeakerInfo {
+            speaker: name.to_string(),
+            slug: name.to_lowercase(),
+            episodes_count: 1,
+            utterances_count,
+            total_words: 0,
+            has_profile: false,
+            image: None,
+        };
+        let mut speakers = vec![speaker("Gast", 12), speaker("Denis", 900), speaker("Tim", 5000), speaker("Clemens", 900)];
+        sort_speakers(&mut speakers);
+        let names: Vec<&str> = speakers.iter().map(|s| s.speaker.as_str()).collect();
+        assert_eq!(names, vec!["Tim", "Clemens", "Denis", "Gast"]);
+    }
+
+    #[test]
+    fn test_rag_entry_older_than_max_age_is_reloaded() {
+        let path = PathBuf::from("db/freakshow/rag-embeddings.json");
+        let now = SystemTime::now();
+        // Same source path (the file looks unchanged), but loaded two hours ago
+        let cached = CachedRagIndex {
+            rag: Arc::new(RagIndex::test_index(Vec::new())),
+            loaded_at: now - Duration::from_secs(7200),
+            file_path: path.clone(),
+        };
+
+        assert!(is_rag_entry_fresh(&cached, &path, None, now));
+        assert!(is_rag_entry_fresh(&cached, &path, Some(Duration::from_secs(3 * 3600)), now));
+        assert!(!is_rag_entry_fresh(&cached, &path, Some(Duration::from_secs(3600)), now));
+        assert!(!is_rag_entry_fresh(&cached, Path::new("db/rag-embeddings.json"), None, now));
+    }
+
+    #[tokio::test]
+    async fn test_manifest_only_podcast_resolves_to_its_manifest() {
+        let db = std::env::temp_dir().join(format!("rag-resolve-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&db);
+        std::fs::create_dir_all(db.join("lnp")).unwrap();
+        std::fs::create_dir_all(db.join("freakshow")).unwrap();
+        assert_eq!(resolve_rag_db_path(&db, "lnp").await, None);
+
+        // lnp has no rag-embeddings.json of its own, only the manifest
+        std::fs::write(db.join("lnp/indices.json"), r#"{ "indices": ["../freakshow/rag-embeddings.json"] }"#).unwrap();
+        std::fs::write(db.join("freakshow/rag-embeddings.json"), "[]").unwrap();
+        assert_eq!(resolve_rag_db_path(&db, "lnp").await, Some(db.join("lnp/indices.json")));
+        assert_eq!(
+            resolve_rag_db_path(&db, "freakshow").await,
+            Some(db.join("freakshow/rag-embeddings.json"))
+        );
+    }
+
+    #[test]
+    fn test_topics_per_episode_capped_by_frequency() {
+        let mut items: Vec<RagItem> = (0..20).map(|i| item(7, &format!("Thema {i}"))).collect();
+        items.extend([item(7, "Apple"), item(7, "Apple"), item(7, "Apple"), item(7, "Podcasting"), item(7, "Podcasting")]);
+
+        let capped = build_episode_topics_map("freakshow", &items, Some(3));
+        let topics = &capped[&("freakshow".to_string(), 7)];
+        assert_eq!(topics.len(), 3);
+        assert!(topics.contains("Apple") && topics.contains("Podcasting"));
+
+        let all = build_episode_topics_map("freakshow", &items, None);
+        assert_eq!(all[&("freakshow".to_string(), 7)].len(), 22);
+    }
+
+    #[tokio::test]
+    async fn test_embedding_model_mismatch_warns_once() {
+        let st = AppState::for_tests(crate::config::AppConfig::for_tests());
+        let index = |model: &str| {
+            let mut rag = RagIndex::test_index(Vec::new());
+            rag.embedding_model = Some(model.to_string());
+            rag
+        };
+
+        assert!(!check_index_embedding_model(&st, "freakshow", &index("test-embedding")).await);
+        assert!(st.embedding_model_mismatches.get("freakshow").await.is_none());
+
+        assert!(check_index_embedding_model(&st, "lnp", &index("text-embedding-3-large")).await);
+        assert_eq!(st.embedding_model_mismatches.get("lnp").await.as_deref(), Some("text-embedding-3-large"));
+        // Reloading the same index doesn't warn again
+        assert!(!check_index_embedding_model(&st, "lnp", &index("text-embedding-3-large")).await);
+    }
+
+    #[tokio::test]
+    async fn test_load_bounded_caps_concurrency() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        const LIMIT: usize = 4;
+        // Semaphore with LIMIT permits: a load that can't get a permit immediately means the cap was exceeded
+        let permits = Arc::new(tokio::sync::Semaphore::new(LIMIT));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let episodes: Vec<u32> = (1..=50).collect();
+
+        let results = load_bounded(&episodes, LIMIT, |ep_num| {
+            let permits = permits.clone();
+            let max_in_flight = max_in_flight.clone();
+            async move {
+                let _permit = permits.try_acquire().expect("more than LIMIT loads in flight");
+                max_in_flight.fetch_max(LIMIT - permits.available_permits(), Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+                ep_num * 10
+            }
+        })
+        .await;
+
+        assert_eq!(results.len(), episodes.len());
+        assert!(results.iter().all(|&(ep_num, v)| v == ep_num * 10));
+        let max = max_in_flight.load(Ordering::SeqCst);
+        assert!(max <= LIMIT && max > 1, "max in flight: {max}");
+    }
+}
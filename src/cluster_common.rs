@@ -0,0 +1,105 @@
+//! Pieces shared by the cluster-topics binaries (V1 and V2)
+
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Topic dropped before clustering, written to filtered-topics.json for tuning the filters
+#[derive(Debug, Clone, Serialize)]
+pub struct FilteredTopic {
+    pub topic: String,
+    pub reason: FilterReason,
+    /// Fraction of all episodes the topic appears in (0.0-1.0)
+    #[serde(rename = "episodeShare")]
+    pub episode_share: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterReason {
+    IntroOutro,
+    Ubiquitous,
+}
+
+/// Why a topic is filtered out before clustering, if it is: intro/outro by name, otherwise
+/// ubiquitous when it appears in at least `share_threshold` of all episodes
+pub fn filter_reason(topic: &str, episode_share: f64, share_threshold: f64) -> Option<FilterReason> {
+    let topic_lc = topic.to_lowercase();
+    if topic_lc.contains("intro") || topic_lc.contains("outro") {
+        Some(FilterReason::IntroOutro)
+    } else if episode_share >= share_threshold {
+        Some(FilterReason::Ubiquitous)
+    } else {
+        None
+    }
+}
+
+/// Build a cluster id from its name. Default keeps German umlauts/ß as-is; with `ascii`
+/// they are transliterated (ä -> ae, ß -> ss) and any other non-ASCII character becomes a dash.
+pub fn cluster_id_slug(name: &str, ascii: bool) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.to_lowercase().chars() {
+        match c {
+            'ä' | 'ö' | 'ü' | 'ß' if ascii => slug.push_str(match c {
+                'ä' => "ae",
+                'ö' => "oe",
+                'ü' => "ue",
+                _ => "ss",
+            }),
+            'ä' | 'ö' | 'ü' | 'ß' => slug.push(c),
+            c if c.is_ascii_alphanumeric() || (!ascii && c.is_alphanumeric()) => slug.push(c),
+            _ => slug.push('-'),
+        }
+    }
+    slug.split('-')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Why a variant could not be loaded; `main` reports it and exits
+#[derive(Debug)]
+pub enum VariantError {
+    /// The variants file has no entry with this name
+    NotFound(String),
+    FileMissing(PathBuf),
+    /// The variants file could not be read or is not valid JSON
+    Parse(String),
+}
+
+impl std::fmt::Display for VariantError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VariantError::NotFound(name) => write!(f, "Variant '{}' not found in variants.json", name),
+            VariantError::FileMissing(path) => write!(f, "{} not found", path.display()),
+            VariantError::Parse(msg) => write!(f, "variants.json is invalid: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for VariantError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cluster_id_slug() {
+        assert_eq!(cluster_id_slug("Größe & Öl", true), "groesse-oel");
+        assert_eq!(cluster_id_slug("Größe & Öl", false), "größe-öl");
+        assert_eq!(cluster_id_slug("Café Ελλάδα: KI", true), "caf-ki");
+        assert_eq!(cluster_id_slug("Apple -- Vision Pro", false), "apple-vision-pro");
+    }
+
+    #[test]
+    fn test_filter_reason_and_report_format() {
+        assert_eq!(filter_reason("Intro & Begrüßung", 0.99, 0.9), Some(FilterReason::IntroOutro));
+        assert_eq!(filter_reason("Feedback", 0.95, 0.9), Some(FilterReason::Ubiquitous));
+        assert_eq!(filter_reason("Universal Control", 0.1, 0.9), None);
+
+        let removed = FilteredTopic { topic: "Outro".to_string(), reason: FilterReason::IntroOutro, episode_share: 0.5 };
+        assert_eq!(
+            serde_json::to_value(&removed).unwrap(),
+            serde_json::json!({ "topic": "Outro", "reason": "intro_outro", "episodeShare": 0.5 })
+        );
+    }
+}
@@ -1,4 +1,7 @@
+mod cluster_common;
+
 use clap::Parser;
+use cluster_common::{cluster_id_slug, filter_reason, FilterReason, FilteredTopic, VariantError};
 use indicatif::{ProgressBar, ProgressStyle};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -102,6 +105,9 @@ struct TopicClusteringSettings {
     #[serde(rename = "useLLMNaming")]
     use_llm_naming: Option<bool>,
     model: Option<String>,
+    /// Transliterate umlauts and drop other non-ASCII characters in cluster ids
+    #[serde(rename = "asciiSlugs")]
+    ascii_slugs: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -300,12 +306,12 @@ fn compute_weighted_centroid(
     for &idx in items {
         let w = weights[idx];
         total_weight += w;
-        for d in 0..dim {
-            centroid[d] += embeddings[idx][d] * w;
+        for (c, e) in centroid.iter_mut().zip(&embeddings[idx]) {
+            *c += e * w;
         }
     }
-    for d in 0..dim {
-        centroid[d] /= total_weight;
+    for c in centroid.iter_mut() {
+        *c /= total_weight;
     }
     (centroid, total_weight)
 }
@@ -442,12 +448,12 @@ fn hierarchical_clustering(
         } else {
             let mut centroid = vec![0.0; embeddings[0].len()];
             for &idx in &new_items {
-                for d in 0..embeddings[0].len() {
-                    centroid[d] += embeddings[idx][d];
+                for (c, e) in centroid.iter_mut().zip(&embeddings[idx]) {
+                    *c += e;
                 }
             }
-            for d in 0..embeddings[0].len() {
-                centroid[d] /= new_items.len() as f64;
+            for c in centroid.iter_mut() {
+                *c /= new_items.len() as f64;
             }
             (centroid, new_items.len() as f64)
         };
@@ -711,21 +717,27 @@ Regeln:
 }
 
 /// Load variant settings from variants.json
-fn load_variant_settings(
+fn load_variant_settings(variant_name: &str) -> Result<(String, VariantSettingsJson), VariantError> {
+    load_variant_settings_from(&PathBuf::from("variants.json"), variant_name)
+}
+
+fn load_variant_settings_from(
+    variants_path: &PathBuf,
     variant_name: &str,
-) -> Result<(String, VariantSettingsJson), Box<dyn std::error::Error>> {
-    let variants_path = PathBuf::from("variants.json");
+) -> Result<(String, VariantSettingsJson), VariantError> {
     if !variants_path.exists() {
-        return Err("variants.json not found".into());
+        return Err(VariantError::FileMissing(variants_path.clone()));
     }
 
-    let variants_content = fs::read_to_string(&variants_path)?;
-    let variants_config: VariantsConfig = serde_json::from_str(&variants_content)?;
+    let variants_content =
+        fs::read_to_string(variants_path).map_err(|e| VariantError::Parse(e.to_string()))?;
+    let variants_config: VariantsConfig =
+        serde_json::from_str(&variants_content).map_err(|e| VariantError::Parse(e.to_string()))?;
 
     let variant = variants_config
         .variants
         .get(variant_name)
-        .ok_or_else(|| format!("Variant '{}' not found in variants.json", variant_name))?;
+        .ok_or_else(|| VariantError::NotFound(variant_name.to_string()))?;
 
     Ok((variant.name.clone(), variant.settings.clone()))
 }
@@ -837,6 +849,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .unwrap_or(false),
         )
     };
+    let ascii_slugs = settings
+        .topic_clustering
+        .as_ref()
+        .and_then(|s| s.ascii_slugs)
+        .unwrap_or(false);
     println!("📂 Lade Embeddings-Datenbank (Podcast: {})...", args.podcast);
     let db_path = PathBuf::from(format!("db/{}/topic-embeddings.json", args.podcast));
     if !db_path.exists() {
@@ -873,19 +890,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut filtered_topics: Vec<TopicWithEmbedding> = Vec::with_capacity(db.topics.len());
     let mut skipped_by_name = 0usize;
     let mut skipped_by_share = 0usize;
+    let mut removed_topics: Vec<FilteredTopic> = Vec::new();
 
     for t in db.topics.iter().cloned() {
-        let topic_lc = t.topic.to_lowercase();
-        let is_intro_outro = topic_lc.contains("intro") || topic_lc.contains("outro");
-
-        if is_intro_outro {
-            skipped_by_name += 1;
-            continue;
-        }
-
         let share = (t.episodes.len() as f64) / (total_episodes as f64);
-        if share >= ubiquitous_share_threshold {
-            skipped_by_share += 1;
+        if let Some(reason) = filter_reason(&t.topic, share, ubiquitous_share_threshold) {
+            match reason {
+                FilterReason::IntroOutro => skipped_by_name += 1,
+                FilterReason::Ubiquitous => skipped_by_share += 1,
+            }
+            removed_topics.push(FilteredTopic { topic: t.topic, reason, episode_share: share });
             continue;
         }
 
@@ -960,11 +974,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .collect();
         let name = if cluster.is_outlier || cluster.max_merge_distance > outlier_threshold {
             outlier_count += 1;
-            pb.set_message(format!("\"Sonstiges\" (Outlier)"));
+            pb.set_message("\"Sonstiges\" (Outlier)".to_string());
             "Sonstiges".to_string()
         } else if use_llm_naming && cluster_topics.len() > 1 {
             let mut sorted_topics = cluster_topics.clone();
-            sorted_topics.sort_by(|a, b| b.episodes.len().cmp(&a.episodes.len()));
+            sorted_topics.sort_by_key(|t| std::cmp::Reverse(t.episodes.len()));
             let top_topics: Vec<String> = sorted_topics
                 .iter()
                 .take(10)
@@ -1004,21 +1018,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
         let mut episodes: Vec<u32> = all_episodes.into_iter().collect();
         episodes.sort_unstable();
-        let id = name
-            .to_lowercase()
-            .chars()
-            .map(|c| {
-                if c.is_alphanumeric() || c == 'ä' || c == 'ö' || c == 'ü' || c == 'ß' {
-                    c
-                } else {
-                    '-'
-                }
-            })
-            .collect::<String>()
-            .split('-')
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<_>>()
-            .join("-");
+        let id = cluster_id_slug(&name, ascii_slugs);
         named_clusters.push(NamedCluster {
             id,
             name,
@@ -1039,7 +1039,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
     pb.finish_with_message("Done");
     println!("\n   ℹ️  {} Outlier-Cluster gefunden\n", outlier_count);
-    named_clusters.sort_by(|a, b| b.episode_count.cmp(&a.episode_count));
+    named_clusters.sort_by_key(|c| std::cmp::Reverse(c.episode_count));
     let taxonomy_file = PathBuf::from("topic-taxonomy.json");
     let outliers: Vec<_> = named_clusters.iter().filter(|c| c.is_outlier).collect();
     let result = TaxonomyResult {
@@ -1113,6 +1113,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let detailed_json = serde_json::to_string_pretty(&detailed_mapping)?;
     fs::write(&detailed_file, detailed_json)?;
     println!("✅ Detailed Topic-Mapping gespeichert: {:?}", detailed_file);
+    let filtered_file = PathBuf::from("filtered-topics.json");
+    fs::write(&filtered_file, serde_json::to_string_pretty(&removed_topics)?)?;
+    println!("✅ Gefilterte Topics gespeichert: {:?} ({})", filtered_file, removed_topics.len());
     println!("\n📋 Top 15 Cluster:");
     for (i, c) in named_clusters.iter().take(15).enumerate() {
         let outlier_tag = if c.is_outlier { " [Outlier]" } else { "" };
@@ -1138,3 +1141,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("   Laufzeit: {:.2}s", elapsed.as_secs_f64());
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_variant_is_not_found() {
+        let dir = std::env::temp_dir().join(format!("{}-variants-test-{}", env!("CARGO_BIN_NAME"), std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("variants.json");
+        fs::write(&path, r#"{ "variants": { "fein": { "version": "v1", "name": "Fein", "settings": {} } } }"#).unwrap();
+
+        assert_eq!(load_variant_settings_from(&path, "fein").unwrap().0, "Fein");
+        assert!(matches!(
+            load_variant_settings_from(&path, "gibt-es-nicht"),
+            Err(VariantError::NotFound(name)) if name == "gibt-es-nicht"
+        ));
+        assert!(matches!(
+            load_variant_settings_from(&dir.join("missing.json"), "fein"),
+            Err(VariantError::FileMissing(_))
+        ));
+        fs::write(&path, "{ not json").unwrap();
+        assert!(matches!(load_variant_settings_from(&path, "fein"), Err(VariantError::Parse(_))));
+    }
+}
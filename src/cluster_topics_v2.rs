@@ -6,7 +6,12 @@
 //! - Automatic optimal cluster count detection
 //! - Better outlier handling
 
+mod cluster_common;
+#[cfg(test)]
+mod test_support;
+
 use clap::Parser;
+use cluster_common::{cluster_id_slug, filter_reason, FilterReason, FilteredTopic, VariantError};
 use indicatif::{ProgressBar, ProgressStyle};
 use ndarray::{Array1, Array2, Axis};
 use ordered_float::OrderedFloat;
@@ -15,6 +20,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering as AtomicOrdering};
 use std::time::Instant;
 
 // ============================================================================
@@ -31,6 +37,32 @@ struct Args {
     /// Podcast ID for podcast-specific database paths
     #[arg(long, default_value = "freakshow")]
     podcast: String,
+    /// Ignore `minClusterRelevanceSec` and write every cluster to the main list
+    #[arg(long)]
+    full_output: bool,
+    /// After clustering, explain why this topic landed in its cluster
+    #[arg(long)]
+    explain_topic: Option<String>,
+    /// Write the raw HDBSCAN cluster tree to this JSON file
+    #[arg(long)]
+    hierarchy: Option<PathBuf>,
+    /// Seed for the dimensionality reduction (overrides the variant's `seed`; default 42)
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Use all pairwise distances (exact MST) instead of the k-nearest-neighbor graph;
+    /// O(n²) memory, so only for small topic sets (limited by `maxDenseTopics`)
+    #[arg(long)]
+    dense: bool,
+    /// Earlier topic-taxonomy.json whose names are reused for matching clusters (only
+    /// genuinely new clusters are named by the LLM)
+    #[arg(long)]
+    reuse_names: Option<PathBuf>,
+    /// Minimum centroid cosine similarity for `--reuse-names`
+    #[arg(long, default_value_t = DEFAULT_REUSE_NAME_THRESHOLD)]
+    reuse_threshold: f64,
+    /// Also project the topic embeddings to 2D and write topic-coords.json for the map view
+    #[arg(long)]
+    project_2d: bool,
 }
 
 // ============================================================================
@@ -58,6 +90,11 @@ struct VariantSettingsJson {
     min_samples: Option<usize>,
     #[serde(rename = "reducedDimensions")]
     reduced_dimensions: Option<usize>,
+    /// How to reach `reducedDimensions`: "random" (default, fast), "pca" (slower, better) or "none".
+    #[serde(rename = "reductionMethod")]
+    reduction_method: Option<ReductionMethod>,
+    /// RNG seed for the dimensionality reduction; `--seed` takes precedence.
+    seed: Option<u64>,
     #[serde(rename = "outlierThreshold")]
     outlier_threshold: Option<f64>,
     /// Default per-topic duration (seconds) used as relevance when no duration is available.
@@ -67,7 +104,70 @@ struct VariantSettingsJson {
     use_relevance_weighting: Option<bool>,
     #[serde(rename = "useLLMNaming")]
     use_llm_naming: Option<bool>,
+    /// Whether outlier clusters count towards the total used for `relevanceShare`.
+    #[serde(rename = "relevanceShareIncludeOutliers")]
+    relevance_share_include_outliers: Option<bool>,
+    /// Collapse single-topic clusters (see `collapseSingletonsMode`).
+    #[serde(rename = "collapseSingletons")]
+    collapse_singletons: Option<bool>,
+    /// "misc" (default): group all singletons into one bucket; "outlier": mark each singleton as outlier.
+    #[serde(rename = "collapseSingletonsMode")]
+    collapse_singletons_mode: Option<SingletonMode>,
+    /// Refuse `--dense` runs over more topics than this (all pairwise distances are O(n²) memory).
+    #[serde(rename = "maxDenseTopics")]
+    max_dense_topics: Option<usize>,
+    /// Clusters below this relevance (seconds) are left out of the main `clusters` list.
+    #[serde(rename = "minClusterRelevanceSec")]
+    min_cluster_relevance_sec: Option<u64>,
+    /// Move filtered clusters into `minorClusters` instead of dropping them.
+    #[serde(rename = "keepMinorClusters")]
+    keep_minor_clusters: Option<bool>,
+    /// Leading phrases stripped from topics before naming (replaces the defaults).
+    #[serde(rename = "topicNamePrefixes")]
+    topic_name_prefixes: Option<Vec<String>>,
+    /// Transliterate umlauts and drop other non-ASCII characters in cluster ids.
+    #[serde(rename = "asciiSlugs")]
+    ascii_slugs: Option<bool>,
+    /// Write outlier clusters to the taxonomy (default true); they're always counted in statistics.
+    #[serde(rename = "includeOutliers")]
+    include_outliers: Option<bool>,
+    /// Total LLM naming retries for the whole run; once used up, remaining clusters are named heuristically.
+    #[serde(rename = "namingRetryBudget")]
+    naming_retry_budget: Option<u32>,
+    /// Wall-clock budget (seconds) for LLM naming; after it, remaining clusters are named heuristically.
+    #[serde(rename = "namingTimeBudgetSec")]
+    naming_time_budget_sec: Option<u64>,
+}
+
+impl VariantSettingsJson {
+    /// Fill every setting the variant leaves open from settings.json's `topicClustering`
+    fn or_base(mut self, base: Option<&TopicClusteringSettings>) -> Self {
+        let Some(base) = base else {
+            return self;
+        };
+        self.min_cluster_size = self.min_cluster_size.or(base.min_cluster_size);
+        self.min_samples = self.min_samples.or(base.min_samples);
+        self.reduced_dimensions = self.reduced_dimensions.or(base.reduced_dimensions);
+        self.reduction_method = self.reduction_method.or(base.reduction_method);
+        self.outlier_threshold = self.outlier_threshold.or(base.outlier_threshold);
+        self.use_relevance_weighting = self.use_relevance_weighting.or(base.use_relevance_weighting);
+        self.use_llm_naming = self.use_llm_naming.or(base.use_llm_naming);
+        self.relevance_share_include_outliers =
+            self.relevance_share_include_outliers.or(base.relevance_share_include_outliers);
+        self.collapse_singletons = self.collapse_singletons.or(base.collapse_singletons);
+        self.collapse_singletons_mode = self.collapse_singletons_mode.or(base.collapse_singletons_mode);
+        self.max_dense_topics = self.max_dense_topics.or(base.max_dense_topics);
+        self.min_cluster_relevance_sec = self.min_cluster_relevance_sec.or(base.min_cluster_relevance_sec);
+        self.keep_minor_clusters = self.keep_minor_clusters.or(base.keep_minor_clusters);
+        self.topic_name_prefixes = self.topic_name_prefixes.or_else(|| base.topic_name_prefixes.clone());
+        self.ascii_slugs = self.ascii_slugs.or(base.ascii_slugs);
+        self.include_outliers = self.include_outliers.or(base.include_outliers);
+        self.naming_retry_budget = self.naming_retry_budget.or(base.naming_retry_budget);
+        self.naming_time_budget_sec = self.naming_time_budget_sec.or(base.naming_time_budget_sec);
+        self
+    }
 }
+
 use rand::SeedableRng;
 use rand_distr::{Distribution, Normal};
 
@@ -127,8 +227,69 @@ struct TopicClusteringSettings {
     min_cluster_size: Option<usize>,
     #[serde(rename = "reducedDimensions")]
     reduced_dimensions: Option<usize>,
+    #[serde(rename = "reductionMethod")]
+    reduction_method: Option<ReductionMethod>,
     #[serde(rename = "minSamples")]
     min_samples: Option<usize>,
+    #[serde(rename = "relevanceShareIncludeOutliers")]
+    relevance_share_include_outliers: Option<bool>,
+    #[serde(rename = "collapseSingletons")]
+    collapse_singletons: Option<bool>,
+    #[serde(rename = "collapseSingletonsMode")]
+    collapse_singletons_mode: Option<SingletonMode>,
+    /// Refuse `--dense` runs over more topics than this (all pairwise distances are O(n²) memory).
+    #[serde(rename = "maxDenseTopics")]
+    max_dense_topics: Option<usize>,
+    /// Clusters below this relevance (seconds) are left out of the main `clusters` list.
+    #[serde(rename = "minClusterRelevanceSec")]
+    min_cluster_relevance_sec: Option<u64>,
+    /// Move filtered clusters into `minorClusters` instead of dropping them.
+    #[serde(rename = "keepMinorClusters")]
+    keep_minor_clusters: Option<bool>,
+    /// Leading phrases stripped from topics before naming (replaces the defaults).
+    #[serde(rename = "topicNamePrefixes")]
+    topic_name_prefixes: Option<Vec<String>>,
+    /// Transliterate umlauts and drop other non-ASCII characters in cluster ids.
+    #[serde(rename = "asciiSlugs")]
+    ascii_slugs: Option<bool>,
+    /// Write outlier clusters to the taxonomy (default true); they're always counted in statistics.
+    #[serde(rename = "includeOutliers")]
+    include_outliers: Option<bool>,
+    /// Total LLM naming retries for the whole run; once used up, remaining clusters are named heuristically.
+    #[serde(rename = "namingRetryBudget")]
+    naming_retry_budget: Option<u32>,
+    /// Wall-clock budget (seconds) for LLM naming; after it, remaining clusters are named heuristically.
+    #[serde(rename = "namingTimeBudgetSec")]
+    naming_time_budget_sec: Option<u64>,
+}
+
+/// How `collapseSingletons` treats clusters with exactly one topic
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+enum SingletonMode {
+    #[default]
+    Misc,
+    Outlier,
+}
+
+/// Dimensionality reduction before HDBSCAN (`reductionMethod`)
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+enum ReductionMethod {
+    Pca,
+    #[default]
+    Random,
+    None,
+}
+
+impl ReductionMethod {
+    fn label(self) -> &'static str {
+        match self {
+            ReductionMethod::Pca => "PCA",
+            ReductionMethod::Random => "Random Projection",
+            ReductionMethod::None => "keine",
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -182,10 +343,27 @@ struct NamedCluster {
     /// Sum of topic relevance (seconds) within the cluster.
     #[serde(rename = "relevanceSec")]
     relevance_sec: u64,
+    /// Mean pairwise cosine similarity between the cluster's topics.
+    #[serde(rename = "intraClusterCohesion")]
+    intra_cluster_cohesion: f64,
+    #[serde(rename = "nameSource")]
+    name_source: NameSource,
     topics: Vec<ClusterTopic>,
     episodes: Vec<u32>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum NameSource {
+    Llm,
+    Heuristic,
+    Outlier,
+    /// Taken over from the `--reuse-names` taxonomy
+    Reused,
+    /// The bucket that `collapseSingletons` in `misc` mode gathers single-topic clusters into
+    Misc,
+}
+
 #[derive(Debug, Clone, Serialize)]
 struct ClusterTopic {
     topic: String,
@@ -223,6 +401,9 @@ struct TaxonomyResult {
     settings: ClusterSettings,
     statistics: Statistics,
     clusters: Vec<TaxonomyCluster>,
+    /// Clusters below `minClusterRelevanceSec` (only with `keepMinorClusters`).
+    #[serde(rename = "minorClusters", skip_serializing_if = "Vec::is_empty")]
+    minor_clusters: Vec<TaxonomyCluster>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -234,6 +415,8 @@ struct ClusterSettings {
     linkage_method: String,
     #[serde(rename = "useRelevanceWeighting")]
     use_relevance_weighting: bool,
+    /// Seed the dimensionality reduction ran with, to reproduce the run
+    seed: u64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -244,6 +427,9 @@ struct Statistics {
     outlier_count: usize,
     #[serde(rename = "outlierPercentage")]
     outlier_percentage: String,
+    /// Mean silhouette coefficient of the final labels (noise excluded); None with < 2 clusters
+    #[serde(rename = "silhouetteScore")]
+    silhouette_score: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -259,6 +445,15 @@ struct TaxonomyCluster {
     episode_count: usize,
     #[serde(rename = "relevanceSec")]
     relevance_sec: u64,
+    /// Fraction of the total relevanceSec across all clusters (0.0-1.0).
+    #[serde(rename = "relevanceShare")]
+    relevance_share: f64,
+    /// Mean pairwise cosine similarity between the cluster's topics (1.0 for single-topic clusters).
+    #[serde(rename = "intraClusterCohesion")]
+    intra_cluster_cohesion: f64,
+    /// How the name was produced (debugging aid for LLM flakiness).
+    #[serde(rename = "nameSource")]
+    name_source: NameSource,
     #[serde(rename = "sampleTopics")]
     sample_topics: Vec<String>,
     episodes: Vec<u32>,
@@ -297,10 +492,31 @@ struct LlmRequestMessage {
 // Dimensionality Reduction: PCA via Power Iteration
 // ============================================================================
 
+/// Reduction seed when neither `--seed` nor the variant sets one
+const DEFAULT_SEED: u64 = 42;
+
+/// Scale each row to unit length (rows of norm ~0 are left as they are)
+fn normalize_rows(rows: &mut [Vec<f64>]) {
+    for row in rows {
+        let norm: f64 = row.iter().map(|x| x * x).sum::<f64>().sqrt();
+        if norm > 1e-10 {
+            for x in row.iter_mut() {
+                *x /= norm;
+            }
+        }
+    }
+}
+
+/// PCA for clustering: the projection with every row scaled to unit length (cosine space)
+fn pca_reduce(embeddings: &[Vec<f64>], target_dims: usize, seed: u64) -> Vec<Vec<f64>> {
+    let mut result = pca_project(embeddings, target_dims, seed);
+    normalize_rows(&mut result);
+    result
+}
+
 /// Perform PCA using power iteration method (no BLAS/LAPACK needed)
 /// This is slower than SVD-based PCA but has no external dependencies
-#[allow(dead_code)]
-fn pca_reduce(embeddings: &[Vec<f64>], target_dims: usize) -> Vec<Vec<f64>> {
+fn pca_project(embeddings: &[Vec<f64>], target_dims: usize, seed: u64) -> Vec<Vec<f64>> {
     let n = embeddings.len();
     let d = embeddings[0].len();
 
@@ -323,7 +539,7 @@ fn pca_reduce(embeddings: &[Vec<f64>], target_dims: usize) -> Vec<Vec<f64>> {
     }
 
     // Power iteration to find principal components
-    let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
     let normal = Normal::new(0.0, 1.0).unwrap();
 
     let mut components = Vec::with_capacity(target_dims);
@@ -390,22 +606,19 @@ fn pca_reduce(embeddings: &[Vec<f64>], target_dims: usize) -> Vec<Vec<f64>> {
         }
     }
 
-    // Normalize the reduced embeddings
-    for emb in &mut result {
-        let norm: f64 = emb.iter().map(|x| x * x).sum::<f64>().sqrt();
-        if norm > 1e-10 {
-            for x in emb.iter_mut() {
-                *x /= norm;
-            }
-        }
-    }
+    result
+}
 
+/// Random projection for clustering, rows scaled to unit length like `pca_reduce`
+fn random_projection_reduce(embeddings: &[Vec<f64>], target_dims: usize, seed: u64) -> Vec<Vec<f64>> {
+    let mut result = random_project(embeddings, target_dims, seed);
+    normalize_rows(&mut result);
     result
 }
 
 /// Random Projection for dimensionality reduction (faster than PCA)
 /// Based on Johnson-Lindenstrauss lemma - preserves distances well
-fn random_projection_reduce(embeddings: &[Vec<f64>], target_dims: usize) -> Vec<Vec<f64>> {
+fn random_project(embeddings: &[Vec<f64>], target_dims: usize, seed: u64) -> Vec<Vec<f64>> {
     let n = embeddings.len();
     let d = embeddings[0].len();
 
@@ -415,7 +628,7 @@ fn random_projection_reduce(embeddings: &[Vec<f64>], target_dims: usize) -> Vec<
     );
 
     // Generate random projection matrix
-    let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
     let normal = Normal::new(0.0, 1.0 / (target_dims as f64).sqrt()).unwrap();
 
     let projection: Vec<Vec<f64>> = (0..target_dims)
@@ -432,13 +645,6 @@ fn random_projection_reduce(embeddings: &[Vec<f64>], target_dims: usize) -> Vec<
                     reduced[k] += val * proj_row[j];
                 }
             }
-            // Normalize
-            let norm: f64 = reduced.iter().map(|x| x * x).sum::<f64>().sqrt();
-            if norm > 1e-10 {
-                for x in &mut reduced {
-                    *x /= norm;
-                }
-            }
             reduced
         })
         .collect();
@@ -450,29 +656,62 @@ fn random_projection_reduce(embeddings: &[Vec<f64>], target_dims: usize) -> Vec<
 // HDBSCAN Implementation
 // ============================================================================
 
+/// Nearest neighbors kept per point in the sparse (non-`--dense`) HDBSCAN graph
+const SPARSE_NEIGHBORS: usize = 30;
+
+/// Upper bound of the cosine distance; weight of the edges joining kNN graph components
+const MAX_COSINE_DISTANCE: f64 = 2.0;
+
+/// The `k` nearest neighbors of every point as (index, cosine distance), closest first.
+/// The point itself is not included; `k >= n - 1` yields all pairwise distances.
+type NeighborLists = Vec<Vec<(usize, f64)>>;
+
+/// Build the neighbor lists with a bounded max-heap per row, so memory stays O(n·k)
+fn compute_neighbor_lists(embeddings: &[Vec<f64>], k: usize) -> NeighborLists {
+    let n = embeddings.len();
+    let k = k.min(n.saturating_sub(1));
+    (0..n)
+        .into_par_iter()
+        .map(|i| {
+            let mut heap: BinaryHeap<(OrderedFloat<f64>, usize)> = BinaryHeap::with_capacity(k + 1);
+            for j in (0..n).filter(|&j| j != i) {
+                let d = OrderedFloat(1.0 - cosine_similarity(&embeddings[i], &embeddings[j]));
+                if heap.len() < k {
+                    heap.push((d, j));
+                } else if heap.peek().is_some_and(|&(worst, _)| d < worst) {
+                    heap.pop();
+                    heap.push((d, j));
+                }
+            }
+            heap.into_sorted_vec()
+                .into_iter()
+                .map(|(d, j)| (j, d.0))
+                .collect()
+        })
+        .collect()
+}
+
 /// Core distance: minimum distance at which a point is considered a core point
-fn compute_core_distances(distances: &[Vec<f64>], min_samples: usize) -> Vec<f64> {
-    let n = distances.len();
-    distances
+fn compute_core_distances(neighbors: &[Vec<(usize, f64)>], min_samples: usize) -> Vec<f64> {
+    neighbors
         .par_iter()
         .map(|row| {
-            let mut sorted: Vec<f64> = row.clone();
-            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
-            // k-th nearest neighbor distance (k = min_samples)
-            sorted[min_samples.min(n - 1)]
+            // k-th nearest neighbor distance (k = min_samples, the point itself counting as 0th)
+            match min_samples {
+                0 => 0.0,
+                k => row.get(k - 1).or(row.last()).map_or(0.0, |&(_, d)| d),
+            }
         })
         .collect()
 }
 
-/// Mutual reachability distance
+/// Mutual reachability distance of point `i` to its neighbor `(j, d)`
 #[inline]
 fn mutual_reachability_distance(
     i: usize,
-    j: usize,
-    distances: &[Vec<f64>],
+    &(j, d): &(usize, f64),
     core_distances: &[f64],
 ) -> f64 {
-    let d = distances[i][j];
     d.max(core_distances[i]).max(core_distances[j])
 }
 
@@ -484,44 +723,45 @@ struct MstEdge {
     weight: f64,
 }
 
-/// Build MST using Prim's algorithm on mutual reachability distances
-fn build_mst(distances: &[Vec<f64>], core_distances: &[f64]) -> Vec<MstEdge> {
-    let n = distances.len();
-    let mut in_tree = vec![false; n];
-    let mut edges = Vec::with_capacity(n - 1);
-
-    // Min-heap: (distance, from, to)
-    let mut heap: BinaryHeap<(std::cmp::Reverse<OrderedFloat<f64>>, usize, usize)> =
-        BinaryHeap::new();
-
-    // Start from node 0
-    in_tree[0] = true;
-    for j in 1..n {
-        let d = mutual_reachability_distance(0, j, distances, core_distances);
-        heap.push((std::cmp::Reverse(OrderedFloat(d)), 0, j));
-    }
-
-    while edges.len() < n - 1 {
-        if let Some((std::cmp::Reverse(OrderedFloat(weight)), from, to)) = heap.pop() {
-            if in_tree[to] {
-                continue;
-            }
-
-            in_tree[to] = true;
-            edges.push(MstEdge { from, to, weight });
+/// Build MST using Kruskal's algorithm on the mutual reachability distances of the neighbor
+/// graph. Exact when the lists hold all pairs; otherwise components the kNN graph leaves
+/// disconnected are joined at `MAX_COSINE_DISTANCE`.
+fn build_mst(neighbors: &[Vec<(usize, f64)>], core_distances: &[f64]) -> Vec<MstEdge> {
+    let n = neighbors.len();
+    let mut candidates: Vec<MstEdge> = neighbors
+        .iter()
+        .enumerate()
+        .flat_map(|(i, row)| {
+            // Mutual neighbors show up twice; Kruskal skips the second copy
+            row.iter().map(move |nb| MstEdge {
+                from: i,
+                to: nb.0,
+                weight: mutual_reachability_distance(i, nb, core_distances),
+            })
+        })
+        .collect();
+    candidates.par_sort_unstable_by(|a, b| a.weight.total_cmp(&b.weight));
 
-            // Add edges from new node
-            for (j, &is_in_tree) in in_tree.iter().enumerate() {
-                if !is_in_tree {
-                    let d = mutual_reachability_distance(to, j, distances, core_distances);
-                    heap.push((std::cmp::Reverse(OrderedFloat(d)), to, j));
-                }
+    let mut uf = UnionFind::new(n);
+    let mut edges = Vec::with_capacity(n.saturating_sub(1));
+    for edge in candidates {
+        if uf.union(edge.from, edge.to) {
+            edges.push(edge);
+            if edges.len() + 1 == n {
+                return edges;
             }
-        } else {
-            break;
         }
     }
 
+    for i in 1..n {
+        if uf.union(0, i) {
+            edges.push(MstEdge {
+                from: 0,
+                to: i,
+                weight: MAX_COSINE_DISTANCE,
+            });
+        }
+    }
     edges
 }
 
@@ -577,9 +817,9 @@ impl UnionFind {
 }
 
 /// HDBSCAN cluster hierarchy node
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 struct HdbscanNode {
-    #[allow(dead_code)]
     id: usize,
     children: Vec<usize>,
     lambda_birth: f64, // 1/distance at which this cluster was formed
@@ -789,8 +1029,26 @@ fn extract_flat_clusters(nodes: &[HdbscanNode], n: usize, min_cluster_size: usiz
     labels
 }
 
-/// Main HDBSCAN function
-fn hdbscan(embeddings: &[Vec<f64>], min_cluster_size: usize, min_samples: usize) -> Vec<i32> {
+/// `--hierarchy` export; point ids in `nodes[].points` index into `topics`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HierarchyExport<'a> {
+    created_at: String,
+    min_cluster_size: usize,
+    min_samples: usize,
+    topics: Vec<&'a str>,
+    nodes: &'a [HdbscanNode],
+}
+
+/// Main HDBSCAN function. Returns the flat labels and the cluster tree they were selected from
+/// (labels come from the DBSCAN fallback if the tree selection degenerates). `dense` uses all
+/// pairwise distances instead of the `SPARSE_NEIGHBORS` nearest per topic.
+fn hdbscan(
+    embeddings: &[Vec<f64>],
+    min_cluster_size: usize,
+    min_samples: usize,
+    dense: bool,
+) -> (Vec<i32>, Vec<HdbscanNode>) {
     let n = embeddings.len();
 
     println!(
@@ -799,17 +1057,22 @@ fn hdbscan(embeddings: &[Vec<f64>], min_cluster_size: usize, min_samples: usize)
     );
     println!("   Anzahl Topics: {}", n);
 
-    // Step 1: Compute distance matrix
-    println!("   Berechne Distanz-Matrix...");
-    let distances = compute_distance_matrix(embeddings);
+    // Step 1: Compute nearest neighbors
+    let k = if dense {
+        n.saturating_sub(1)
+    } else {
+        SPARSE_NEIGHBORS.max(min_samples)
+    };
+    println!("   Berechne Nachbarn (k={})...", k.min(n.saturating_sub(1)));
+    let neighbors = compute_neighbor_lists(embeddings, k);
 
     // Step 2: Compute core distances
     println!("   Berechne Core-Distanzen...");
-    let core_distances = compute_core_distances(&distances, min_samples);
+    let core_distances = compute_core_distances(&neighbors, min_samples);
 
     // Step 3: Build MST
     println!("   Erstelle Minimum Spanning Tree...");
-    let mst = build_mst(&distances, &core_distances);
+    let mst = build_mst(&neighbors, &core_distances);
 
     // Step 4: Build cluster hierarchy
     println!("   Erstelle Cluster-Hierarchie...");
@@ -845,32 +1108,24 @@ fn hdbscan(embeddings: &[Vec<f64>], min_cluster_size: usize, min_samples: usize)
     let degenerate_many = (num_clusters as usize) > (n / 2);
     if num_clusters <= 1 || degenerate_many {
         println!("   ⚠️  HDBSCAN selection degenerate (clusters={}, noise={}). Falling back to DBSCAN(auto-eps)...", num_clusters, num_noise);
-        let (db_labels, eps) = dbscan_auto_eps(embeddings, min_samples);
+        let (db_labels, eps) = dbscan_auto_eps(embeddings, &neighbors, min_samples);
         println!("   ✓ Fallback DBSCAN eps={:.4}", eps);
-        return db_labels;
+        return (db_labels, nodes);
     }
 
-    labels
+    (labels, nodes)
 }
 
 /// Alternative: DBSCAN with automatic epsilon selection
-#[allow(dead_code)]
-fn dbscan_auto_eps(embeddings: &[Vec<f64>], min_samples: usize) -> (Vec<i32>, f64) {
+fn dbscan_auto_eps(
+    embeddings: &[Vec<f64>],
+    neighbors: &[Vec<(usize, f64)>],
+    min_samples: usize,
+) -> (Vec<i32>, f64) {
     let n = embeddings.len();
 
-    // Compute distance matrix
-    let distances = compute_distance_matrix(embeddings);
-
-    // Compute k-distance for each point
-    let k = min_samples;
-    let mut k_distances: Vec<f64> = distances
-        .iter()
-        .map(|row| {
-            let mut sorted: Vec<f64> = row.clone();
-            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
-            sorted[k.min(n - 1)]
-        })
-        .collect();
+    // k-distance for each point (same as the core distance)
+    let mut k_distances = compute_core_distances(neighbors, min_samples);
 
     k_distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
 
@@ -902,15 +1157,20 @@ fn dbscan_auto_eps(embeddings: &[Vec<f64>], min_samples: usize) -> (Vec<i32>, f6
     );
 
     // Run DBSCAN with this epsilon
-    let labels = dbscan(&distances, eps, min_samples);
+    let labels = dbscan(embeddings, eps, min_samples);
 
     (labels, eps)
 }
 
-/// Simple DBSCAN implementation
-#[allow(dead_code)]
-fn dbscan(distances: &[Vec<f64>], eps: f64, min_samples: usize) -> Vec<i32> {
-    let n = distances.len();
+/// Simple DBSCAN implementation. Region queries compute distances on the fly, so memory
+/// stays O(n) at the cost of O(n²) distance evaluations.
+fn dbscan(embeddings: &[Vec<f64>], eps: f64, min_samples: usize) -> Vec<i32> {
+    let n = embeddings.len();
+    let region = |i: usize| -> Vec<usize> {
+        (0..n)
+            .filter(|&j| 1.0 - cosine_similarity(&embeddings[i], &embeddings[j]) <= eps)
+            .collect()
+    };
     let mut labels = vec![-1i32; n];
     let mut cluster_id = 0;
 
@@ -920,7 +1180,7 @@ fn dbscan(distances: &[Vec<f64>], eps: f64, min_samples: usize) -> Vec<i32> {
         }
 
         // Find neighbors
-        let neighbors: Vec<usize> = (0..n).filter(|&j| distances[i][j] <= eps).collect();
+        let neighbors = region(i);
 
         if neighbors.len() < min_samples {
             // Noise point (will be labeled later if reachable from a core point)
@@ -945,7 +1205,7 @@ fn dbscan(distances: &[Vec<f64>], eps: f64, min_samples: usize) -> Vec<i32> {
                 continue;
             }
 
-            let pt_neighbors: Vec<usize> = (0..n).filter(|&j| distances[pt][j] <= eps).collect();
+            let pt_neighbors = region(pt);
 
             if pt_neighbors.len() >= min_samples {
                 for &neighbor in &pt_neighbors {
@@ -965,30 +1225,101 @@ fn dbscan(distances: &[Vec<f64>], eps: f64, min_samples: usize) -> Vec<i32> {
     labels
 }
 
-/// Compute cosine distance matrix (parallel)
-fn compute_distance_matrix(embeddings: &[Vec<f64>]) -> Vec<Vec<f64>> {
-    let n = embeddings.len();
+/// Default for `maxDenseTopics`: ~1 GB of neighbor lists and MST candidate edges
+/// (`dense_matrix_bytes`); bigger sets need the sparse default or an explicit, larger limit
+const DEFAULT_MAX_DENSE_TOPICS: usize = 5_000;
 
-    // Parallel computation
-    let results: Vec<(usize, usize, f64)> = (0..n)
+/// Projected peak memory of a `--dense` run for `n` points, in bytes: n-1 (index, dist)
+/// pairs per point plus one MST candidate edge for each of them
+fn dense_matrix_bytes(n: usize) -> u64 {
+    let n = n as u64;
+    let per_pair = std::mem::size_of::<(usize, f64)>() + std::mem::size_of::<MstEdge>();
+    n * n.saturating_sub(1) * per_pair as u64
+}
+
+/// Refuse `--dense` runs whose distances would exceed `max_topics`, instead of OOMing
+fn check_dense_matrix_size(n: usize, max_topics: usize) -> Result<u64, String> {
+    let bytes = dense_matrix_bytes(n);
+    if n > max_topics {
+        return Err(format!(
+            "{} Topics überschreiten maxDenseTopics ({}): die Distanzmatrix bräuchte ~{:.1} GB. \
+             Topic-Menge verkleinern (z.B. ubiquitousTopicMaxEpisodeShare) oder maxDenseTopics erhöhen, \
+             wenn genug Speicher vorhanden ist.",
+            n,
+            max_topics,
+            bytes as f64 / 1e9
+        ));
+    }
+    Ok(bytes)
+}
+
+/// Silhouette scores below this suggest the parameters produced poorly separated clusters
+const SILHOUETTE_WARN_BELOW: f64 = 0.1;
+
+/// Mean silhouette coefficient over all non-noise points (`label == -1` is skipped entirely,
+/// also as a neighbor). Points alone in their cluster score 0. None with fewer than 2 clusters.
+/// Distances are computed per point, so this needs O(n²) time but no distance matrix.
+fn silhouette(labels: &[i32], embeddings: &[Vec<f64>]) -> Option<f64> {
+    let num_clusters = labels.iter().filter(|&&l| l >= 0).max().map_or(0, |&m| m as usize + 1);
+    let mut sizes = vec![0usize; num_clusters];
+    for &l in labels.iter().filter(|&&l| l >= 0) {
+        sizes[l as usize] += 1;
+    }
+    if sizes.iter().filter(|&&s| s > 0).count() < 2 {
+        return None;
+    }
+
+    let scores: Vec<f64> = (0..labels.len())
         .into_par_iter()
-        .flat_map(|i| {
-            (i + 1..n)
-                .map(|j| {
-                    let dist = 1.0 - cosine_similarity(&embeddings[i], &embeddings[j]);
-                    (i, j, dist)
-                })
-                .collect::<Vec<_>>()
+        .filter(|&i| labels[i] >= 0)
+        .map(|i| {
+            let own = labels[i] as usize;
+            if sizes[own] < 2 {
+                return 0.0;
+            }
+            let mut sums = vec![0.0; num_clusters];
+            for (j, &l) in labels.iter().enumerate() {
+                if l >= 0 && j != i {
+                    sums[l as usize] += 1.0 - cosine_similarity(&embeddings[i], &embeddings[j]);
+                }
+            }
+            let a = sums[own] / (sizes[own] - 1) as f64;
+            let b = (0..num_clusters)
+                .filter(|&c| c != own && sizes[c] > 0)
+                .map(|c| sums[c] / sizes[c] as f64)
+                .fold(f64::INFINITY, f64::min);
+            let denom = a.max(b);
+            if denom > 0.0 {
+                (b - a) / denom
+            } else {
+                0.0
+            }
         })
         .collect();
+    Some(scores.iter().sum::<f64>() / scores.len() as f64)
+}
 
-    let mut distances = vec![vec![0.0; n]; n];
-    for (i, j, dist) in results {
-        distances[i][j] = dist;
-        distances[j][i] = dist;
+/// Mean pairwise cosine similarity of a cluster's members in O(m·d): for unit vectors
+/// ‖Σv‖² = m + 2·Σ_{i<j} vᵢ·vⱼ, so no pairwise distances are needed. Zero vectors count as
+/// similarity 0 to everything.
+fn intra_cluster_cohesion(embeddings: &[Vec<f64>]) -> f64 {
+    let m = embeddings.len();
+    if m < 2 {
+        return 1.0;
     }
-
-    distances
+    let mut sum = vec![0.0; embeddings[0].len()];
+    let mut nonzero = 0;
+    for emb in embeddings {
+        let norm: f64 = emb.iter().map(|x| x * x).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            nonzero += 1;
+            for (s, x) in sum.iter_mut().zip(emb) {
+                *s += x / norm;
+            }
+        }
+    }
+    let sum_sq: f64 = sum.iter().map(|x| x * x).sum();
+    (sum_sq - nonzero as f64) / (m * (m - 1)) as f64
 }
 
 #[inline]
@@ -1010,10 +1341,113 @@ fn cosine_similarity(a: &[f64], b: &[f64]) -> f64 {
     }
 }
 
+/// Why a topic ended up in its cluster (`--explain-topic`)
+#[derive(Debug)]
+struct TopicExplanation {
+    label: i32,
+    /// Distance to the `min_samples`-th nearest topic
+    core_distance: f64,
+    /// 1 / smallest mutual reachability distance to another member of its cluster
+    join_lambda: Option<f64>,
+    /// (label, cosine similarity to the cluster centroid), most similar first
+    nearest_clusters: Vec<(i32, f64)>,
+}
+
+fn core_distance_of(embeddings: &[Vec<f64>], i: usize, min_samples: usize) -> f64 {
+    let mut row: Vec<f64> = embeddings
+        .iter()
+        .map(|e| 1.0 - cosine_similarity(&embeddings[i], e))
+        .collect();
+    row.sort_by(f64::total_cmp);
+    row[min_samples.min(row.len() - 1)]
+}
+
+/// Explain the assignment of topic `idx` given the final `labels`. Recomputes core distances
+/// for the topic and its cluster members only, so it's cheap enough to run after clustering.
+fn explain_topic(
+    embeddings: &[Vec<f64>],
+    labels: &[i32],
+    idx: usize,
+    min_samples: usize,
+) -> TopicExplanation {
+    let label = labels[idx];
+
+    // Centroid direction is enough for cosine similarity, so sums will do
+    let mut sums: HashMap<i32, Vec<f64>> = HashMap::new();
+    for (e, &l) in embeddings.iter().zip(labels) {
+        if l < 0 {
+            continue;
+        }
+        let sum = sums.entry(l).or_insert_with(|| vec![0.0; e.len()]);
+        for (s, x) in sum.iter_mut().zip(e) {
+            *s += x;
+        }
+    }
+    let mut nearest_clusters: Vec<(i32, f64)> = sums
+        .into_iter()
+        .map(|(l, sum)| (l, cosine_similarity(&embeddings[idx], &sum)))
+        .collect();
+    nearest_clusters.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let core_distance = core_distance_of(embeddings, idx, min_samples);
+    let join_lambda = if label >= 0 {
+        labels
+            .iter()
+            .enumerate()
+            .filter(|&(j, &l)| l == label && j != idx)
+            .map(|(j, _)| {
+                let d = 1.0 - cosine_similarity(&embeddings[idx], &embeddings[j]);
+                d.max(core_distance).max(core_distance_of(embeddings, j, min_samples))
+            })
+            .min_by(f64::total_cmp)
+            .map(|mrd| if mrd > 0.0 { 1.0 / mrd } else { f64::INFINITY })
+    } else {
+        None
+    };
+
+    TopicExplanation {
+        label,
+        core_distance,
+        join_lambda,
+        nearest_clusters,
+    }
+}
+
 // ============================================================================
 // Post-processing: Merge small clusters
 // ============================================================================
 
+/// Handle clusters with exactly one topic. `Misc` relabels all of them into one new regular
+/// cluster; `Outlier` keeps them as they are. Returns the labels that should be treated as
+/// outliers and the label of the Misc cluster, if one was created.
+fn collapse_singleton_clusters(labels: &mut [i32], mode: SingletonMode) -> (HashSet<i32>, Option<i32>) {
+    let mut sizes: HashMap<i32, usize> = HashMap::new();
+    for &label in labels.iter().filter(|&&l| l >= 0) {
+        *sizes.entry(label).or_insert(0) += 1;
+    }
+    let singletons: HashSet<i32> = sizes
+        .into_iter()
+        .filter(|&(_, size)| size == 1)
+        .map(|(label, _)| label)
+        .collect();
+    if singletons.is_empty() {
+        return (singletons, None);
+    }
+
+    match mode {
+        SingletonMode::Outlier => (singletons, None),
+        SingletonMode::Misc => {
+            let misc_label = labels.iter().copied().max().unwrap_or(-1) + 1;
+            for label in labels.iter_mut() {
+                if singletons.contains(label) {
+                    *label = misc_label;
+                }
+            }
+            (HashSet::new(), Some(misc_label))
+        }
+    }
+}
+
 /// Merge clusters that are too small into their nearest neighbor
 fn merge_small_clusters(
     labels: &[i32],
@@ -1140,11 +1574,58 @@ fn merge_small_clusters(
 // Cluster Naming (same as V1)
 // ============================================================================
 
+/// Boilerplate lead-ins that bias cluster names ("Diskussion über Apple" -> "Apple")
+const DEFAULT_TOPIC_NAME_PREFIXES: &[&str] = &[
+    "Diskussion über",
+    "Diskussion zu",
+    "Diskussion um",
+    "Gespräch über",
+    "Debatte über",
+    "Gedanken zu",
+    "Überlegungen zu",
+    "Überblick über",
+    "Einführung in",
+    "Thema:",
+];
+
+/// Strip leading boilerplate `prefixes` (case-insensitive, repeatedly), trim punctuation
+/// and collapse whitespace. Returns the cleaned original if nothing would remain.
+fn normalize_topic_name(topic: &str, prefixes: &[String]) -> String {
+    let collapsed = topic.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut rest: &str = &collapsed;
+    loop {
+        let lower = rest.to_lowercase();
+        // Only whole words: "Diskussion zu" must not eat the start of "Diskussion zum ..."
+        let Some(prefix) = prefixes.iter().find(|p| {
+            let p = p.to_lowercase();
+            !p.is_empty()
+                && lower.starts_with(&p)
+                && (p.ends_with(|c: char| !c.is_alphanumeric())
+                    || lower[p.len()..].chars().next().is_none_or(|c| !c.is_alphanumeric()))
+        }) else {
+            break;
+        };
+        let byte_len: usize = rest
+            .chars()
+            .take(prefix.chars().count())
+            .map(char::len_utf8)
+            .sum();
+        rest = rest[byte_len..].trim_start_matches(|c: char| c.is_whitespace() || c == ':' || c == '-');
+    }
+    let rest = rest.trim_matches(|c: char| c.is_whitespace() || c == ':' || c == '-' || c == ',');
+    if rest.is_empty() {
+        collapsed
+    } else {
+        rest.to_string()
+    }
+}
+
 fn find_cluster_name(
     cluster_items: &[usize],
     all_topics: &[TopicWithEmbedding],
     use_relevance_weighting: bool,
     default_topic_duration_sec: u32,
+    topic_name_prefixes: &[String],
 ) -> String {
     let mut keyword_counts: HashMap<String, f64> = HashMap::new();
     let mut topic_words: HashMap<String, f64> = HashMap::new();
@@ -1207,8 +1688,7 @@ fn find_cluster_name(
             *keyword_counts.entry(key).or_insert(0.0) += weight;
         }
 
-        let words: Vec<String> = topic
-            .topic
+        let words: Vec<String> = normalize_topic_name(&topic.topic, topic_name_prefixes)
             .to_lowercase()
             .chars()
             .map(|c| {
@@ -1311,10 +1791,202 @@ fn topic_relevance_sec(topic: &TopicWithEmbedding, default_topic_duration_sec: u
         .sum()
 }
 
+struct NamingOptions<'a> {
+    use_llm_naming: bool,
+    settings: &'a Settings,
+    model: Option<&'a str>,
+    use_relevance_weighting: bool,
+    default_topic_duration_sec: u32,
+    topic_name_prefixes: &'a [String],
+    retry_budget: &'a RetryBudget,
+}
+
+const DEFAULT_NAMING_RETRY_BUDGET: u32 = 25;
+
+/// Retries and time shared by all `call_llm_for_naming` calls of a run, so an API outage
+/// can't multiply into hours of backoff: once spent, naming falls back to the heuristic.
+struct RetryBudget {
+    remaining_retries: AtomicU32,
+    deadline: Option<Instant>,
+    fallbacks: AtomicUsize,
+}
+
+impl RetryBudget {
+    fn new(retries: u32, time_budget: Option<std::time::Duration>) -> Self {
+        Self {
+            remaining_retries: AtomicU32::new(retries),
+            deadline: time_budget.map(|d| Instant::now() + d),
+            fallbacks: AtomicUsize::new(0),
+        }
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.remaining_retries.load(AtomicOrdering::Relaxed) == 0
+            || self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+
+    /// Take one retry from the budget; false once it is spent
+    fn try_take_retry(&self) -> bool {
+        if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            return false;
+        }
+        self.remaining_retries
+            .fetch_update(AtomicOrdering::Relaxed, AtomicOrdering::Relaxed, |n| n.checked_sub(1))
+            .is_ok()
+    }
+
+    fn record_fallback(&self) {
+        self.fallbacks.fetch_add(1, AtomicOrdering::Relaxed);
+    }
+
+    /// Clusters named heuristically although LLM naming was enabled
+    fn fallback_count(&self) -> usize {
+        self.fallbacks.load(AtomicOrdering::Relaxed)
+    }
+}
+
+/// Default `--reuse-threshold`: centroids this close are treated as the same cluster
+const DEFAULT_REUSE_NAME_THRESHOLD: f64 = 0.9;
+
+/// Cluster names of an earlier run (`--reuse-names`); only what matching needs is read
+#[derive(Debug, Deserialize)]
+struct PreviousTaxonomy {
+    clusters: Vec<PreviousCluster>,
+    #[serde(rename = "minorClusters", default)]
+    minor_clusters: Vec<PreviousCluster>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PreviousCluster {
+    name: String,
+    #[serde(rename = "isOutlier", default)]
+    is_outlier: bool,
+    #[serde(rename = "sampleTopics", default)]
+    sample_topics: Vec<String>,
+}
+
+fn mean_embedding<'a>(vectors: impl Iterator<Item = &'a [f64]>) -> Option<Vec<f64>> {
+    let mut sum: Vec<f64> = Vec::new();
+    let mut count = 0usize;
+    for v in vectors {
+        if sum.is_empty() {
+            sum = vec![0.0; v.len()];
+        }
+        for (s, x) in sum.iter_mut().zip(v) {
+            *s += x;
+        }
+        count += 1;
+    }
+    if count == 0 {
+        return None;
+    }
+    for s in sum.iter_mut() {
+        *s /= count as f64;
+    }
+    Some(sum)
+}
+
+/// Centroids of the previous (non-outlier) clusters. The taxonomy stores no embeddings, so
+/// each centroid is the mean of its sample topics that still exist in this run.
+fn previous_centroids(
+    previous: &PreviousTaxonomy,
+    topic_index: &HashMap<&str, usize>,
+    embeddings: &[Vec<f64>],
+) -> Vec<(String, Vec<f64>)> {
+    previous
+        .clusters
+        .iter()
+        .chain(&previous.minor_clusters)
+        .filter(|c| !c.is_outlier)
+        .filter_map(|c| {
+            let members = c
+                .sample_topics
+                .iter()
+                .filter_map(|t| topic_index.get(t.as_str()))
+                .map(|&i| embeddings[i].as_slice());
+            mean_embedding(members).map(|centroid| (c.name.clone(), centroid))
+        })
+        .collect()
+}
+
+/// Previous names for new clusters: most similar pairs first, each name used at most once,
+/// only at cosine similarity >= `threshold`
+fn match_previous_names(
+    clusters: &[(i32, Vec<f64>)],
+    previous: &[(String, Vec<f64>)],
+    threshold: f64,
+) -> HashMap<i32, String> {
+    let mut pairs: Vec<(f64, i32, usize)> = Vec::new();
+    for (label, centroid) in clusters {
+        for (p, (_, prev_centroid)) in previous.iter().enumerate() {
+            let similarity = cosine_similarity(centroid, prev_centroid);
+            if similarity >= threshold {
+                pairs.push((similarity, *label, p));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+    let mut names = HashMap::new();
+    let mut used = HashSet::new();
+    for (_, label, p) in pairs {
+        if names.contains_key(&label) || !used.insert(p) {
+            continue;
+        }
+        names.insert(label, previous[p].0.clone());
+    }
+    names
+}
+
+/// Name a cluster: outliers are "Sonstiges", otherwise LLM (if enabled) with heuristic fallback
+async fn name_cluster(
+    topic_indices: &[usize],
+    unique_topics: &[TopicWithEmbedding],
+    is_outlier: bool,
+    opts: &NamingOptions<'_>,
+) -> (String, NameSource) {
+    if is_outlier {
+        return ("Sonstiges".to_string(), NameSource::Outlier);
+    }
+
+    if opts.use_llm_naming && topic_indices.len() > 1 {
+        let mut sorted_topics: Vec<&TopicWithEmbedding> =
+            topic_indices.iter().map(|&idx| &unique_topics[idx]).collect();
+        sorted_topics.sort_by(|a, b| {
+            topic_relevance_sec(b, opts.default_topic_duration_sec)
+                .cmp(&topic_relevance_sec(a, opts.default_topic_duration_sec))
+        });
+        let top_topics: Vec<String> = sorted_topics
+            .iter()
+            .take(10)
+            .map(|t| normalize_topic_name(&t.topic, opts.topic_name_prefixes))
+            .collect();
+
+        if !opts.retry_budget.is_exhausted() {
+            if let Some(llm_name) =
+                call_llm_for_naming(top_topics, opts.settings, opts.model, opts.retry_budget, 0).await
+            {
+                return (llm_name, NameSource::Llm);
+            }
+        }
+        opts.retry_budget.record_fallback();
+    }
+
+    let heuristic_name = find_cluster_name(
+        topic_indices,
+        unique_topics,
+        opts.use_relevance_weighting,
+        opts.default_topic_duration_sec,
+        opts.topic_name_prefixes,
+    );
+    (heuristic_name, NameSource::Heuristic)
+}
+
 fn call_llm_for_naming<'a>(
     topics: Vec<String>,
     settings: &'a Settings,
     model: Option<&'a str>,
+    budget: &'a RetryBudget,
     retry_count: u32,
 ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Option<String>> + Send + 'a>> {
     Box::pin(async move {
@@ -1373,7 +2045,7 @@ Regeln:
             Ok(response) => {
                 let status = response.status();
                 if status == 429 || status == 503 {
-                    if retry_count < max_retries {
+                    if retry_count < max_retries && budget.try_take_retry() {
                         let backoff_ms = retry_delay_ms * 2u64.pow(retry_count);
                         eprintln!(
                             "   ⚠️  Rate limit ({}), warte {}ms vor Retry {}/{}",
@@ -1383,9 +2055,9 @@ Regeln:
                             max_retries
                         );
                         tokio::time::sleep(tokio::time::Duration::from_millis(backoff_ms)).await;
-                        return call_llm_for_naming(topics, settings, model, retry_count + 1).await;
+                        return call_llm_for_naming(topics, settings, model, budget, retry_count + 1).await;
                     } else {
-                        eprintln!("   ❌ Max retries erreicht nach Rate Limit");
+                        eprintln!("   ❌ Max retries (oder Retry-Budget) erreicht nach Rate Limit");
                         return None;
                     }
                 }
@@ -1406,7 +2078,7 @@ Regeln:
                 None
             }
             Err(e) => {
-                if retry_count < max_retries {
+                if retry_count < max_retries && budget.try_take_retry() {
                     let backoff_ms = retry_delay_ms * 2u64.pow(retry_count);
                     eprintln!(
                         "   ⚠️  Request Error: {}, Retry {}/{}",
@@ -1415,7 +2087,7 @@ Regeln:
                         max_retries
                     );
                     tokio::time::sleep(tokio::time::Duration::from_millis(backoff_ms)).await;
-                    return call_llm_for_naming(topics, settings, model, retry_count + 1).await;
+                    return call_llm_for_naming(topics, settings, model, budget, retry_count + 1).await;
                 }
                 eprintln!("   ❌ Request failed: {}", e);
                 None
@@ -1424,26 +2096,192 @@ Regeln:
     })
 }
 
+/// Set each cluster's `relevance_share` to its fraction of the total relevance.
+/// With `include_outliers == false`, outliers neither count towards the total nor get a share.
+fn assign_relevance_shares(clusters: &mut [TaxonomyCluster], include_outliers: bool) {
+    let counts = |c: &TaxonomyCluster| include_outliers || !c.is_outlier;
+    let total: u64 = clusters
+        .iter()
+        .filter(|c| counts(c))
+        .map(|c| c.relevance_sec)
+        .sum();
+
+    for c in clusters.iter_mut() {
+        c.relevance_share = if total > 0 && counts(c) {
+            c.relevance_sec as f64 / total as f64
+        } else {
+            0.0
+        };
+    }
+}
+
+/// One entry of `topic-assignments.json`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TopicAssignment {
+    topic: String,
+    /// None for topics filtered out before clustering (intro/outro, ubiquitous) and for noise
+    cluster_id: Option<String>,
+    cluster_name: Option<String>,
+    is_outlier: bool,
+}
+
+/// Assignment of every `db.topics` entry, in that order. `filtered_db_indices` maps the
+/// clustered topics (the positions in `labels`) back to `topics`; `clusters` maps labels to
+/// (id, name, is_outlier). Noise points count as outliers without a cluster.
+fn topic_assignments(
+    topics: &[&str],
+    filtered_db_indices: &[usize],
+    labels: &[i32],
+    clusters: &HashMap<i32, (String, String, bool)>,
+) -> Vec<TopicAssignment> {
+    let mut assignments: Vec<TopicAssignment> = topics
+        .iter()
+        .map(|t| TopicAssignment {
+            topic: t.to_string(),
+            cluster_id: None,
+            cluster_name: None,
+            is_outlier: false,
+        })
+        .collect();
+    for (&db_idx, label) in filtered_db_indices.iter().zip(labels) {
+        let assignment = &mut assignments[db_idx];
+        match clusters.get(label) {
+            Some((id, name, is_outlier)) => {
+                assignment.cluster_id = Some(id.clone());
+                assignment.cluster_name = Some(name.clone());
+                assignment.is_outlier = *is_outlier;
+            }
+            None => assignment.is_outlier = true,
+        }
+    }
+    assignments
+}
+
+/// One point of `topic-coords.json` (`--project-2d`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TopicCoord {
+    topic: String,
+    x: f64,
+    y: f64,
+    /// None for noise points
+    cluster_id: Option<String>,
+}
+
+/// 2D projection with the run's reduction method; without one PCA is used. Rows are not scaled
+/// to unit length as for clustering, which would put every point on the unit circle.
+fn project_2d(embeddings: &[Vec<f64>], method: ReductionMethod, seed: u64) -> Vec<Vec<f64>> {
+    match method {
+        ReductionMethod::Random => random_project(embeddings, 2, seed),
+        ReductionMethod::Pca | ReductionMethod::None => pca_project(embeddings, 2, seed),
+    }
+}
+
+/// Center the points and scale both axes by the same factor so the wider one spans [-1, 1]
+/// (keeps the aspect ratio of the projection)
+fn normalize_coords(points: &mut [Vec<f64>]) {
+    let mut min = [f64::INFINITY; 2];
+    let mut max = [f64::NEG_INFINITY; 2];
+    for p in points.iter() {
+        for axis in 0..2 {
+            min[axis] = min[axis].min(p[axis]);
+            max[axis] = max[axis].max(p[axis]);
+        }
+    }
+    let half_range = ((max[0] - min[0]).max(max[1] - min[1]) / 2.0).max(f64::EPSILON);
+    for p in points.iter_mut() {
+        for axis in 0..2 {
+            p[axis] = (p[axis] - (min[axis] + max[axis]) / 2.0) / half_range;
+        }
+    }
+}
+
+/// Coordinates of the clustered topics, colored by the cluster of their label
+fn topic_coords(
+    topics: &[&str],
+    mut points: Vec<Vec<f64>>,
+    labels: &[i32],
+    clusters: &HashMap<i32, (String, String, bool)>,
+) -> Vec<TopicCoord> {
+    normalize_coords(&mut points);
+    topics
+        .iter()
+        .zip(points)
+        .zip(labels)
+        .map(|((topic, p), label)| TopicCoord {
+            topic: topic.to_string(),
+            x: p[0],
+            y: p[1],
+            cluster_id: clusters.get(label).map(|(id, _, _)| id.clone()),
+        })
+        .collect()
+}
+
+/// Cluster/outlier counts over all clusters (taken before any output filtering)
+fn taxonomy_statistics(clusters: &[TaxonomyCluster], silhouette_score: Option<f64>) -> Statistics {
+    let outlier_count = clusters.iter().filter(|c| c.is_outlier).count();
+    Statistics {
+        cluster_count: clusters.len(),
+        outlier_count,
+        outlier_percentage: format!(
+            "{:.1}%",
+            (outlier_count as f64 / clusters.len() as f64) * 100.0
+        ),
+        silhouette_score,
+    }
+}
+
+/// Drop the outlier clusters unless `include_outliers`; the statistics are taken before, so
+/// omitted outliers are still counted
+fn omit_outliers_unless(
+    mut clusters: Vec<TaxonomyCluster>,
+    include_outliers: bool,
+    silhouette_score: Option<f64>,
+) -> (Vec<TaxonomyCluster>, Statistics) {
+    let statistics = taxonomy_statistics(&clusters, silhouette_score);
+    if !include_outliers {
+        clusters.retain(|c| !c.is_outlier);
+    }
+    (clusters, statistics)
+}
+
+/// Split clusters into (main, minor) by `relevance_sec`; input order is preserved.
+fn split_minor_clusters(
+    clusters: Vec<TaxonomyCluster>,
+    min_relevance_sec: u64,
+) -> (Vec<TaxonomyCluster>, Vec<TaxonomyCluster>) {
+    clusters
+        .into_iter()
+        .partition(|c| c.relevance_sec >= min_relevance_sec)
+}
+
 // ============================================================================
 // Main
 // ============================================================================
 
 /// Load variant settings from variants.json
-fn load_variant_settings(
+fn load_variant_settings(variant_name: &str) -> Result<(String, VariantSettingsJson), VariantError> {
+    load_variant_settings_from(&PathBuf::from("variants.json"), variant_name)
+}
+
+fn load_variant_settings_from(
+    variants_path: &PathBuf,
     variant_name: &str,
-) -> Result<(String, VariantSettingsJson), Box<dyn std::error::Error>> {
-    let variants_path = PathBuf::from("variants.json");
+) -> Result<(String, VariantSettingsJson), VariantError> {
     if !variants_path.exists() {
-        return Err("variants.json not found".into());
+        return Err(VariantError::FileMissing(variants_path.clone()));
     }
 
-    let variants_content = fs::read_to_string(&variants_path)?;
-    let variants_config: VariantsConfig = serde_json::from_str(&variants_content)?;
+    let variants_content =
+        fs::read_to_string(variants_path).map_err(|e| VariantError::Parse(e.to_string()))?;
+    let variants_config: VariantsConfig =
+        serde_json::from_str(&variants_content).map_err(|e| VariantError::Parse(e.to_string()))?;
 
     let variant = variants_config
         .variants
         .get(variant_name)
-        .ok_or_else(|| format!("Variant '{}' not found in variants.json", variant_name))?;
+        .ok_or_else(|| VariantError::NotFound(variant_name.to_string()))?;
 
     Ok((variant.name.clone(), variant.settings.clone()))
 }
@@ -1471,67 +2309,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let settings_content = fs::read_to_string(&settings_path)?;
     let settings: Settings = serde_json::from_str(&settings_content)?;
 
-    // Load variant settings if specified, otherwise use base settings
-    let (
-        min_cluster_size,
-        min_samples,
-        reduced_dims,
-        use_llm_naming,
-        use_relevance_weighting,
-        outlier_threshold,
-        default_topic_duration_sec,
-    ) = if let Some(ref variant_name) = args.variant {
-        match load_variant_settings(variant_name) {
+    // Load variant settings if specified; settings.json fills in what the variant leaves open
+    let variant_settings = match args.variant.as_deref() {
+        Some(variant_name) => match load_variant_settings(variant_name) {
             Ok((variant_display_name, variant_settings)) => {
                 println!(
                     "📋 Lade Variante: {} ({})\n",
                     variant_display_name, variant_name
                 );
-                (
-                    variant_settings
-                        .min_cluster_size
-                        .or(settings
-                            .topic_clustering
-                            .as_ref()
-                            .and_then(|s| s.min_cluster_size))
-                        .unwrap_or(5),
-                    variant_settings
-                        .min_samples
-                        .or(settings
-                            .topic_clustering
-                            .as_ref()
-                            .and_then(|s| s.min_samples))
-                        .unwrap_or(3),
-                    variant_settings
-                        .reduced_dimensions
-                        .or(settings
-                            .topic_clustering
-                            .as_ref()
-                            .and_then(|s| s.reduced_dimensions))
-                        .unwrap_or(50),
-                    variant_settings
-                        .use_llm_naming
-                        .or(settings
-                            .topic_clustering
-                            .as_ref()
-                            .and_then(|s| s.use_llm_naming))
-                        .unwrap_or(false),
-                    variant_settings
-                        .use_relevance_weighting
-                        .or(settings
-                            .topic_clustering
-                            .as_ref()
-                            .and_then(|s| s.use_relevance_weighting))
-                        .unwrap_or(true),
-                    variant_settings
-                        .outlier_threshold
-                        .or(settings
-                            .topic_clustering
-                            .as_ref()
-                            .and_then(|s| s.outlier_threshold))
-                        .unwrap_or(0.15),
-                    variant_settings.default_topic_duration_sec.unwrap_or(300),
-                )
+                variant_settings
             }
             Err(e) => {
                 eprintln!(
@@ -1540,42 +2326,33 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 );
                 std::process::exit(1);
             }
-        }
-    } else {
-        (
-            settings
-                .topic_clustering
-                .as_ref()
-                .and_then(|s| s.min_cluster_size)
-                .unwrap_or(5),
-            settings
-                .topic_clustering
-                .as_ref()
-                .and_then(|s| s.min_samples)
-                .unwrap_or(3),
-            settings
-                .topic_clustering
-                .as_ref()
-                .and_then(|s| s.reduced_dimensions)
-                .unwrap_or(50),
-            settings
-                .topic_clustering
-                .as_ref()
-                .and_then(|s| s.use_llm_naming)
-                .unwrap_or(false),
-            settings
-                .topic_clustering
-                .as_ref()
-                .and_then(|s| s.use_relevance_weighting)
-                .unwrap_or(true),
-            settings
-                .topic_clustering
-                .as_ref()
-                .and_then(|s| s.outlier_threshold)
-                .unwrap_or(0.15),
-            300,
-        )
-    };
+        },
+        None => VariantSettingsJson::default(),
+    }
+    .or_base(settings.topic_clustering.as_ref());
+
+    let min_cluster_size = variant_settings.min_cluster_size.unwrap_or(5);
+    let min_samples = variant_settings.min_samples.unwrap_or(3);
+    let reduced_dims = variant_settings.reduced_dimensions.unwrap_or(50);
+    let use_llm_naming = variant_settings.use_llm_naming.unwrap_or(false);
+    let use_relevance_weighting = variant_settings.use_relevance_weighting.unwrap_or(true);
+    let outlier_threshold = variant_settings.outlier_threshold.unwrap_or(0.15);
+    let default_topic_duration_sec = variant_settings.default_topic_duration_sec.unwrap_or(300);
+    let relevance_share_include_outliers = variant_settings.relevance_share_include_outliers.unwrap_or(true);
+    let collapse_singletons = variant_settings.collapse_singletons.unwrap_or(false);
+    let collapse_singletons_mode = variant_settings.collapse_singletons_mode.unwrap_or_default();
+    let max_dense_topics = variant_settings.max_dense_topics.unwrap_or(DEFAULT_MAX_DENSE_TOPICS);
+    let min_cluster_relevance_sec = variant_settings.min_cluster_relevance_sec.filter(|_| !args.full_output);
+    let keep_minor_clusters = variant_settings.keep_minor_clusters.unwrap_or(false);
+    let topic_name_prefixes: Vec<String> = variant_settings
+        .topic_name_prefixes
+        .unwrap_or_else(|| DEFAULT_TOPIC_NAME_PREFIXES.iter().map(|p| p.to_string()).collect());
+    let ascii_slugs = variant_settings.ascii_slugs.unwrap_or(false);
+    let include_outliers = variant_settings.include_outliers.unwrap_or(true);
+    let naming_retry_budget = variant_settings.naming_retry_budget.unwrap_or(DEFAULT_NAMING_RETRY_BUDGET);
+    let naming_time_budget_sec = variant_settings.naming_time_budget_sec;
+    let reduction_method = variant_settings.reduction_method.unwrap_or_default();
+    let seed = args.seed.or(variant_settings.seed).unwrap_or(DEFAULT_SEED);
 
     // Load embeddings
     println!("📂 Lade Embeddings-Datenbank (Podcast: {})...", args.podcast);
@@ -1591,6 +2368,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let db_content = fs::read_to_string(&db_path)?;
     let db: EmbeddingsDatabase = serde_json::from_str(&db_content)?;
 
+    let previous_taxonomy = match &args.reuse_names {
+        Some(path) => {
+            let previous: PreviousTaxonomy = serde_json::from_str(&fs::read_to_string(path)?)?;
+            println!(
+                "   Vorherige Taxonomie: {} ({} Cluster)",
+                path.display(),
+                previous.clusters.len() + previous.minor_clusters.len()
+            );
+            Some(previous)
+        }
+        None => None,
+    };
+
     println!("   Modell: {}", db.embedding_model);
     println!("   Topics: {}", db.topics.len());
     println!("   Dimensionen: {}", db.embedding_dimensions);
@@ -1614,25 +2404,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let total_episodes = all_episode_ids.len().max(1);
 
     let mut filtered_topics: Vec<TopicWithEmbedding> = Vec::with_capacity(db.topics.len());
+    // db.topics index of each filtered topic
+    let mut filtered_db_indices: Vec<usize> = Vec::with_capacity(db.topics.len());
     let mut skipped_by_name = 0usize;
     let mut skipped_by_share = 0usize;
+    let mut removed_topics: Vec<FilteredTopic> = Vec::new();
 
-    for t in db.topics.iter().cloned() {
-        let topic_lc = t.topic.to_lowercase();
-        let is_intro_outro = topic_lc.contains("intro") || topic_lc.contains("outro");
-
-        if is_intro_outro {
-            skipped_by_name += 1;
-            continue;
-        }
-
+    for (db_idx, t) in db.topics.iter().cloned().enumerate() {
         let share = (t.episodes.len() as f64) / (total_episodes as f64);
-        if share >= ubiquitous_share_threshold {
-            skipped_by_share += 1;
+        if let Some(reason) = filter_reason(&t.topic, share, ubiquitous_share_threshold) {
+            match reason {
+                FilterReason::IntroOutro => skipped_by_name += 1,
+                FilterReason::Ubiquitous => skipped_by_share += 1,
+            }
+            removed_topics.push(FilteredTopic { topic: t.topic, reason, episode_share: share });
             continue;
         }
 
         filtered_topics.push(t);
+        filtered_db_indices.push(db_idx);
     }
 
     if skipped_by_name > 0 || skipped_by_share > 0 {
@@ -1651,6 +2441,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("   Min Cluster Size:    {}", min_cluster_size);
     println!("   Min Samples:         {}", min_samples);
     println!("   Reduzierte Dims:     {}", reduced_dims);
+    println!("   Reduktion:           {}", reduction_method.label());
+    println!("   Seed:                {}", seed);
     println!(
         "   Relevanz-Gewichtung: {}",
         if use_relevance_weighting {
@@ -1673,16 +2465,50 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Step 1: Dimensionality reduction
     println!("📉 Dimensionsreduktion...");
-    let reduced_embeddings = if reduced_dims < db.embedding_dimensions {
-        // Use random projection (faster) or PCA (better quality)
-        random_projection_reduce(&embeddings, reduced_dims)
-    } else {
-        embeddings.clone()
+    let reduction_start = Instant::now();
+    let reduced_embeddings = match reduction_method {
+        _ if reduced_dims >= db.embedding_dimensions => embeddings.clone(),
+        // Random projection is faster, PCA preserves more structure
+        ReductionMethod::Random => random_projection_reduce(&embeddings, reduced_dims, seed),
+        ReductionMethod::Pca => pca_reduce(&embeddings, reduced_dims, seed),
+        ReductionMethod::None => embeddings.clone(),
     };
+    println!(
+        "   Reduktion ({}): {:.1}s",
+        reduction_method.label(),
+        reduction_start.elapsed().as_secs_f64()
+    );
 
     // Step 2: HDBSCAN clustering
     println!("\n📊 HDBSCAN Clustering...");
-    let labels = hdbscan(&reduced_embeddings, min_cluster_size, min_samples);
+    if args.dense {
+        match check_dense_matrix_size(reduced_embeddings.len(), max_dense_topics) {
+            Ok(bytes) => println!(
+                "   Distanzmatrix (--dense): {} Topics, ~{:.1} GB Speicher",
+                reduced_embeddings.len(),
+                bytes as f64 / 1e9
+            ),
+            Err(msg) => {
+                eprintln!("\n❌ {}", msg);
+                std::process::exit(1);
+            }
+        }
+    }
+    let (labels, hierarchy) = hdbscan(&reduced_embeddings, min_cluster_size, min_samples, args.dense);
+
+    if let Some(path) = &args.hierarchy {
+        let export = HierarchyExport {
+            created_at: chrono::Utc::now().to_rfc3339(),
+            min_cluster_size,
+            min_samples,
+            topics: unique_topics.iter().map(|t| t.topic.as_str()).collect(),
+            nodes: &hierarchy,
+        };
+        fs::write(path, serde_json::to_string(&export)?)?;
+        println!("   ✓ Cluster-Hierarchie ({} Knoten) gespeichert: {:?}", hierarchy.len(), path);
+    }
+    // Each node stores its member list; free the tree before naming
+    drop(hierarchy);
 
     // Count clusters and noise
     let num_clusters = labels
@@ -1698,13 +2524,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Step 3: Merge small clusters and assign noise
     println!("\n🔄 Post-Processing...");
-    let final_labels = merge_small_clusters(
+    let mut final_labels = merge_small_clusters(
         &labels,
         &reduced_embeddings,
         min_cluster_size,
         outlier_threshold,
     );
 
+    // Clusters that get the outlier treatment regardless of size, and the Misc cluster
+    let (forced_outlier_labels, misc_label) = if collapse_singletons {
+        let (forced, misc) = collapse_singleton_clusters(&mut final_labels, collapse_singletons_mode);
+        match misc {
+            Some(_) => println!("   ✓ Singleton-Cluster zu \"Misc\" zusammengefasst"),
+            None => println!("   ✓ Singleton-Cluster: {} Cluster als Outlier", forced.len()),
+        }
+        (forced, misc)
+    } else {
+        (HashSet::new(), None)
+    };
+
     let final_num_clusters = final_labels
         .iter()
         .filter(|&&l| l >= 0)
@@ -1717,6 +2555,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         final_num_outliers, outlier_threshold
     );
 
+    // Cluster quality on the final labels
+    let silhouette_score = silhouette(&final_labels, &reduced_embeddings);
+    match silhouette_score {
+        Some(score) if score < SILHOUETTE_WARN_BELOW => println!(
+            "   ⚠️  Silhouette-Score {:.3} < {}: Cluster schlecht getrennt, minClusterSize/outlierThreshold anpassen",
+            score, SILHOUETTE_WARN_BELOW
+        ),
+        Some(score) => println!("   ✓ Silhouette-Score: {:.3}", score),
+        None => println!("   ℹ️  Silhouette-Score nicht berechenbar (weniger als 2 Cluster)"),
+    }
+
     // Step 4: Build cluster structures
     println!("\n🏷️  Cluster benennen...");
     let delay_ms = settings
@@ -1733,6 +2582,31 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // Names of matching clusters from the previous run (outliers are named "Sonstiges" anyway)
+    let reused_names = match &previous_taxonomy {
+        Some(previous) => {
+            let topic_index: HashMap<&str, usize> = unique_topics
+                .iter()
+                .enumerate()
+                .map(|(i, t)| (t.topic.as_str(), i))
+                .collect();
+            let candidates: Vec<(i32, Vec<f64>)> = cluster_topics
+                .iter()
+                .filter(|(label, indices)| {
+                    indices.len() >= min_cluster_size
+                        && !forced_outlier_labels.contains(label)
+                        && misc_label != Some(**label)
+                })
+                .filter_map(|(label, indices)| {
+                    mean_embedding(indices.iter().map(|&idx| embeddings[idx].as_slice())).map(|c| (*label, c))
+                })
+                .collect();
+            let previous = previous_centroids(previous, &topic_index, &embeddings);
+            match_previous_names(&candidates, &previous, args.reuse_threshold)
+        }
+        None => HashMap::new(),
+    };
+
     let pb = ProgressBar::new(cluster_topics.len() as u64);
     pb.set_style(
         ProgressStyle::default_bar()
@@ -1742,68 +2616,66 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
 
     let mut named_clusters = Vec::new();
+    let mut label_names: HashMap<i32, String> = HashMap::new();
+    let mut label_clusters: HashMap<i32, (String, String, bool)> = HashMap::new();
     let model = settings
         .topic_clustering
         .as_ref()
         .and_then(|s| s.model.as_deref());
+    let retry_budget = RetryBudget::new(
+        naming_retry_budget,
+        naming_time_budget_sec.map(std::time::Duration::from_secs),
+    );
+    let naming = NamingOptions {
+        use_llm_naming,
+        settings: &settings,
+        model,
+        use_relevance_weighting,
+        default_topic_duration_sec,
+        topic_name_prefixes: &topic_name_prefixes,
+        retry_budget: &retry_budget,
+    };
 
-    for (i, (_cluster_label, topic_indices)) in cluster_topics.iter().enumerate() {
+    for (i, (cluster_label, topic_indices)) in cluster_topics.iter().enumerate() {
         let cluster_topics_data: Vec<_> = topic_indices
             .iter()
             .map(|&idx| unique_topics[idx].clone())
             .collect();
 
+        // The Misc cluster is a regular cluster regardless of its size
+        let is_misc = misc_label == Some(*cluster_label);
+
         // Determine if outlier based on cluster cohesion
-        let is_outlier = cluster_topics_data.len() < min_cluster_size;
-
-        let name = if is_outlier {
-            pb.set_message("\"Sonstiges\" (Outlier)".to_string());
-            "Sonstiges".to_string()
-        } else if use_llm_naming && cluster_topics_data.len() > 1 {
-            let mut sorted_topics = cluster_topics_data.clone();
-            sorted_topics.sort_by(|a, b| {
-                topic_relevance_sec(b, default_topic_duration_sec)
-                    .cmp(&topic_relevance_sec(a, default_topic_duration_sec))
-            });
-            let top_topics: Vec<String> = sorted_topics
-                .iter()
-                .take(10)
-                .map(|t| t.topic.clone())
-                .collect();
+        let is_outlier = !is_misc
+            && (cluster_topics_data.len() < min_cluster_size
+                || forced_outlier_labels.contains(cluster_label));
 
-            // Rate limit prevention
-            if i > 0 && i % 50 == 0 {
-                pb.set_message("⏸️  Pause (Rate Limit Prävention)".to_string());
-                tokio::time::sleep(tokio::time::Duration::from_millis(30000)).await;
-            }
+        let reused_name = reused_names.get(cluster_label).filter(|_| !is_outlier);
 
-            match call_llm_for_naming(top_topics, &settings, model, 0).await {
-                Some(llm_name) => {
-                    pb.set_message(format!("\"{}\" (LLM)", llm_name));
-                    tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
-                    llm_name
-                }
-                None => {
-                    let heuristic_name = find_cluster_name(
-                        topic_indices,
-                        &unique_topics,
-                        use_relevance_weighting,
-                        default_topic_duration_sec,
-                    );
-                    pb.set_message(format!("\"{}\" (Heuristik)", heuristic_name));
-                    heuristic_name
-                }
-            }
+        // Rate limit prevention
+        if use_llm_naming && !is_outlier && !is_misc && reused_name.is_none() && !retry_budget.is_exhausted() && i > 0 && i % 50 == 0 {
+            pb.set_message("⏸️  Pause (Rate Limit Prävention)".to_string());
+            tokio::time::sleep(tokio::time::Duration::from_millis(30000)).await;
+        }
+
+        let (name, name_source) = if is_misc {
+            ("Misc".to_string(), NameSource::Misc)
         } else {
-            let heuristic_name = find_cluster_name(
-                topic_indices,
-                &unique_topics,
-                use_relevance_weighting,
-                default_topic_duration_sec,
-            );
-            pb.set_message(format!("\"{}\" (Heuristik)", heuristic_name));
-            heuristic_name
+            match reused_name {
+                Some(name) => (name.clone(), NameSource::Reused),
+                None => name_cluster(topic_indices, &unique_topics, is_outlier, &naming).await,
+            }
         };
+        match name_source {
+            NameSource::Outlier => pb.set_message(format!("\"{}\" (Outlier)", name)),
+            NameSource::Misc => pb.set_message(format!("\"{}\" (Singletons)", name)),
+            NameSource::Reused => pb.set_message(format!("\"{}\" (übernommen)", name)),
+            NameSource::Llm => {
+                pb.set_message(format!("\"{}\" (LLM)", name));
+                tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
+            }
+            NameSource::Heuristic => pb.set_message(format!("\"{}\" (Heuristik)", name)),
+        }
 
         // Collect all episodes
         let mut all_episodes = HashSet::new();
@@ -1820,22 +2692,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .map(|t| topic_relevance_sec(t, default_topic_duration_sec))
             .sum();
 
+        // Cohesion on the original (unreduced) embeddings so scores are real cosine similarities
+        let member_embeddings: Vec<Vec<f64>> = topic_indices
+            .iter()
+            .map(|&idx| embeddings[idx].clone())
+            .collect();
+        let cohesion = intra_cluster_cohesion(&member_embeddings);
+
+        label_names.insert(*cluster_label, name.clone());
+
         // Create ID from name
-        let id = name
-            .to_lowercase()
-            .chars()
-            .map(|c| {
-                if c.is_alphanumeric() || c == 'ä' || c == 'ö' || c == 'ü' || c == 'ß' {
-                    c
-                } else {
-                    '-'
-                }
-            })
-            .collect::<String>()
-            .split('-')
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<_>>()
-            .join("-");
+        let id = cluster_id_slug(&name, ascii_slugs);
+        label_clusters.insert(*cluster_label, (id.clone(), name.clone(), is_outlier));
 
         named_clusters.push(NamedCluster {
             id,
@@ -1844,6 +2712,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             topic_count: cluster_topics_data.len(),
             episode_count: episodes.len(),
             relevance_sec: cluster_relevance_sec,
+            intra_cluster_cohesion: cohesion,
+            name_source,
             topics: cluster_topics_data
                 .iter()
                 .map(|t| ClusterTopic {
@@ -1861,9 +2731,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     pb.finish_with_message("Done");
+    if previous_taxonomy.is_some() {
+        let reused = named_clusters.iter().filter(|c| c.name_source == NameSource::Reused).count();
+        let generated = named_clusters
+            .iter()
+            .filter(|c| matches!(c.name_source, NameSource::Llm | NameSource::Heuristic))
+            .count();
+        println!("   ♻️  {} Namen übernommen, {} neu benannt", reused, generated);
+    }
+    if retry_budget.fallback_count() > 0 {
+        println!(
+            "   ⚠️  {} Cluster heuristisch benannt (LLM fehlgeschlagen oder Retry-Budget erschöpft)",
+            retry_budget.fallback_count()
+        );
+    }
 
     // Sort by relevance (duration) so "bigger" clusters bubble to the top
-    named_clusters.sort_by(|a, b| b.relevance_sec.cmp(&a.relevance_sec));
+    named_clusters.sort_by_key(|c| std::cmp::Reverse(c.relevance_sec));
 
     let outlier_count = named_clusters.iter().filter(|c| c.is_outlier).count();
     println!("\n   ℹ️  {} Outlier-Cluster gefunden\n", outlier_count);
@@ -1871,6 +2755,42 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Save results (same format as V1)
     let taxonomy_file = PathBuf::from("topic-taxonomy.json");
 
+    let mut taxonomy_clusters: Vec<TaxonomyCluster> = named_clusters
+        .iter()
+        .map(|c| TaxonomyCluster {
+            id: c.id.clone(),
+            name: c.name.clone(),
+            description: format!("{} Topics in {} Episoden", c.topic_count, c.episode_count),
+            is_outlier: c.is_outlier,
+            topic_count: c.topic_count,
+            episode_count: c.episode_count,
+            relevance_sec: c.relevance_sec,
+            relevance_share: 0.0,
+            intra_cluster_cohesion: c.intra_cluster_cohesion,
+            name_source: c.name_source,
+            sample_topics: c.topics.iter().take(5).map(|t| t.topic.clone()).collect(),
+            episodes: c.episodes.clone(),
+        })
+        .collect();
+    assign_relevance_shares(&mut taxonomy_clusters, relevance_share_include_outliers);
+    let (taxonomy_clusters, statistics) = omit_outliers_unless(taxonomy_clusters, include_outliers, silhouette_score);
+
+    let (taxonomy_clusters, mut minor_clusters) = match min_cluster_relevance_sec {
+        Some(min_sec) => split_minor_clusters(taxonomy_clusters, min_sec),
+        None => (taxonomy_clusters, Vec::new()),
+    };
+    if !minor_clusters.is_empty() {
+        println!(
+            "   ℹ️  {} Cluster unter {}s Relevanz {}",
+            minor_clusters.len(),
+            min_cluster_relevance_sec.unwrap_or_default(),
+            if keep_minor_clusters { "nach minorClusters verschoben" } else { "entfernt" }
+        );
+        if !keep_minor_clusters {
+            minor_clusters.clear();
+        }
+    }
+
     let result = TaxonomyResult {
         created_at: chrono::Utc::now().to_rfc3339(),
         method: "hdbscan-v2".to_string(),
@@ -1886,29 +2806,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 min_cluster_size, min_samples
             ),
             use_relevance_weighting,
+            seed,
         },
-        statistics: Statistics {
-            cluster_count: named_clusters.len(),
-            outlier_count,
-            outlier_percentage: format!(
-                "{:.1}%",
-                (outlier_count as f64 / named_clusters.len() as f64) * 100.0
-            ),
-        },
-        clusters: named_clusters
-            .iter()
-            .map(|c| TaxonomyCluster {
-                id: c.id.clone(),
-                name: c.name.clone(),
-                description: format!("{} Topics in {} Episoden", c.topic_count, c.episode_count),
-                is_outlier: c.is_outlier,
-                topic_count: c.topic_count,
-                episode_count: c.episode_count,
-                relevance_sec: c.relevance_sec,
-                sample_topics: c.topics.iter().take(5).map(|t| t.topic.clone()).collect(),
-                episodes: c.episodes.clone(),
-            })
-            .collect(),
+        statistics,
+        clusters: taxonomy_clusters,
+        minor_clusters,
     };
 
     let result_json = serde_json::to_string_pretty(&result)?;
@@ -1953,6 +2855,37 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     fs::write(&detailed_file, detailed_json)?;
     println!("✅ Detailed Topic-Mapping gespeichert: {:?}", detailed_file);
 
+    // Flat topic -> cluster map in db.topics order, so consumers can zip by index
+    let assignments_file = PathBuf::from("topic-assignments.json");
+    let db_topic_names: Vec<&str> = db.topics.iter().map(|t| t.topic.as_str()).collect();
+    let assignments = topic_assignments(
+        &db_topic_names,
+        &filtered_db_indices,
+        &final_labels,
+        &label_clusters,
+    );
+    fs::write(&assignments_file, serde_json::to_string_pretty(&assignments)?)?;
+    println!("✅ Topic-Zuordnung gespeichert: {:?}", assignments_file);
+
+    if args.project_2d {
+        let projection_start = Instant::now();
+        let points = project_2d(&embeddings, reduction_method, seed);
+        let topic_names: Vec<&str> = unique_topics.iter().map(|t| t.topic.as_str()).collect();
+        let coords = topic_coords(&topic_names, points, &final_labels, &label_clusters);
+        let coords_file = PathBuf::from("topic-coords.json");
+        fs::write(&coords_file, serde_json::to_string_pretty(&coords)?)?;
+        println!(
+            "✅ 2D-Koordinaten gespeichert: {:?} ({} Topics, {:.1}s)",
+            coords_file,
+            coords.len(),
+            projection_start.elapsed().as_secs_f64()
+        );
+    }
+
+    let filtered_file = PathBuf::from("filtered-topics.json");
+    fs::write(&filtered_file, serde_json::to_string_pretty(&removed_topics)?)?;
+    println!("✅ Gefilterte Topics gespeichert: {:?} ({})", filtered_file, removed_topics.len());
+
     // Print top clusters
     println!("\n📋 Top 15 Cluster:");
     for (i, c) in named_clusters.iter().take(15).enumerate() {
@@ -1969,6 +2902,35 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("      Beispiele: {}", examples.join(", "));
     }
 
+    if let Some(query) = args.explain_topic.as_deref() {
+        match unique_topics
+            .iter()
+            .position(|t| t.topic.eq_ignore_ascii_case(query.trim()))
+        {
+            Some(idx) => {
+                let explanation = explain_topic(&reduced_embeddings, &final_labels, idx, min_samples);
+                let label_name = |l: i32| label_names.get(&l).cloned().unwrap_or_else(|| format!("#{}", l));
+                println!("\n🔍 Erklärung für \"{}\":", unique_topics[idx].topic);
+                if explanation.label >= 0 {
+                    println!("   Zugeordnet zu: {}", label_name(explanation.label));
+                } else {
+                    println!("   Zugeordnet zu: keinem Cluster (Noise)");
+                }
+                println!("   Core-Distanz (min_samples={}): {:.4}", min_samples, explanation.core_distance);
+                match explanation.join_lambda {
+                    Some(lambda) => println!("   Beitritts-λ zum Cluster: {:.4}", lambda),
+                    None => println!("   Beitritts-λ zum Cluster: -"),
+                }
+                println!("   Nächste Cluster (Centroid-Ähnlichkeit):");
+                for (l, sim) in explanation.nearest_clusters.iter().take(5) {
+                    let marker = if *l == explanation.label { " ← zugeordnet" } else { "" };
+                    println!("      {:.4}  {}{}", sim, label_name(*l), marker);
+                }
+            }
+            None => println!("\n⚠️  Topic \"{}\" nicht gefunden (--explain-topic)", query),
+        }
+    }
+
     let elapsed = start_time.elapsed();
     println!("\n✨ Statistik:");
     println!(
@@ -1984,3 +2946,525 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reduction_method_setting() {
+        let settings: VariantSettingsJson = serde_json::from_str(r#"{ "reductionMethod": "pca" }"#).unwrap();
+        assert_eq!(settings.reduction_method, Some(ReductionMethod::Pca));
+        let settings: VariantSettingsJson = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings.reduction_method.unwrap_or_default(), ReductionMethod::Random);
+        assert!(serde_json::from_str::<VariantSettingsJson>(r#"{ "reductionMethod": "umap" }"#).is_err());
+
+        // PCA output has the target dimension and unit length
+        let embeddings: Vec<Vec<f64>> = (0..20)
+            .map(|i| (0..8).map(|j| ((i * 7 + j * 3) % 11) as f64 - 5.0).collect())
+            .collect();
+        let reduced = pca_reduce(&embeddings, 3, DEFAULT_SEED);
+        assert_eq!(reduced.len(), 20);
+        assert!(reduced.iter().all(|v| v.len() == 3));
+        assert!(reduced.iter().all(|v| (v.iter().map(|x| x * x).sum::<f64>().sqrt() - 1.0).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_reduction_seed_is_reproducible() {
+        let settings: VariantSettingsJson = serde_json::from_str(r#"{ "seed": 7 }"#).unwrap();
+        assert_eq!(settings.seed, Some(7));
+
+        let embeddings: Vec<Vec<f64>> = (0..10)
+            .map(|i| (0..16).map(|j| ((i * 5 + j * 3) % 13) as f64 - 6.0).collect())
+            .collect();
+        let a = random_projection_reduce(&embeddings, 4, 7);
+        assert_eq!(a, random_projection_reduce(&embeddings, 4, 7));
+        assert_ne!(a, random_projection_reduce(&embeddings, 4, DEFAULT_SEED));
+    }
+
+    #[test]
+    fn test_unknown_variant_is_not_found() {
+        let dir = std::env::temp_dir().join(format!("{}-variants-test-{}", env!("CARGO_BIN_NAME"), std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("variants.json");
+        fs::write(&path, r#"{ "variants": { "fein": { "version": "v1", "name": "Fein", "settings": {} } } }"#).unwrap();
+
+        assert_eq!(load_variant_settings_from(&path, "fein").unwrap().0, "Fein");
+        assert!(matches!(
+            load_variant_settings_from(&path, "gibt-es-nicht"),
+            Err(VariantError::NotFound(name)) if name == "gibt-es-nicht"
+        ));
+        assert!(matches!(
+            load_variant_settings_from(&dir.join("missing.json"), "fein"),
+            Err(VariantError::FileMissing(_))
+        ));
+        fs::write(&path, "{ not json").unwrap();
+        assert!(matches!(load_variant_settings_from(&path, "fein"), Err(VariantError::Parse(_))));
+    }
+
+    #[test]
+    fn test_variant_settings_fall_back_to_settings_json() {
+        let variant: VariantSettingsJson =
+            serde_json::from_str(r#"{ "minClusterSize": 8, "seed": 7 }"#).unwrap();
+        let base: TopicClusteringSettings =
+            serde_json::from_str(r#"{ "minClusterSize": 4, "minSamples": 2, "asciiSlugs": true }"#).unwrap();
+
+        let resolved = variant.clone().or_base(Some(&base));
+        assert_eq!(resolved.min_cluster_size, Some(8));
+        assert_eq!(resolved.min_samples, Some(2));
+        assert_eq!(resolved.ascii_slugs, Some(true));
+        assert_eq!(resolved.seed, Some(7));
+        assert_eq!(resolved.reduced_dimensions, None);
+        assert_eq!(variant.or_base(None).min_samples, None);
+    }
+
+    fn taxonomy_cluster(id: &str, relevance_sec: u64, is_outlier: bool) -> TaxonomyCluster {
+        TaxonomyCluster {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            is_outlier,
+            topic_count: 1,
+            episode_count: 1,
+            relevance_sec,
+            relevance_share: 0.0,
+            intra_cluster_cohesion: 1.0,
+            name_source: NameSource::Heuristic,
+            sample_topics: vec![],
+            episodes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_relevance_shares_sum_to_one() {
+        let mut clusters = vec![
+            taxonomy_cluster("a", 600, false),
+            taxonomy_cluster("b", 300, false),
+            taxonomy_cluster("sonstiges", 100, true),
+        ];
+
+        assign_relevance_shares(&mut clusters, true);
+        let sum: f64 = clusters.iter().map(|c| c.relevance_share).sum();
+        assert!((sum - 1.0).abs() < 1e-9);
+        assert!((clusters[0].relevance_share - 0.6).abs() < 1e-9);
+        assert!((clusters[2].relevance_share - 0.1).abs() < 1e-9);
+
+        assign_relevance_shares(&mut clusters, false);
+        let sum: f64 = clusters.iter().map(|c| c.relevance_share).sum();
+        assert!((sum - 1.0).abs() < 1e-9);
+        assert!((clusters[0].relevance_share - 600.0 / 900.0).abs() < 1e-9);
+        assert_eq!(clusters[2].relevance_share, 0.0);
+    }
+
+    #[test]
+    fn test_min_cluster_relevance_filter() {
+        let clusters = vec![
+            taxonomy_cluster("a", 600, false),
+            taxonomy_cluster("b", 120, false),
+            taxonomy_cluster("c", 30, false),
+            taxonomy_cluster("sonstiges", 10, true),
+        ];
+
+        let (main, minor) = split_minor_clusters(clusters, 120);
+        let main_ids: Vec<&str> = main.iter().map(|c| c.id.as_str()).collect();
+        let minor_ids: Vec<&str> = minor.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(main_ids, vec!["a", "b"]);
+        assert_eq!(minor_ids, vec!["c", "sonstiges"]);
+        assert!(main.iter().all(|c| c.relevance_sec >= 120));
+    }
+
+    #[test]
+    fn test_explain_topic_ranks_assigned_cluster_first() {
+        let embeddings = vec![
+            vec![1.0, 0.0, 0.0],
+            vec![0.98, 0.05, 0.0],
+            vec![0.97, 0.0, 0.05],
+            vec![0.0, 1.0, 0.0],
+            vec![0.05, 0.98, 0.0],
+            vec![0.0, 0.97, 0.05],
+        ];
+        let labels = vec![0, 0, 0, 1, 1, 1];
+
+        let explanation = explain_topic(&embeddings, &labels, 1, 2);
+        assert_eq!(explanation.label, 0);
+        assert_eq!(explanation.nearest_clusters[0].0, 0);
+        assert!(explanation.nearest_clusters[0].1 > explanation.nearest_clusters[1].1);
+        assert!(explanation.core_distance >= 0.0);
+        assert!(explanation.join_lambda.is_some_and(|l| l > 1.0));
+
+        // A broken (NaN) embedding elsewhere must not panic the explanation
+        let mut with_nan = embeddings.clone();
+        with_nan[5] = vec![f64::NAN, 0.0, 0.0];
+        assert_eq!(explain_topic(&with_nan, &labels, 1, 2).label, 0);
+        explain_topic(&with_nan, &labels, 4, 2);
+    }
+
+    #[test]
+    fn test_tight_cluster_more_cohesive_than_loose() {
+        let tight = vec![
+            vec![1.0, 0.05, 0.0],
+            vec![1.0, 0.0, 0.05],
+            vec![0.95, 0.05, 0.05],
+        ];
+        let loose = vec![
+            vec![1.0, 0.0, 0.0],
+            vec![0.2, 1.0, 0.0],
+            vec![0.3, 0.1, 1.0],
+        ];
+
+        let tight_cohesion = intra_cluster_cohesion(&tight);
+        let loose_cohesion = intra_cluster_cohesion(&loose);
+        assert!(tight_cohesion > 0.99);
+        assert!(tight_cohesion > loose_cohesion);
+        assert_eq!(intra_cluster_cohesion(&tight[..1]), 1.0);
+
+        // Same as the mean over all pairs
+        let pairs = [(0, 1), (0, 2), (1, 2)];
+        let pairwise = pairs.iter().map(|&(i, j)| cosine_similarity(&loose[i], &loose[j])).sum::<f64>() / 3.0;
+        assert!((loose_cohesion - pairwise).abs() < 1e-12);
+        let with_zero = vec![loose[0].clone(), vec![0.0; 3], loose[1].clone()];
+        let pairwise = cosine_similarity(&loose[0], &loose[1]) / 3.0;
+        assert!((intra_cluster_cohesion(&with_zero) - pairwise).abs() < 1e-12);
+    }
+
+    fn topic(name: &str, keywords: &[&str]) -> TopicWithEmbedding {
+        TopicWithEmbedding {
+            topic: name.to_string(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            count: 1,
+            episodes: vec![1],
+            occurrences: None,
+            embedding: vec![1.0, 0.0],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_name_source_heuristic_when_llm_disabled() {
+        let settings: Settings = serde_json::from_value(serde_json::json!({
+            "llm": { "model": "test", "apiKey": "test", "baseURL": "http://127.0.0.1:9" }
+        }))
+        .unwrap();
+        let naming = NamingOptions {
+            use_llm_naming: false,
+            settings: &settings,
+            model: None,
+            use_relevance_weighting: false,
+            default_topic_duration_sec: 300,
+            topic_name_prefixes: &[],
+            retry_budget: &RetryBudget::new(DEFAULT_NAMING_RETRY_BUDGET, None),
+        };
+        let topics = vec![
+            topic("Apple Vision Pro", &["apple", "vr"]),
+            topic("Apple Watch", &["apple", "uhr"]),
+        ];
+
+        let (_, source) = name_cluster(&[0, 1], &topics, false, &naming).await;
+        assert_eq!(source, NameSource::Heuristic);
+
+        let (name, source) = name_cluster(&[0], &topics, true, &naming).await;
+        assert_eq!(name, "Sonstiges");
+        assert_eq!(source, NameSource::Outlier);
+        assert_eq!(serde_json::to_value(NameSource::Heuristic).unwrap(), "heuristic");
+    }
+
+    #[test]
+    fn test_normalize_topic_name_strips_boilerplate() {
+        let prefixes: Vec<String> = DEFAULT_TOPIC_NAME_PREFIXES.iter().map(|p| p.to_string()).collect();
+        assert_eq!(normalize_topic_name("Diskussion über  Apple Vision Pro", &prefixes), "Apple Vision Pro");
+        assert_eq!(normalize_topic_name("diskussion über Thema: Mastodon", &prefixes), "Mastodon");
+        assert_eq!(normalize_topic_name("  Gespräch über die Ärzteschaft ", &prefixes), "die Ärzteschaft");
+        // Topics without boilerplate and pure boilerplate stay intact
+        assert_eq!(normalize_topic_name("Diskussionskultur", &prefixes), "Diskussionskultur");
+        assert_eq!(normalize_topic_name("Diskussion zum Datenschutz", &prefixes), "Diskussion zum Datenschutz");
+        assert_eq!(normalize_topic_name("Diskussion über", &prefixes), "Diskussion über");
+
+        let custom = vec!["Talk about".to_string()];
+        assert_eq!(normalize_topic_name("Talk about Rust", &custom), "Rust");
+    }
+
+    #[test]
+    fn test_hierarchy_export_shape() {
+        // Two well-separated groups of 4 points each
+        let embeddings: Vec<Vec<f64>> = (0..8)
+            .map(|i| {
+                let jitter = 0.01 * (i % 4) as f64;
+                if i < 4 {
+                    vec![1.0, jitter, 0.0]
+                } else {
+                    vec![0.0, jitter, 1.0]
+                }
+            })
+            .collect();
+        let n = embeddings.len();
+
+        let (_, nodes) = hdbscan(&embeddings, 3, 2, false);
+        assert_eq!(nodes.len(), 2 * n - 1);
+
+        let mut seen = HashSet::new();
+        for node in nodes.iter().filter(|node| node.selected) {
+            for &p in &node.points {
+                assert!(seen.insert(p), "point {} is in more than one selected node", p);
+            }
+        }
+
+        let json = serde_json::to_value(&nodes[n]).unwrap();
+        for key in ["id", "children", "lambdaBirth", "lambdaDeath", "stability", "selected"] {
+            assert!(json.get(key).is_some(), "missing {}", key);
+        }
+    }
+
+    #[test]
+    fn test_silhouette_excludes_noise() {
+        // Two tight, well separated groups plus one noise point between them
+        let embeddings = vec![
+            vec![1.0, 0.0],
+            vec![0.99, 0.05],
+            vec![0.98, -0.05],
+            vec![0.0, 1.0],
+            vec![0.05, 0.99],
+            vec![-0.05, 0.98],
+            vec![0.7, 0.7],
+        ];
+        let good = silhouette(&[0, 0, 0, 1, 1, 1, -1], &embeddings).unwrap();
+        assert!(good > 0.9, "{good}");
+
+        // The noise point would drag the score down if it counted
+        let with_noise_as_cluster = silhouette(&[0, 0, 0, 1, 1, 1, 0], &embeddings).unwrap();
+        assert!(with_noise_as_cluster < good);
+
+        // Mixed-up labels separate badly
+        assert!(silhouette(&[0, 1, 0, 1, 0, 1, -1], &embeddings).unwrap() < SILHOUETTE_WARN_BELOW);
+        assert_eq!(silhouette(&[0, 0, 0, 0, 0, 0, -1], &embeddings), None);
+        assert_eq!(silhouette(&[-1; 7], &embeddings), None);
+    }
+
+    #[test]
+    fn test_outliers_omitted_but_counted() {
+        let clusters = vec![
+            taxonomy_cluster("a", 600, false),
+            taxonomy_cluster("sonstiges", 100, true),
+            taxonomy_cluster("b", 300, false),
+            taxonomy_cluster("sonstiges-2", 50, true),
+        ];
+
+        let (kept, statistics) = omit_outliers_unless(clusters.clone(), false, None);
+        assert_eq!(statistics.cluster_count, 4);
+        assert_eq!(statistics.outlier_count, 2);
+        assert_eq!(statistics.outlier_percentage, "50.0%");
+        let ids: Vec<&str> = kept.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+
+        let (kept, statistics) = omit_outliers_unless(clusters, true, None);
+        assert_eq!(statistics.outlier_count, 2);
+        assert_eq!(kept.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget_falls_back_to_heuristic() {
+        use axum::{http::StatusCode, routing::post, Router};
+        use std::sync::Arc;
+
+        // LLM endpoint that is permanently rate limited
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let app = Router::new().route(
+            "/chat/completions",
+            post(move || {
+                counter.fetch_add(1, AtomicOrdering::SeqCst);
+                async { StatusCode::TOO_MANY_REQUESTS }
+            }),
+        );
+        let addr = crate::test_support::spawn_app(app).await;
+
+        let settings: Settings = serde_json::from_value(serde_json::json!({
+            "llm": { "model": "test", "apiKey": "test", "baseURL": format!("http://{addr}") },
+            "topicExtraction": { "maxRetries": 5, "retryDelayMs": 1 }
+        }))
+        .unwrap();
+        let budget = RetryBudget::new(3, None);
+        let naming = NamingOptions {
+            use_llm_naming: true,
+            settings: &settings,
+            model: None,
+            use_relevance_weighting: false,
+            default_topic_duration_sec: 300,
+            topic_name_prefixes: &[],
+            retry_budget: &budget,
+        };
+        let topics = vec![
+            topic("Apple Vision Pro", &["apple", "vr"]),
+            topic("Apple Watch", &["apple", "uhr"]),
+        ];
+
+        for _ in 0..5 {
+            let (_, source) = name_cluster(&[0, 1], &topics, false, &naming).await;
+            assert_eq!(source, NameSource::Heuristic);
+        }
+
+        // One initial attempt plus the shared budget, not 5 clusters x (1 + maxRetries)
+        assert_eq!(requests.load(AtomicOrdering::SeqCst), 1 + 3);
+        assert!(budget.is_exhausted());
+        assert_eq!(budget.fallback_count(), 5);
+    }
+
+    #[test]
+    fn test_collapse_singleton_clusters() {
+        // Clusters 0 and 2 have one topic each, cluster 1 has two, -1 is noise
+        let labels = vec![0, 1, 1, 2, -1];
+
+        let mut misc = labels.clone();
+        let (forced, misc_label) = collapse_singleton_clusters(&mut misc, SingletonMode::Misc);
+        assert_eq!(misc, vec![3, 1, 1, 3, -1]);
+        assert!(forced.is_empty());
+        assert_eq!(misc_label, Some(3));
+
+        let mut outlier = labels.clone();
+        let (forced, misc_label) = collapse_singleton_clusters(&mut outlier, SingletonMode::Outlier);
+        assert_eq!(outlier, labels);
+        assert_eq!(forced, HashSet::from([0, 2]));
+        assert_eq!(misc_label, None);
+
+        // Without singletons nothing changes
+        let mut no_singletons = vec![0, 0, 1, 1];
+        let (forced, misc_label) = collapse_singleton_clusters(&mut no_singletons, SingletonMode::Misc);
+        assert!(forced.is_empty() && misc_label.is_none());
+        assert_eq!(no_singletons, vec![0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn test_sparse_neighbor_graph() {
+        // Two far-apart groups: with k=2 the kNN graph has no edge between them
+        let embeddings = vec![
+            vec![1.0, 0.0],
+            vec![0.99, 0.05],
+            vec![0.98, -0.05],
+            vec![0.0, 1.0],
+            vec![0.05, 0.99],
+            vec![-0.05, 0.98],
+        ];
+        let all = compute_neighbor_lists(&embeddings, usize::MAX);
+        let knn = compute_neighbor_lists(&embeddings, 2);
+        for (full, sparse) in all.iter().zip(&knn) {
+            assert_eq!(full.len(), 5);
+            assert_eq!(&full[..2], sparse.as_slice());
+            assert!(full.windows(2).all(|w| w[0].1 <= w[1].1));
+        }
+        assert_eq!(compute_core_distances(&knn, 2), compute_core_distances(&all, 2));
+
+        let core = compute_core_distances(&knn, 2);
+        let exact = build_mst(&all, &core);
+        let sparse = build_mst(&knn, &core);
+        assert_eq!(exact.len(), 5);
+        assert_eq!(sparse.len(), 5);
+        // The sparse MST matches within groups and bridges them at the distance bound
+        let mut weights: Vec<f64> = sparse.iter().map(|e| e.weight).collect();
+        weights.sort_by(f64::total_cmp);
+        let mut exact_weights: Vec<f64> = exact.iter().map(|e| e.weight).collect();
+        exact_weights.sort_by(f64::total_cmp);
+        assert_eq!(weights[..4], exact_weights[..4]);
+        assert_eq!(weights[4], MAX_COSINE_DISTANCE);
+    }
+
+    #[test]
+    fn test_topic_assignments_keep_db_order() {
+        let topics = ["Intro", "Apple", "Podcasting", "Banane", "Rauschen"];
+        // "Intro" was filtered out; the rest were clustered in order
+        let filtered = [1, 2, 3, 4];
+        let labels = [0, 1, 0, -1];
+        let clusters = HashMap::from([
+            (0, ("tech".to_string(), "Tech".to_string(), false)),
+            (1, ("sonstiges".to_string(), "Sonstiges".to_string(), true)),
+        ]);
+        let assignments = topic_assignments(&topics, &filtered, &labels, &clusters);
+
+        let order: Vec<&str> = assignments.iter().map(|a| a.topic.as_str()).collect();
+        assert_eq!(order, topics);
+        assert_eq!(assignments[0].cluster_id, None);
+        assert!(!assignments[0].is_outlier);
+        assert_eq!(assignments[1].cluster_id.as_deref(), Some("tech"));
+        assert_eq!(assignments[3].cluster_name.as_deref(), Some("Tech"));
+        assert!(assignments[2].is_outlier);
+        assert_eq!(assignments[4].cluster_id, None);
+        assert!(assignments[4].is_outlier);
+
+        let json = serde_json::to_value(&assignments[0]).unwrap();
+        assert_eq!(json["clusterId"], serde_json::Value::Null);
+        assert_eq!(json["isOutlier"], false);
+    }
+
+    #[test]
+    fn test_dense_matrix_guard() {
+        assert_eq!(check_dense_matrix_size(100, 100), Ok(dense_matrix_bytes(100)));
+        assert!(check_dense_matrix_size(10, 100).is_ok());
+
+        let err = check_dense_matrix_size(101, 100).unwrap_err();
+        assert!(err.contains("maxDenseTopics"));
+        // 100 points: 99 neighbors of 16 bytes plus 99 edges of 24 bytes each
+        assert_eq!(dense_matrix_bytes(100), 100 * 99 * (16 + 24));
+    }
+
+    #[test]
+    fn test_previous_names_match_one_to_one_above_threshold() {
+        let previous: PreviousTaxonomy = serde_json::from_value(serde_json::json!({
+            "clusters": [
+                { "name": "Apple", "sampleTopics": ["iPhone", "Mac", "gelöscht"] },
+                { "name": "Sonstiges", "isOutlier": true, "sampleTopics": ["Raumfahrt"] },
+                { "name": "Verschwunden", "sampleTopics": ["gibt es nicht mehr"] },
+            ],
+            "minorClusters": [{ "name": "Raumfahrt", "sampleTopics": ["Raumfahrt"] }],
+        }))
+        .unwrap();
+        let embeddings = vec![vec![1.0, 0.0], vec![0.8, 0.2], vec![0.0, 1.0]];
+        let topic_index: HashMap<&str, usize> = [("iPhone", 0), ("Mac", 1), ("Raumfahrt", 2)].into_iter().collect();
+        let centroids = previous_centroids(&previous, &topic_index, &embeddings);
+        assert_eq!(centroids.len(), 2);
+        assert_eq!(centroids[0], ("Apple".to_string(), vec![0.9, 0.1]));
+        assert_eq!(centroids[1].0, "Raumfahrt");
+
+        // Two new clusters close to "Apple": only the closer one inherits the name
+        let clusters = vec![(0, vec![0.95, 0.1]), (1, vec![0.9, 0.1]), (2, vec![0.1, 1.0]), (3, vec![1.0, 1.0])];
+        let names = match_previous_names(&clusters, &centroids, 0.9);
+        assert_eq!(names.get(&1).map(String::as_str), Some("Apple"));
+        assert_eq!(names.get(&2).map(String::as_str), Some("Raumfahrt"));
+        assert!(!names.contains_key(&0));
+        assert!(!names.contains_key(&3));
+        assert!(match_previous_names(&clusters, &centroids, 1.01).is_empty());
+        assert_eq!(serde_json::to_value(NameSource::Reused).unwrap(), "reused");
+    }
+
+    #[test]
+    fn test_topic_coords_are_normalized_and_keep_clusters() {
+        let clusters: HashMap<i32, (String, String, bool)> =
+            [(0, ("apple".to_string(), "Apple".to_string(), false))].into_iter().collect();
+        let points = vec![vec![2.0, 10.0], vec![6.0, 11.0], vec![4.0, 12.0]];
+        let coords = topic_coords(&["iPhone", "Mac", "Rauschen"], points, &[0, 0, -1], &clusters);
+
+        // x spans the wider range; y keeps the aspect ratio around its center
+        let xy: Vec<(f64, f64)> = coords.iter().map(|c| (c.x, c.y)).collect();
+        assert_eq!(xy, vec![(-1.0, -0.5), (1.0, 0.0), (0.0, 0.5)]);
+        assert_eq!(coords[0].cluster_id.as_deref(), Some("apple"));
+        assert_eq!(coords[2].cluster_id, None);
+        assert_eq!(
+            serde_json::to_value(&coords[1]).unwrap(),
+            serde_json::json!({ "topic": "Mac", "x": 1.0, "y": 0.0, "clusterId": "apple" })
+        );
+
+        // A single point sits in the center
+        let single = topic_coords(&["x"], vec![vec![3.0, 3.0]], &[0], &clusters);
+        assert_eq!((single[0].x, single[0].y), (0.0, 0.0));
+
+        let embeddings: Vec<Vec<f64>> = (0..6).map(|i| vec![i as f64, (i * i) as f64, 1.0]).collect();
+        assert!(project_2d(&embeddings, ReductionMethod::None, 42).iter().all(|p| p.len() == 2));
+
+        // Distances survive the projection: the points don't all land on the unit circle
+        let norm = |x: f64, y: f64| (x * x + y * y).sqrt();
+        for method in [ReductionMethod::Pca, ReductionMethod::Random] {
+            let points = project_2d(&embeddings, method, 42);
+            assert!(points.iter().any(|p| (norm(p[0], p[1]) - 1.0).abs() > 1e-3), "{method:?}");
+            let labels = vec![0; points.len()];
+            let topics = ["a", "b", "c", "d", "e", "f"];
+            let coords = topic_coords(&topics, points, &labels, &clusters);
+            assert!(coords.iter().any(|c| (norm(c.x, c.y) - 1.0).abs() > 1e-3), "{method:?}");
+        }
+    }
+}
@@ -1,11 +1,12 @@
-use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use std::{collections::HashMap, net::{IpAddr, SocketAddr}, path::PathBuf, sync::{atomic::AtomicBool, Arc}, time::Duration};
 
 use anyhow::{anyhow, Context, Result};
 use moka::future::Cache;
 use reqwest::Client;
 use serde::Deserialize;
 
-use crate::cache::{CachedEpisodeFiles, CachedEpisodeList, CachedEpisodeMetadata, CachedEpisodeTopicsMap, CachedRagIndex, CachedSpeakerMeta, CachedSpeakerProfile, CachedSpeakersIndex};
+use crate::rag::retrieval::TitleEmbeddings;
+use crate::cache::{CachedEpisodeFiles, CachedEpisodeList, CachedEpisodeMetadata, CachedEpisodeTopicsMap, CachedRagIndex, CachedSpeakerMeta, CachedSpeakerProfile, CachedSpeakersIndex, CachedTaxonomy};
 
 // Forward declaration to avoid circular dependency
 pub type AnalyticsDb = crate::handlers::analytics::AnalyticsDb;
@@ -25,6 +26,13 @@ pub struct LlmSettings {
     #[serde(rename = "apiKey")]
     api_key: Option<String>,
     model: Option<String>,
+    provider: Option<String>,
+    #[serde(rename = "embeddingProvider")]
+    embedding_provider: Option<String>,
+    #[serde(rename = "embeddingBaseURL")]
+    embedding_base_url: Option<String>,
+    #[serde(rename = "embeddingApiKey")]
+    embedding_api_key: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -41,8 +49,77 @@ pub struct RagSettings {
     stats_auth_token: Option<String>,
     #[serde(rename = "bindAddr")]
     bind_addr: Option<String>,
+    #[serde(rename = "authExemptPaths")]
+    auth_exempt_paths: Option<Vec<String>>,
+    temperatures: Option<TemperatureSettings>,
+    #[serde(rename = "maxTokens")]
+    max_tokens: Option<u32>,
+    #[serde(rename = "podcastAuthTokens")]
+    podcast_auth_tokens: Option<HashMap<String, String>>,
 }
 
+/// Optional per-podcast settings in `db/<podcast>/settings.json`
+#[derive(Debug, Deserialize)]
+struct PodcastSettingsFile {
+    #[serde(rename = "authToken")]
+    auth_token: Option<String>,
+}
+
+/// Per-podcast tokens from `rag.podcastAuthTokens`, overridden by `db/<podcast>/settings.json`.
+/// A malformed settings file is logged and skipped so one podcast can't block startup.
+fn load_podcast_auth_tokens(from_settings: Option<&HashMap<String, String>>) -> HashMap<String, String> {
+    let mut tokens: HashMap<String, String> = from_settings.cloned().unwrap_or_default();
+    if let Ok(entries) = std::fs::read_dir("db") {
+        for entry in entries.flatten() {
+            let Some(podcast_id) = entry.file_name().to_str().map(|s| s.to_string()) else {
+                continue;
+            };
+            let path = entry.path().join("settings.json");
+            match try_read_json::<PodcastSettingsFile>(&path) {
+                Ok(settings) => {
+                    if let Some(token) = settings.and_then(|s| s.auth_token) {
+                        tokens.insert(podcast_id, token);
+                    }
+                }
+                Err(e) => tracing::warn!("Skipping auth token of podcast {}: {:#}", podcast_id, e),
+            }
+        }
+    }
+    tokens.retain(|_, t| {
+        *t = t.trim().to_string();
+        !t.is_empty()
+    });
+    tokens
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TemperatureSettings {
+    neutral: Option<f32>,
+    persona: Option<f32>,
+    discussion: Option<f32>,
+}
+
+/// LLM sampling temperature per answer mode; personas need more room than neutral answers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnswerTemperatures {
+    pub neutral: f32,
+    pub persona: f32,
+    pub discussion: f32,
+}
+
+impl Default for AnswerTemperatures {
+    fn default() -> Self {
+        Self {
+            neutral: 0.2,
+            persona: 0.5,
+            discussion: 0.8,
+        }
+    }
+}
+
+/// Paths that never require an auth token, so probes and scrapers keep working
+const DEFAULT_AUTH_EXEMPT_PATHS: &[&str] = &["/api/health", "/api/health/ready", "/api/health/embeddings", "/metrics"];
+
 fn try_read_json<T: for<'de> Deserialize<'de>>(path: &PathBuf) -> Result<Option<T>> {
     if !path.exists() {
         return Ok(None);
@@ -53,6 +130,71 @@ fn try_read_json<T: for<'de> Deserialize<'de>>(path: &PathBuf) -> Result<Option<
     Ok(Some(parsed))
 }
 
+/// What to do when the embeddings API returns a vector whose dimension differs from the index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DimMismatchPolicy {
+    /// Fail the request with a clear error
+    Error,
+    /// Truncate (Matryoshka-style) or zero-pad the query vector to the index dimension
+    Truncate,
+}
+
+/// Wire format of the LLM API used for embeddings or chat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LlmProvider {
+    /// OpenAI and compatible APIs (OpenRouter, vLLM, ...): `/embeddings`, `/chat/completions`
+    #[default]
+    OpenAi,
+    /// Cohere v2: `/embed` with `texts`, `/chat`
+    Cohere,
+    /// Ollama's native API: `/embed`, `/chat` with NDJSON streaming
+    Ollama,
+}
+
+impl LlmProvider {
+    /// Parse the provider name of setting `var`; unknown names are rejected
+    fn from_name(name: &str, var: &str) -> Result<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "openai" => Ok(LlmProvider::OpenAi),
+            "cohere" => Ok(LlmProvider::Cohere),
+            "ollama" => Ok(LlmProvider::Ollama),
+            other => Err(anyhow!("Invalid {} '{}' (expected openai, cohere or ollama)", var, other)),
+        }
+    }
+}
+
+/// What cross-podcast search does when indices were built with different embedding models
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossModelPolicy {
+    /// Refuse the search
+    Strict,
+    /// Score each model's indices against a query embedded with that model, normalize, then merge
+    Lenient,
+}
+
+/// Read a boolean env var ("1"/"true"/"yes", case-insensitive), defaulting to false
+fn env_flag(name: &str) -> bool {
+    std::env::var(name)
+        .map(|s| matches!(s.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Validate the LLM API key. With `allow_no_key` a missing/placeholder key is accepted and
+/// reported as unavailable (degraded mode) instead of failing startup.
+fn resolve_llm_api_key(api_key: Option<String>, allow_no_key: bool) -> Result<(String, bool)> {
+    match api_key {
+        Some(key) if !key.trim().is_empty() && key != "YOUR_API_KEY_HERE" => Ok((key, true)),
+        _ if allow_no_key => {
+            tracing::warn!("No LLM API key configured: starting without embeddings/chat (RAG_ALLOW_NO_KEY)");
+            Ok((String::new(), false))
+        }
+        None => Err(anyhow!("Missing LLM API key (set LLM_API_KEY or settings.json: llm.apiKey)")),
+        Some(_) => Err(anyhow!(
+            "LLM API key is missing/placeholder (set LLM_API_KEY or update settings.json: llm.apiKey)"
+        )),
+    }
+}
+
 fn load_settings() -> Result<(Option<SettingsFile>, String)> {
     // Prefer settings.json, fall back to settings.example.json (but still require non-placeholder API key unless env overrides)
     let settings_path = PathBuf::from("settings.json");
@@ -71,16 +213,76 @@ fn load_settings() -> Result<(Option<SettingsFile>, String)> {
 #[derive(Clone, Debug)]
 pub struct AppConfig {
     pub bind_addr: SocketAddr,
+    // Default podcast (PODCAST_ID), whose episode and speaker files may live elsewhere
+    // (EPISODES_DIR / SPEAKERS_DIR); other podcasts always use podcasts/<id>/...
+    pub podcast_id: String,
     pub episodes_dir: PathBuf,
     pub speakers_dir: PathBuf,
     pub llm_base_url: String,
+    // API flavour of the chat endpoint (LLM_PROVIDER / llm.provider)
+    pub llm_provider: LlmProvider,
+    // Embeddings may come from another server, e.g. a local Ollama (EMBEDDING_BASE_URL / EMBEDDING_PROVIDER)
+    pub embedding_base_url: String,
+    pub embedding_provider: LlmProvider,
+    // Key for embedding requests (EMBEDDING_API_KEY / llm.embeddingApiKey), defaults to the LLM key
+    pub embedding_api_key: String,
     pub llm_api_key: String,
+    // False when started without an API key (RAG_ALLOW_NO_KEY); LLM-backed endpoints answer 503
+    pub llm_available: bool,
     pub llm_model: String,
     pub embedding_model: String,
     pub top_k: usize,
     pub max_context_chars: usize,
+    // Queries shorter than this (after trimming) are rejected before embedding
+    pub min_query_len: usize,
+    // Chat sources below this cosine score are dropped (RAG_MIN_SCORE); requests can only raise it
+    pub min_score: Option<f32>,
     pub auth_token: Option<String>,
     pub stats_auth_token: Option<String>,
+    // Tokens that only unlock requests for one podcast (podcast_id -> token)
+    pub podcast_auth_tokens: HashMap<String, String>,
+    pub auth_exempt_paths: Vec<String>,
+    // Skip malformed transcript entries instead of failing the whole episode
+    pub transcript_lenient: bool,
+    // Merge consecutive same-speaker transcript lines at most this many seconds apart (None = off)
+    pub transcript_merge_gap_sec: Option<f64>,
+    // Drop duplicate segments (same window or same embedding) when loading a RAG index
+    pub dedup_items: bool,
+    // Build an HNSW graph at load time and search it instead of scanning (RAG_ANN)
+    pub ann_enabled: bool,
+    // Scale index embeddings to unit length at load so cosine needs no per-item norm (RAG_NORMALIZE_ON_LOAD)
+    pub normalize_on_load: bool,
+    pub embedding_dim_mismatch: DimMismatchPolicy,
+    pub cross_model_policy: CrossModelPolicy,
+    // Max episode metadata files loaded concurrently in batch loads
+    pub metadata_concurrency: usize,
+    pub answer_temperatures: AnswerTemperatures,
+    // Default and upper bound for answer length in tokens (RAG_CHAT_MAX_TOKENS / rag.maxTokens)
+    pub chat_max_tokens: Option<u32>,
+    // Upper bound for scoring in episode search; slower requests get 408
+    pub search_timeout: Duration,
+    // Global kill switch for the query-embedding cache (RAG_NO_EMBED_CACHE)
+    pub embed_cache_enabled: bool,
+    // Lifetime of cached query embeddings (RAG_QUERY_CACHE_TTL_SECS)
+    pub query_cache_ttl: Duration,
+    // Share of an episode's search score taken from its title embedding (0 disables)
+    pub title_blend_weight: f32,
+    // Reload a cached RAG index once it is older than this, whatever the file looks like
+    pub rag_cache_max_age: Option<Duration>,
+    // Topics kept per episode in the topics map (most frequent first); None keeps all
+    pub max_topics_per_episode: Option<usize>,
+    // Persona/discussion mode needs at least this many utterances per speaker (0 disables)
+    pub min_persona_utterances: u32,
+    // Cross-podcast search skips podcasts with fewer episodes (0 disables)
+    pub cross_podcast_min_episodes: usize,
+    // Chat requests per minute and client IP without an auth token (0 disables)
+    pub rate_limit_per_min: u32,
+    // Peers whose X-Forwarded-For/X-Real-IP names the client for rate limiting (RAG_TRUSTED_PROXIES)
+    pub trusted_proxies: Vec<IpAddr>,
+    // Let the chat model rerank the vector hits unless the request says otherwise (RAG_RERANK)
+    pub rerank_default: bool,
+    // Serve /metrics on this separate (internal) address instead of bind_addr (METRICS_BIND_ADDR)
+    pub metrics_bind_addr: Option<SocketAddr>,
 }
 
 impl AppConfig {
@@ -117,16 +319,36 @@ impl AppConfig {
             .or_else(|| settings_llm.and_then(|l| l.base_url.clone()))
             .ok_or_else(|| anyhow!("Missing LLM base URL (set LLM_BASE_URL or settings.json: llm.baseURL)"))?;
 
-        let llm_api_key = std::env::var("LLM_API_KEY")
+        let llm_provider = std::env::var("LLM_PROVIDER")
             .ok()
-            .or_else(|| settings_llm.and_then(|l| l.api_key.clone()))
-            .ok_or_else(|| anyhow!("Missing LLM API key (set LLM_API_KEY or settings.json: llm.apiKey)"))?;
+            .or_else(|| settings_llm.and_then(|l| l.provider.clone()))
+            .map(|p| LlmProvider::from_name(&p, "LLM_PROVIDER"))
+            .transpose()?
+            .unwrap_or_default();
 
-        if llm_api_key.trim().is_empty() || llm_api_key == "YOUR_API_KEY_HERE" {
-            return Err(anyhow!(
-                "LLM API key is missing/placeholder (set LLM_API_KEY or update settings.json: llm.apiKey)"
-            ));
-        }
+        // Embeddings default to the chat API
+        let embedding_base_url = std::env::var("EMBEDDING_BASE_URL")
+            .ok()
+            .or_else(|| settings_llm.and_then(|l| l.embedding_base_url.clone()))
+            .unwrap_or_else(|| llm_base_url.clone());
+        let embedding_provider = std::env::var("EMBEDDING_PROVIDER")
+            .ok()
+            .or_else(|| settings_llm.and_then(|l| l.embedding_provider.clone()))
+            .map(|p| LlmProvider::from_name(&p, "EMBEDDING_PROVIDER"))
+            .transpose()?
+            .unwrap_or(llm_provider);
+
+        let (llm_api_key, llm_available) = resolve_llm_api_key(
+            std::env::var("LLM_API_KEY")
+                .ok()
+                .or_else(|| settings_llm.and_then(|l| l.api_key.clone())),
+            env_flag("RAG_ALLOW_NO_KEY"),
+        )?;
+        // A separate embedding server may need its own key (or none, like a local Ollama)
+        let embedding_api_key = std::env::var("EMBEDDING_API_KEY")
+            .ok()
+            .or_else(|| settings_llm.and_then(|l| l.embedding_api_key.clone()))
+            .unwrap_or_else(|| llm_api_key.clone());
 
         let llm_model = std::env::var("LLM_MODEL")
             .ok()
@@ -148,6 +370,16 @@ impl AppConfig {
             .and_then(|s| s.parse::<usize>().ok())
             .unwrap_or(24_000);
 
+        let min_query_len = std::env::var("RAG_MIN_QUERY_LEN")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(2);
+
+        let min_score = std::env::var("RAG_MIN_SCORE")
+            .ok()
+            .and_then(|s| s.parse::<f32>().ok())
+            .filter(|v| v.is_finite());
+
         let auth_token = std::env::var("RAG_AUTH_TOKEN")
             .ok()
             .or_else(|| settings_rag.and_then(|r| r.auth_token.clone()))
@@ -160,25 +392,236 @@ impl AppConfig {
             .map(|s| s.trim().to_string())
             .filter(|s| !s.is_empty());
 
+        let podcast_auth_tokens =
+            load_podcast_auth_tokens(settings_rag.and_then(|r| r.podcast_auth_tokens.as_ref()));
+
+        // Extra exempt paths extend the defaults; the probe paths can't be removed
+        let mut auth_exempt_paths: Vec<String> = DEFAULT_AUTH_EXEMPT_PATHS.iter().map(|s| s.to_string()).collect();
+        let extra_exempt_paths: Vec<String> = std::env::var("RAG_AUTH_EXEMPT_PATHS")
+            .ok()
+            .map(|s| s.split(',').map(|p| p.to_string()).collect())
+            .or_else(|| settings_rag.and_then(|r| r.auth_exempt_paths.clone()))
+            .unwrap_or_default();
+        for path in extra_exempt_paths {
+            let path = path.trim().to_string();
+            if !path.is_empty() && !auth_exempt_paths.contains(&path) {
+                auth_exempt_paths.push(path);
+            }
+        }
+
+        let metadata_concurrency = std::env::var("RAG_METADATA_CONCURRENCY")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(32);
+
+        let search_timeout = Duration::from_millis(
+            std::env::var("RAG_SEARCH_TIMEOUT_MS")
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .filter(|&ms| ms > 0)
+                .unwrap_or(10_000),
+        );
+
+        let cross_model_policy = match std::env::var("RAG_CROSS_MODEL")
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "" | "lenient" => CrossModelPolicy::Lenient,
+            "strict" => CrossModelPolicy::Strict,
+            other => {
+                return Err(anyhow!(
+                    "Invalid RAG_CROSS_MODEL '{}' (expected 'strict' or 'lenient')",
+                    other
+                ))
+            }
+        };
+
+        let title_blend_weight = std::env::var("RAG_TITLE_BLEND_WEIGHT")
+            .ok()
+            .and_then(|s| s.parse::<f32>().ok())
+            .filter(|w| w.is_finite())
+            .map(|w| w.clamp(0.0, 1.0))
+            .unwrap_or(0.0);
+
+        let rag_cache_max_age = std::env::var("RAG_CACHE_MAX_AGE_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|&secs| secs > 0)
+            .map(Duration::from_secs);
+
+        let max_topics_per_episode = std::env::var("RAG_MAX_TOPICS_PER_EPISODE")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .filter(|&n| n > 0);
+
+        let min_persona_utterances = std::env::var("RAG_MIN_PERSONA_UTTERANCES")
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(0);
+
+        let cross_podcast_min_episodes = std::env::var("RAG_CROSS_PODCAST_MIN_EPISODES")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(0);
+
+        let rate_limit_per_min = std::env::var("RAG_RATE_LIMIT_PER_MIN")
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(0);
+
+        let trusted_proxies = std::env::var("RAG_TRUSTED_PROXIES")
+            .ok()
+            .map(|s| {
+                s.split(',')
+                    .map(str::trim)
+                    .filter(|p| !p.is_empty())
+                    .map(|p| p.parse::<IpAddr>().with_context(|| format!("Invalid IP '{p}' in RAG_TRUSTED_PROXIES")))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        let metrics_bind_addr = match std::env::var("METRICS_BIND_ADDR").ok().filter(|s| !s.trim().is_empty()) {
+            Some(s) => Some(
+                s.trim()
+                    .parse::<SocketAddr>()
+                    .with_context(|| format!("Invalid METRICS_BIND_ADDR '{s}' (expected host:port)"))?,
+            ),
+            None => None,
+        };
+
+        let default_temperatures = AnswerTemperatures::default();
+        let settings_temperatures = settings_rag.and_then(|r| r.temperatures.as_ref());
+        let answer_temperatures = AnswerTemperatures {
+            neutral: settings_temperatures.and_then(|t| t.neutral).unwrap_or(default_temperatures.neutral),
+            persona: settings_temperatures.and_then(|t| t.persona).unwrap_or(default_temperatures.persona),
+            discussion: settings_temperatures
+                .and_then(|t| t.discussion)
+                .unwrap_or(default_temperatures.discussion),
+        };
+
+        let chat_max_tokens = std::env::var("RAG_CHAT_MAX_TOKENS")
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .or_else(|| settings_rag.and_then(|r| r.max_tokens))
+            .filter(|&n| n > 0);
+
+        let transcript_lenient = env_flag("RAG_TRANSCRIPT_LENIENT");
+        let transcript_merge_gap_sec = std::env::var("RAG_TRANSCRIPT_MERGE_GAP_SEC")
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+            .filter(|gap| gap.is_finite() && *gap >= 0.0);
+        let dedup_items = env_flag("RAG_DEDUP_ITEMS");
+        let ann_enabled = env_flag("RAG_ANN");
+        let normalize_on_load = env_flag("RAG_NORMALIZE_ON_LOAD");
+        let rerank_default = env_flag("RAG_RERANK");
+        let embed_cache_enabled = !env_flag("RAG_NO_EMBED_CACHE");
+        let query_cache_ttl = Duration::from_secs(
+            std::env::var("RAG_QUERY_CACHE_TTL_SECS")
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .filter(|&secs| secs > 0)
+                .unwrap_or(600),
+        );
+
+        let embedding_dim_mismatch = match std::env::var("RAG_EMBEDDING_DIM_MISMATCH")
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "" | "error" => DimMismatchPolicy::Error,
+            "truncate" | "pad" | "matryoshka" => DimMismatchPolicy::Truncate,
+            other => {
+                return Err(anyhow!(
+                    "Invalid RAG_EMBEDDING_DIM_MISMATCH '{}' (expected 'error' or 'truncate')",
+                    other
+                ))
+            }
+        };
+
         Ok((
             Self {
                 bind_addr,
+                podcast_id,
                 episodes_dir,
                 speakers_dir,
                 llm_base_url: llm_base_url.trim_end_matches('/').to_string(),
+                llm_provider,
+                embedding_base_url: embedding_base_url.trim_end_matches('/').to_string(),
+                embedding_provider,
+                embedding_api_key,
                 llm_api_key,
+                llm_available,
                 llm_model,
                 embedding_model,
                 top_k,
                 max_context_chars,
+                min_query_len,
+                min_score,
                 auth_token,
                 stats_auth_token,
+                podcast_auth_tokens,
+                auth_exempt_paths,
+                transcript_lenient,
+                transcript_merge_gap_sec,
+                dedup_items,
+                ann_enabled,
+                normalize_on_load,
+                embedding_dim_mismatch,
+                metadata_concurrency,
+                answer_temperatures,
+                chat_max_tokens,
+                search_timeout,
+                embed_cache_enabled,
+                query_cache_ttl,
+                cross_model_policy,
+                title_blend_weight,
+                rag_cache_max_age,
+                max_topics_per_episode,
+                min_persona_utterances,
+                cross_podcast_min_episodes,
+                rate_limit_per_min,
+                trusted_proxies,
+                rerank_default,
+                metrics_bind_addr,
             },
             settings_source,
         ))
     }
 }
 
+impl AppConfig {
+    /// Whether `path` is on the auth exemption list (trailing slashes ignored)
+    pub fn is_auth_exempt(&self, path: &str) -> bool {
+        let path = path.trim_end_matches('/');
+        self.auth_exempt_paths
+            .iter()
+            .any(|p| p.trim_end_matches('/') == path)
+    }
+
+    /// Directory with the episode JSON, transcript and image files of `podcast_id`
+    pub fn episodes_dir_of(&self, podcast_id: &str) -> PathBuf {
+        if podcast_id == self.podcast_id {
+            self.episodes_dir.clone()
+        } else {
+            PathBuf::from(format!("podcasts/{}/episodes", podcast_id))
+        }
+    }
+
+    /// Directory with the speaker profiles and `index.json` of `podcast_id`
+    pub fn speakers_dir_of(&self, podcast_id: &str) -> PathBuf {
+        if podcast_id == self.podcast_id {
+            self.speakers_dir.clone()
+        } else {
+            PathBuf::from(format!("podcasts/{}/speakers", podcast_id))
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct AppState {
     pub cfg: AppConfig,
@@ -193,6 +636,150 @@ pub struct AppState {
     pub speaker_meta_cache: Cache<(String, String), CachedSpeakerMeta>,
     pub episode_topics_map_cache: Cache<String, CachedEpisodeTopicsMap>,
     pub episode_files_cache: Cache<(String, u32), CachedEpisodeFiles>,
+    // topic-taxonomy.json per podcast, invalidated by file mtime
+    pub taxonomy_cache: Cache<String, CachedTaxonomy>,
+    // Raw query embeddings keyed by (embedding model, query)
+    pub query_embedding_cache: Cache<(String, String), Arc<Vec<f32>>>,
+    // Episode title embeddings per podcast (backed by a sidecar file)
+    pub title_embedding_cache: Cache<String, Arc<TitleEmbeddings>>,
+    // Index embedding model per podcast where it differs from EMBEDDING_MODEL
+    pub embedding_model_mismatches: Cache<String, String>,
+    // Last LLM reachability probe result for /api/health (short TTL)
+    pub llm_health_cache: Cache<(), bool>,
     pub analytics_db: Arc<AnalyticsDb>,
+    // Per-IP throttle for the LLM-backed chat endpoints
+    pub chat_rate_limiter: Arc<crate::handlers::rate_limit::RateLimiter>,
+    // Prometheus counters and histograms for /metrics
+    pub metrics: Arc<crate::handlers::metrics::Metrics>,
+    // Set once startup cache warming has finished (readiness probe)
+    pub ready: Arc<AtomicBool>,
 }
 
+#[cfg(test)]
+impl AppConfig {
+    /// Config with defaults and a dummy LLM endpoint for unit tests
+    pub fn for_tests() -> Self {
+        Self {
+            bind_addr: "127.0.0.1:0".parse().unwrap(),
+            podcast_id: "freakshow".to_string(),
+            episodes_dir: PathBuf::from("podcasts/freakshow/episodes"),
+            speakers_dir: PathBuf::from("podcasts/freakshow/speakers"),
+            llm_base_url: "http://127.0.0.1:9".to_string(),
+            llm_provider: LlmProvider::OpenAi,
+            embedding_base_url: "http://127.0.0.1:9".to_string(),
+            embedding_provider: LlmProvider::OpenAi,
+            embedding_api_key: "test-key".to_string(),
+            llm_api_key: "test-key".to_string(),
+            llm_available: true,
+            llm_model: "test-model".to_string(),
+            embedding_model: "test-embedding".to_string(),
+            top_k: 6,
+            max_context_chars: 24_000,
+            min_query_len: 2,
+            min_score: None,
+            auth_token: None,
+            stats_auth_token: None,
+            podcast_auth_tokens: HashMap::new(),
+            auth_exempt_paths: DEFAULT_AUTH_EXEMPT_PATHS.iter().map(|s| s.to_string()).collect(),
+            transcript_lenient: false,
+            transcript_merge_gap_sec: None,
+            dedup_items: false,
+            ann_enabled: false,
+            normalize_on_load: false,
+            embedding_dim_mismatch: DimMismatchPolicy::Error,
+            metadata_concurrency: 32,
+            answer_temperatures: AnswerTemperatures::default(),
+            chat_max_tokens: None,
+            search_timeout: Duration::from_secs(10),
+            embed_cache_enabled: true,
+            query_cache_ttl: Duration::from_secs(600),
+            cross_model_policy: CrossModelPolicy::Lenient,
+            title_blend_weight: 0.0,
+            rag_cache_max_age: None,
+            max_topics_per_episode: None,
+            min_persona_utterances: 0,
+            cross_podcast_min_episodes: 0,
+            rate_limit_per_min: 0,
+            trusted_proxies: Vec::new(),
+            rerank_default: false,
+            metrics_bind_addr: None,
+        }
+    }
+}
+
+#[cfg(test)]
+impl AppState {
+    /// State with empty caches and a throwaway analytics database for unit tests
+    pub fn for_tests(cfg: AppConfig) -> Self {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static NEXT_DB: AtomicUsize = AtomicUsize::new(0);
+
+        let db_path = std::env::temp_dir().join(format!(
+            "pod-insights-test-{}-{}.db",
+            std::process::id(),
+            NEXT_DB.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_file(&db_path);
+
+        Self {
+            cfg,
+            http: Client::new(),
+            transcript_cache: Cache::new(100),
+            rag_cache: Cache::new(10),
+            episode_metadata_cache: Cache::new(100),
+            episode_list_cache: Cache::new(10),
+            speaker_profile_cache: Cache::new(10),
+            speakers_index_cache: Cache::new(10),
+            speaker_meta_cache: Cache::new(10),
+            episode_topics_map_cache: Cache::new(10),
+            episode_files_cache: Cache::new(100),
+            taxonomy_cache: Cache::new(10),
+            query_embedding_cache: Cache::new(100),
+            title_embedding_cache: Cache::new(10),
+            embedding_model_mismatches: Cache::new(10),
+            llm_health_cache: Cache::new(1),
+            analytics_db: Arc::new(AnalyticsDb::new(&db_path, None).expect("test analytics db")),
+            chat_rate_limiter: Arc::new(crate::handlers::rate_limit::RateLimiter::new(0)),
+            metrics: Arc::default(),
+            ready: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_key_allowed_only_in_degraded_mode() {
+        assert!(resolve_llm_api_key(None, false).is_err());
+        assert!(resolve_llm_api_key(Some("YOUR_API_KEY_HERE".to_string()), false).is_err());
+
+        let (key, available) = resolve_llm_api_key(None, true).unwrap();
+        assert!(key.is_empty());
+        assert!(!available);
+
+        let (key, available) = resolve_llm_api_key(Some("sk-test".to_string()), true).unwrap();
+        assert_eq!(key, "sk-test");
+        assert!(available);
+    }
+
+    #[test]
+    fn test_unknown_provider_is_rejected() {
+        assert_eq!(LlmProvider::from_name(" Ollama ", "LLM_PROVIDER").unwrap(), LlmProvider::Ollama);
+        assert_eq!(LlmProvider::from_name("openai", "LLM_PROVIDER").unwrap(), LlmProvider::OpenAi);
+        let err = LlmProvider::from_name("olama", "EMBEDDING_PROVIDER").unwrap_err();
+        assert!(err.to_string().contains("EMBEDDING_PROVIDER 'olama'"), "{err}");
+    }
+
+    #[test]
+    fn test_dir_overrides_apply_to_default_podcast_only() {
+        let mut cfg = AppConfig::for_tests();
+        cfg.episodes_dir = PathBuf::from("./episodes");
+        cfg.speakers_dir = PathBuf::from("./speakers");
+        assert_eq!(cfg.episodes_dir_of("freakshow"), PathBuf::from("./episodes"));
+        assert_eq!(cfg.speakers_dir_of("freakshow"), PathBuf::from("./speakers"));
+        assert_eq!(cfg.episodes_dir_of("lnp"), PathBuf::from("podcasts/lnp/episodes"));
+        assert_eq!(cfg.speakers_dir_of("lnp"), PathBuf::from("podcasts/lnp/speakers"));
+    }
+}
@@ -0,0 +1,82 @@
+//! Reading of `.json.gz` data files next to (or instead of) the plain JSON ones.
+
+use anyhow::{Context, Result};
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+const MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// `rag-embeddings.json` -> `rag-embeddings.json.gz`
+pub fn gz_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+/// The compressed sibling of `path` when it exists, otherwise `path` itself
+pub async fn prefer_gz(path: PathBuf) -> PathBuf {
+    let gz = gz_path(&path);
+    if tokio::fs::metadata(&gz).await.is_ok() {
+        gz
+    } else {
+        path
+    }
+}
+
+/// Open a data file for streamed reading; gzip (by `.gz` extension or magic bytes, concatenated
+/// members included) is decompressed on the fly.
+pub fn open_maybe_gzip(path: &Path) -> Result<Box<dyn BufRead + Send>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let has_gz_ext = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("gz"));
+    let sniffed = reader
+        .fill_buf()
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    if !has_gz_ext && !sniffed.starts_with(&MAGIC) {
+        return Ok(Box::new(reader));
+    }
+    Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::{Read, Write};
+
+    fn compress(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_open_maybe_gzip_sniffs_magic_bytes() {
+        let dir = std::env::temp_dir().join(format!("gzip-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let read = |name: &str, bytes: &[u8]| {
+            let path = dir.join(name);
+            std::fs::write(&path, bytes).unwrap();
+            let mut s = String::new();
+            open_maybe_gzip(&path)?.read_to_string(&mut s)?;
+            anyhow::Ok(s)
+        };
+        assert_eq!(read("plain.json", b"[1,2]").unwrap(), "[1,2]");
+        assert_eq!(read("packed.json.gz", &compress(b"[3]")).unwrap(), "[3]");
+        // Compressed content without the extension is still recognised
+        assert_eq!(read("misnamed.json", &compress(b"[4]")).unwrap(), "[4]");
+        // Concatenated members decode to the concatenated data
+        let mut two = compress(b"[5,");
+        two.extend_from_slice(&compress(b"6]"));
+        assert_eq!(read("two.json.gz", &two).unwrap(), "[5,6]");
+
+        let mut broken = compress(b"[7]");
+        let crc = broken.len() - 8;
+        broken[crc] ^= 0xff;
+        assert!(read("broken.json.gz", &broken).is_err());
+        assert!(open_maybe_gzip(&dir.join("missing.json")).is_err());
+        assert_eq!(gz_path(Path::new("db/lnp/rag-embeddings.json")), PathBuf::from("db/lnp/rag-embeddings.json.gz"));
+    }
+}
@@ -1,22 +1,24 @@
 use anyhow::{Context, Result};
 use axum::{
-    extract::{Query, State},
-    http::{header, HeaderMap},
+    extract::{Path as UrlPath, Query, State},
+    http::{header, HeaderMap, Uri},
     response::IntoResponse,
     Json,
 };
-use chrono::Utc;
+use chrono::{NaiveDate, Utc};
 use moka::future::Cache;
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Mutex;
 
 use crate::config::AppState;
+use crate::handlers::auth::{carries_token, is_auth_ok};
 
 #[derive(Debug, Deserialize)]
 pub struct TrackRequest {
@@ -40,6 +42,16 @@ pub struct TrackEpisodePlayRequest {
     pub user_agent: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct TrackEventRequest {
+    pub event_type: String,
+    pub metadata: Option<serde_json::Value>,
+    pub path: Option<String>,
+    pub podcast: Option<String>,
+    pub episode: Option<String>,
+    pub user_agent: Option<String>,
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct AnalyticsStats {
     pub unique_users: i64,
@@ -51,6 +63,17 @@ pub struct AnalyticsStats {
     pub top_episodes: Vec<EpisodeStats>,
     pub top_played_episodes: Vec<EpisodeStats>,
     pub locations: Vec<LocationStats>,
+    pub top_events: Vec<EventStats>,
+    /// One entry per day of the window, oldest first (zero-activity days included)
+    pub daily: Vec<DailyStats>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct DailyStats {
+    pub date: String,
+    pub page_views: i64,
+    pub episode_plays: i64,
+    pub unique_users: i64,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -74,6 +97,65 @@ pub struct EpisodeStats {
     pub episode: String,
     pub views: i64,
     pub unique_users: i64,
+    /// Time-decayed play count used for ranking (only with ANALYTICS_PLAY_HALF_LIFE_DAYS)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weighted_views: Option<f64>,
+}
+
+/// One row of `episode_plays` as needed for decayed ranking
+struct PlayRow {
+    podcast: String,
+    episode: String,
+    user_fingerprint: String,
+    created_at: String,
+}
+
+/// Rank episodes by plays where each play counts 0.5^(age / half_life); unparseable timestamps count fully
+fn rank_plays_with_decay(
+    rows: &[PlayRow],
+    now: chrono::DateTime<Utc>,
+    half_life_days: f64,
+    limit: usize,
+) -> Vec<EpisodeStats> {
+    let mut per_episode: HashMap<(&str, &str), (f64, i64, std::collections::HashSet<&str>)> = HashMap::new();
+    for row in rows {
+        let age_days = chrono::DateTime::parse_from_rfc3339(&row.created_at)
+            .map(|t| (now - t.with_timezone(&Utc)).num_seconds().max(0) as f64 / 86_400.0)
+            .unwrap_or(0.0);
+        let weight = 0.5f64.powf(age_days / half_life_days);
+        let entry = per_episode
+            .entry((row.podcast.as_str(), row.episode.as_str()))
+            .or_default();
+        entry.0 += weight;
+        entry.1 += 1;
+        entry.2.insert(row.user_fingerprint.as_str());
+    }
+
+    let mut stats: Vec<EpisodeStats> = per_episode
+        .into_iter()
+        .map(|((podcast, episode), (weighted, views, users))| EpisodeStats {
+            podcast: podcast.to_string(),
+            episode: episode.to_string(),
+            views,
+            unique_users: users.len() as i64,
+            weighted_views: Some(weighted),
+        })
+        .collect();
+    stats.sort_by(|a, b| {
+        b.weighted_views
+            .partial_cmp(&a.weighted_views)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(b.views.cmp(&a.views))
+    });
+    stats.truncate(limit);
+    stats
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct EventStats {
+    pub event_type: String,
+    pub count: i64,
+    pub unique_users: i64,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -88,49 +170,122 @@ pub struct LocationStats {
     pub longitude: Option<f64>,
 }
 
-pub struct AnalyticsDb {
-    conn: Arc<Mutex<Connection>>, // Write connection
-    read_conn: Arc<Mutex<Connection>>, // Read-only connection for stats queries
-    geoip_db: Option<maxminddb::Reader<Vec<u8>>>,
-    stats_cache: Cache<Option<i64>, AnalyticsStats>,
-    city_coordinates: Arc<std::collections::HashMap<String, (f64, f64)>>, // Key: "country-city", Value: (lat, lng)
+/// How much location detail is stored per page view (ANALYTICS_LOCATION_GRANULARITY)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LocationGranularity {
+    #[default]
+    City,
+    Country,
+    None,
 }
 
-impl AnalyticsDb {
-    pub fn new(db_path: &PathBuf, geoip_db_path: Option<&PathBuf>) -> Result<Self> {
-        // Create database directory if it doesn't exist
-        if let Some(parent) = db_path.parent() {
-            std::fs::create_dir_all(parent)
-                .with_context(|| format!("Failed to create database directory: {:?}", parent))?;
+impl LocationGranularity {
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("ANALYTICS_LOCATION_GRANULARITY")
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "" | "city" => Ok(Self::City),
+            "country" => Ok(Self::Country),
+            "none" => Ok(Self::None),
+            other => Err(anyhow::anyhow!(
+                "Invalid ANALYTICS_LOCATION_GRANULARITY '{}' (expected country, city or none)",
+                other
+            )),
         }
+    }
 
-        let mut conn = Connection::open(db_path)
-            .with_context(|| format!("Failed to open database: {:?}", db_path))?;
+    /// Drop the location parts this granularity must not store
+    fn apply(self, location: (Option<String>, Option<String>)) -> (Option<String>, Option<String>) {
+        match self {
+            Self::City => location,
+            Self::Country => (location.0, None),
+            Self::None => (None, None),
+        }
+    }
+}
 
-        // Enable WAL mode for better concurrency (reads don't block writes)
-        conn.pragma_update(None, "journal_mode", "WAL")?;
-        
-        // Set synchronous mode to NORMAL for better write performance
-        // (WAL mode makes this safe - data is still durable)
-        conn.pragma_update(None, "synchronous", "NORMAL")?;
-        
-        // Increase cache size for better performance (default is 2MB, set to 64MB)
-        conn.pragma_update(None, "cache_size", "-65536")?; // Negative = KB, so -65536 = 64MB
-        
-        // Set busy timeout to handle concurrent access gracefully
-        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+/// (country, city, views, unique_users) as returned by the location query
+type LocationRow = (Option<String>, Option<String>, i64, i64);
 
-        // Create read-only connection for stats queries (allows concurrent reads)
-        let mut read_conn = Connection::open(db_path)
-            .with_context(|| format!("Failed to open read-only database: {:?}", db_path))?;
-        
-        // Configure read-only connection with same optimizations
-        read_conn.pragma_update(None, "journal_mode", "WAL")?;
-        read_conn.pragma_update(None, "synchronous", "NORMAL")?;
-        read_conn.pragma_update(None, "cache_size", "-65536")?;
-        read_conn.busy_timeout(std::time::Duration::from_secs(5))?;
+/// Split off locations with fewer than `min_views` views and sum them into one "other" row
+/// (unique users are summed per location, so they may overcount).
+fn bucket_rare_locations(rows: Vec<LocationRow>, min_views: i64) -> (Vec<LocationRow>, Option<LocationStats>) {
+    let (kept, rare): (Vec<LocationRow>, Vec<LocationRow>) =
+        rows.into_iter().partition(|(_, _, views, _)| *views >= min_views);
+    if rare.is_empty() {
+        return (kept, None);
+    }
+    let other = LocationStats {
+        country: Some("other".to_string()),
+        city: None,
+        views: rare.iter().map(|r| r.2).sum(),
+        unique_users: rare.iter().map(|r| r.3).sum(),
+        latitude: None,
+        longitude: None,
+    };
+    (kept, Some(other))
+}
+
+/// Fields starting like a formula get a leading `'` so spreadsheets show tracked paths verbatim
+/// instead of evaluating them
+fn spreadsheet_safe(field: &str) -> std::borrow::Cow<'_, str> {
+    if field.starts_with(['=', '+', '-', '@']) {
+        format!("'{field}").into()
+    } else {
+        field.into()
+    }
+}
 
-        // Create tables
+/// Top pages, podcasts and episodes of `stats` as one flat CSV table (RFC 4180, CRLF line ends)
+fn stats_csv(stats: &AnalyticsStats) -> Result<String> {
+    let mut w = csv::WriterBuilder::new().terminator(csv::Terminator::CRLF).from_writer(Vec::new());
+    w.write_record(["section", "name", "detail", "views", "unique_users"])?;
+    let mut row = |section: &str, name: &str, detail: &str, views: i64, unique_users: i64| {
+        w.write_record([
+            section,
+            &spreadsheet_safe(name),
+            &spreadsheet_safe(detail),
+            &views.to_string(),
+            &unique_users.to_string(),
+        ])
+    };
+    for p in &stats.top_pages {
+        row("page", &p.path, p.route_name.as_deref().unwrap_or(""), p.views, p.unique_users)?;
+    }
+    for (section, podcasts) in [("podcast", &stats.top_podcasts), ("played_podcast", &stats.top_played_podcasts)] {
+        for p in podcasts {
+            row(section, &p.podcast, "", p.views, p.unique_users)?;
+        }
+    }
+    for (section, episodes) in [("episode", &stats.top_episodes), ("played_episode", &stats.top_played_episodes)] {
+        for e in episodes {
+            row(section, &e.podcast, &e.episode, e.views, e.unique_users)?;
+        }
+    }
+    Ok(String::from_utf8(w.into_inner()?)?)
+}
+
+/// Default user agent substrings (lowercase) that mark crawlers, scripts and headless browsers
+pub const BOT_UA_PATTERNS: &[&str] = &["googlebot", "bingbot", "bot", "spider", "crawler", "curl", "headless"];
+
+/// Default play de-duplication window (PLAY_DEDUP_MINUTES)
+pub const DEFAULT_PLAY_DEDUP_MINUTES: i64 = 30;
+
+/// Whether `user_agent` contains one of the lowercase `patterns` (case-insensitive)
+fn is_bot(user_agent: &str, patterns: &[String]) -> bool {
+    let ua = user_agent.to_lowercase();
+    patterns.iter().any(|p| ua.contains(p.as_str()))
+}
+
+/// Schema migrations in order; `MIGRATIONS[i]` upgrades a database at `user_version` i to i + 1.
+/// Append new migrations, never edit applied ones.
+const MIGRATIONS: &[fn(&Connection) -> Result<()>] = &[
+    // 1: tables and indexes as of the first versioned schema (CREATE IF NOT EXISTS, so databases
+    // created before versioning pass through unchanged)
+    |conn| {
         conn.execute(
             "CREATE TABLE IF NOT EXISTS page_views (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -219,6 +374,162 @@ impl AnalyticsDb {
             [],
         )?;
 
+        // Create events table for custom frontend events (e.g. "share clicked") with a JSON payload
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_fingerprint TEXT NOT NULL,
+                event_type TEXT NOT NULL,
+                metadata TEXT,
+                path TEXT,
+                podcast TEXT,
+                episode TEXT,
+                user_agent TEXT,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_events_stats ON events(created_at, event_type)",
+            [],
+        )?;
+
+        Ok(())
+    },
+    // 2: bot flag (databases created by an unversioned build may already have it)
+    |conn| {
+        for table in ["page_views", "episode_plays", "events"] {
+            add_column_if_missing(conn, table, "is_bot", "INTEGER NOT NULL DEFAULT 0")?;
+        }
+        Ok(())
+    },
+];
+
+/// Apply the migrations newer than the database's `user_version`, each in its own transaction
+fn run_migrations(conn: &mut Connection) -> Result<()> {
+    let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get::<_, i64>(0))?
+        .try_into()
+        .unwrap_or(0);
+    if version > MIGRATIONS.len() {
+        tracing::warn!("Analytics database schema version {} is newer than this build ({})", version, MIGRATIONS.len());
+    }
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        migration(&tx).with_context(|| format!("Analytics migration {} failed", i + 1))?;
+        tx.pragma_update(None, "user_version", (i + 1) as i64)?;
+        tx.commit()?;
+        tracing::info!("Applied analytics migration {}", i + 1);
+    }
+    Ok(())
+}
+
+/// `ALTER TABLE ... ADD COLUMN` unless `table` already has `column`
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+        params![table, column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl}"), [])
+            .with_context(|| format!("Failed to add column {column} to {table}"))?;
+        tracing::info!("Added column {}.{}", table, column);
+    }
+    Ok(())
+}
+
+/// (day as "YYYY-MM-DD", count, unique users) as returned by the daily queries
+type DailyRow = (String, i64, i64);
+
+/// Merge daily page views and plays into one series from `first_day` (default: the earliest day
+/// with activity) through `last_day`; days without activity get zeros. Unique users come from
+/// page views, like the totals.
+fn merge_daily_series(views: Vec<DailyRow>, plays: Vec<DailyRow>, first_day: Option<NaiveDate>, last_day: NaiveDate) -> Vec<DailyStats> {
+    let parse = |day: &str| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok();
+    let mut by_day: std::collections::BTreeMap<NaiveDate, (i64, i64, i64)> = std::collections::BTreeMap::new();
+    for (day, count, users) in views {
+        if let Some(day) = parse(&day) {
+            let entry = by_day.entry(day).or_default();
+            entry.0 += count;
+            entry.2 += users;
+        }
+    }
+    for (day, count, _) in plays {
+        if let Some(day) = parse(&day) {
+            by_day.entry(day).or_default().1 += count;
+        }
+    }
+
+    let Some(start) = first_day.or_else(|| by_day.keys().next().copied()) else {
+        return Vec::new();
+    };
+    let end = by_day.keys().next_back().map_or(last_day, |&d| d.max(last_day));
+    start
+        .iter_days()
+        .take_while(|d| *d <= end)
+        .map(|d| {
+            let (page_views, episode_plays, unique_users) = by_day.get(&d).copied().unwrap_or_default();
+            DailyStats {
+                date: d.format("%Y-%m-%d").to_string(),
+                page_views,
+                episode_plays,
+                unique_users,
+            }
+        })
+        .collect()
+}
+
+pub struct AnalyticsDb {
+    conn: Arc<Mutex<Connection>>, // Write connection
+    read_conn: Arc<Mutex<Connection>>, // Read-only connection for stats queries
+    geoip_db: Option<maxminddb::Reader<Vec<u8>>>,
+    stats_cache: Cache<Option<i64>, AnalyticsStats>,
+    city_coordinates: Arc<std::collections::HashMap<String, (f64, f64)>>, // Key: "country-city", Value: (lat, lng)
+    location_granularity: LocationGranularity,
+    play_half_life_days: Option<f64>,
+    location_min_views: i64,
+    bot_ua_patterns: Vec<String>, // Lowercase user agent substrings flagged as bots
+    play_dedup_minutes: i64, // Repeat plays of an episode by the same user within this window are dropped
+}
+
+impl AnalyticsDb {
+    pub fn new(db_path: &PathBuf, geoip_db_path: Option<&PathBuf>) -> Result<Self> {
+        // Create database directory if it doesn't exist
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create database directory: {:?}", parent))?;
+        }
+
+        let mut conn = Connection::open(db_path)
+            .with_context(|| format!("Failed to open database: {:?}", db_path))?;
+
+        // Enable WAL mode for better concurrency (reads don't block writes)
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        
+        // Set synchronous mode to NORMAL for better write performance
+        // (WAL mode makes this safe - data is still durable)
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        
+        // Increase cache size for better performance (default is 2MB, set to 64MB)
+        conn.pragma_update(None, "cache_size", "-65536")?; // Negative = KB, so -65536 = 64MB
+        
+        // Set busy timeout to handle concurrent access gracefully
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+
+        // Create read-only connection for stats queries (allows concurrent reads)
+        let read_conn = Connection::open(db_path)
+            .with_context(|| format!("Failed to open read-only database: {:?}", db_path))?;
+        
+        // Configure read-only connection with same optimizations
+        read_conn.pragma_update(None, "journal_mode", "WAL")?;
+        read_conn.pragma_update(None, "synchronous", "NORMAL")?;
+        read_conn.pragma_update(None, "cache_size", "-65536")?;
+        read_conn.busy_timeout(std::time::Duration::from_secs(5))?;
+
+        // Create or upgrade the schema
+        run_migrations(&mut conn)?;
+
         // Initialize stats cache (5 minute TTL, 1 minute idle)
         let stats_cache = Cache::builder()
             .max_capacity(10) // Cache up to 10 different time ranges
@@ -255,63 +566,119 @@ impl AnalyticsDb {
             geoip_db,
             stats_cache,
             city_coordinates: Arc::new(city_coordinates),
+            location_granularity: LocationGranularity::default(),
+            play_half_life_days: None,
+            location_min_views: 1,
+            bot_ua_patterns: BOT_UA_PATTERNS.iter().map(|p| p.to_string()).collect(),
+            play_dedup_minutes: DEFAULT_PLAY_DEDUP_MINUTES,
         })
     }
 
+    /// Rank top played episodes with exponential time decay (None = all-time counts)
+    pub fn with_play_half_life_days(mut self, half_life_days: Option<f64>) -> Self {
+        self.play_half_life_days = half_life_days.filter(|d| *d > 0.0);
+        self
+    }
+
+    pub fn with_location_granularity(mut self, granularity: LocationGranularity) -> Self {
+        self.location_granularity = granularity;
+        self
+    }
+
+    /// Report locations with fewer views than this only as part of an aggregated "other" row
+    pub fn with_location_min_views(mut self, min_views: i64) -> Self {
+        self.location_min_views = min_views.max(1);
+        self
+    }
+
+    /// Count a user's repeated plays of an episode (reloads, scrubbing) only once per window (0 disables)
+    pub fn with_play_dedup_minutes(mut self, minutes: i64) -> Self {
+        self.play_dedup_minutes = minutes.max(0);
+        self
+    }
+
+    /// Replace the user agent substrings that flag a request as a bot (matched case-insensitively)
+    pub fn with_bot_ua_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.bot_ua_patterns = patterns
+            .into_iter()
+            .map(|p| p.trim().to_lowercase())
+            .filter(|p| !p.is_empty())
+            .collect();
+        self
+    }
+
+    /// Write the last day's stats to `<dir>/YYYY-MM-DD.json` (UTC date), replacing an earlier
+    /// snapshot of the same day
+    pub async fn write_stats_snapshot(&self, dir: &Path) -> Result<PathBuf> {
+        let stats = self.get_stats(Some(1)).await?;
+        let now = Utc::now();
+        let snapshot = serde_json::json!({
+            "date": now.format("%Y-%m-%d").to_string(),
+            "generated_at": now.to_rfc3339(),
+            "days": 1,
+            "stats": stats,
+        });
+        tokio::fs::create_dir_all(dir)
+            .await
+            .with_context(|| format!("Failed to create snapshot directory: {:?}", dir))?;
+        let path = dir.join(format!("{}.json", now.format("%Y-%m-%d")));
+        tokio::fs::write(&path, serde_json::to_vec_pretty(&snapshot)?)
+            .await
+            .with_context(|| format!("Failed to write snapshot: {:?}", path))?;
+        Ok(path)
+    }
+
     fn load_city_coordinates() -> Result<HashMap<String, (f64, f64)>> {
         let csv_path = PathBuf::from("worldcities.csv");
         if !csv_path.exists() {
             return Err(anyhow::anyhow!("worldcities.csv not found"));
         }
 
-        let mut coordinates = HashMap::new();
         let content = std::fs::read_to_string(&csv_path)
-            .with_context(|| format!("Failed to read worldcities.csv"))?;
-        
-        let mut lines = content.lines();
-        // Skip header
-        lines.next();
-        
-        for line in lines {
-            if line.trim().is_empty() {
-                continue;
-            }
-            
-            // Parse CSV line (handling quoted fields)
-            let fields: Vec<String> = line
-                .split(',')
-                .map(|s| {
-                    s.trim_matches('"').trim().to_string()
-                })
-                .collect();
-            
-            if fields.len() < 6 {
+            .with_context(|| "Failed to read worldcities.csv".to_string())?;
+        let coordinates = Self::parse_city_coordinates(&content)?;
+
+        tracing::info!("Loaded {} city coordinates from worldcities.csv", coordinates.len());
+        Ok(coordinates)
+    }
+
+    /// "COUNTRY-CITY" (uppercase) -> (lat, lng), also keyed by `city_ascii` where it differs.
+    /// Columns are looked up by header name, so their order doesn't matter.
+    fn parse_city_coordinates(content: &str) -> Result<HashMap<String, (f64, f64)>> {
+        let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(content.as_bytes());
+        let header = reader.headers().context("Failed to read worldcities.csv header")?.clone();
+        let column = |name: &str| {
+            header
+                .iter()
+                .position(|h| h.trim() == name)
+                .ok_or_else(|| anyhow::anyhow!("worldcities.csv has no '{}' column", name))
+        };
+        let (city_col, ascii_col, lat_col, lng_col, iso2_col) =
+            (column("city")?, column("city_ascii")?, column("lat")?, column("lng")?, column("iso2")?);
+
+        let mut coordinates = HashMap::new();
+        for fields in reader.records() {
+            let fields = fields.context("Failed to parse worldcities.csv")?;
+            let field = |i: usize| fields.get(i).map(|s| s.trim()).filter(|s| !s.is_empty());
+            let lat = field(lat_col).and_then(|s| s.parse::<f64>().ok());
+            let lng = field(lng_col).and_then(|s| s.parse::<f64>().ok());
+            let (Some(city_name), Some(lat), Some(lng), Some(country_code)) = (field(city_col), lat, lng, field(iso2_col)) else {
                 continue;
-            }
-            
-            // Format: city, city_ascii, lat, lng, country, iso2, ...
-            let city = fields.get(0).map(|s| s.trim().to_string());
-            let lat_str = fields.get(2).and_then(|s| s.parse::<f64>().ok());
-            let lng_str = fields.get(3).and_then(|s| s.parse::<f64>().ok());
-            let iso2 = fields.get(5).map(|s| s.trim().to_uppercase());
-            
-            if let (Some(city_name), Some(lat), Some(lng), Some(country_code)) = (city, lat_str, lng_str, iso2) {
-                // Create lookup key: "COUNTRY-CITY" (uppercase for consistency)
-                let key = format!("{}-{}", country_code, city_name.to_uppercase());
-                coordinates.insert(key, (lat, lng));
-                
-                // Also add city_ascii variant if different
-                if let Some(city_ascii) = fields.get(1) {
-                    let city_ascii_upper = city_ascii.trim().to_uppercase();
-                    if city_ascii_upper != city_name.to_uppercase() {
-                        let key_ascii = format!("{}-{}", country_code, city_ascii_upper);
-                        coordinates.insert(key_ascii, (lat, lng));
-                    }
+            };
+            let country_code = country_code.to_uppercase();
+
+            // Create lookup key: "COUNTRY-CITY" (uppercase for consistency)
+            let city_upper = city_name.to_uppercase();
+            coordinates.insert(format!("{}-{}", country_code, city_upper), (lat, lng));
+
+            // Also add city_ascii variant if different
+            if let Some(city_ascii) = field(ascii_col) {
+                let city_ascii_upper = city_ascii.to_uppercase();
+                if city_ascii_upper != city_upper {
+                    coordinates.insert(format!("{}-{}", country_code, city_ascii_upper), (lat, lng));
                 }
             }
         }
-        
-        tracing::info!("Loaded {} city coordinates from worldcities.csv", coordinates.len());
         Ok(coordinates)
     }
 
@@ -363,15 +730,31 @@ impl AnalyticsDb {
         req: TrackRequest,
         ip: String,
         user_agent: String,
+    ) -> Result<()> {
+        // Skip the GeoIP lookup entirely when no location may be stored
+        let location = match self.location_granularity {
+            LocationGranularity::None => (None, None),
+            _ => self.lookup_location(&ip),
+        };
+        self.insert_page_view(req, ip, user_agent, location).await
+    }
+
+    async fn insert_page_view(
+        &self,
+        req: TrackRequest,
+        ip: String,
+        user_agent: String,
+        location: (Option<String>, Option<String>),
     ) -> Result<()> {
         let fingerprint = Self::get_user_fingerprint(&ip, &user_agent);
-        let (country, city) = self.lookup_location(&ip);
+        let (country, city) = self.location_granularity.apply(location);
         let created_at = Utc::now().to_rfc3339();
+        let bot = is_bot(&user_agent, &self.bot_ua_patterns);
 
         let conn = self.conn.lock().await;
         conn.execute(
-            "INSERT INTO page_views (user_fingerprint, path, route_name, podcast, episode, country, city, referrer, user_agent, ip_address, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            "INSERT INTO page_views (user_fingerprint, path, route_name, podcast, episode, country, city, referrer, user_agent, ip_address, created_at, is_bot)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
             params![
                 fingerprint,
                 req.path,
@@ -383,7 +766,8 @@ impl AnalyticsDb {
                 req.referrer,
                 user_agent,
                 ip,
-                created_at
+                created_at,
+                bot
             ],
         )?;
 
@@ -398,21 +782,71 @@ impl AnalyticsDb {
         req: TrackEpisodePlayRequest,
         ip: String,
         user_agent: String,
+    ) -> Result<bool> {
+        let fingerprint = Self::get_user_fingerprint(&ip, &user_agent);
+        let now = Utc::now();
+        let created_at = now.to_rfc3339();
+        let bot = is_bot(&user_agent, &self.bot_ua_patterns);
+
+        let conn = self.conn.lock().await;
+        // Already counted within the window: don't record the play again
+        if self.play_dedup_minutes > 0 {
+            let since = (now - chrono::Duration::minutes(self.play_dedup_minutes)).to_rfc3339();
+            let recent: bool = conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM episode_plays
+                 WHERE user_fingerprint = ?1 AND podcast = ?2 AND episode = ?3 AND created_at >= ?4)",
+                params![fingerprint, req.podcast, req.episode, since],
+                |row| row.get(0),
+            )?;
+            if recent {
+                return Ok(false);
+            }
+        }
+        conn.execute(
+            "INSERT INTO episode_plays (user_fingerprint, podcast, episode, user_agent, ip_address, created_at, is_bot)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                fingerprint,
+                req.podcast,
+                req.episode,
+                user_agent,
+                ip,
+                created_at,
+                bot
+            ],
+        )?;
+
+        // Invalidate stats cache since we added new data
+        self.stats_cache.invalidate_all();
+
+        Ok(true)
+    }
+
+    pub async fn track_event(
+        &self,
+        req: TrackEventRequest,
+        ip: String,
+        user_agent: String,
     ) -> Result<()> {
         let fingerprint = Self::get_user_fingerprint(&ip, &user_agent);
         let created_at = Utc::now().to_rfc3339();
+        let metadata = req.metadata.as_ref().map(|m| m.to_string());
+        let bot = is_bot(&user_agent, &self.bot_ua_patterns);
 
         let conn = self.conn.lock().await;
         conn.execute(
-            "INSERT INTO episode_plays (user_fingerprint, podcast, episode, user_agent, ip_address, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            "INSERT INTO events (user_fingerprint, event_type, metadata, path, podcast, episode, user_agent, created_at, is_bot)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
             params![
                 fingerprint,
+                req.event_type,
+                metadata,
+                req.path,
                 req.podcast,
                 req.episode,
                 user_agent,
-                ip,
-                created_at
+                created_at,
+                bot
             ],
         )?;
 
@@ -422,6 +856,24 @@ impl AnalyticsDb {
         Ok(())
     }
 
+    /// Delete all analytics rows of one user (GDPR erasure); returns the number of rows removed
+    pub async fn delete_user(&self, fingerprint: &str) -> Result<usize> {
+        let mut conn = self.conn.lock().await;
+        let tx = conn.transaction()?;
+        let mut removed = 0;
+        for table in ["page_views", "episode_plays", "events"] {
+            removed += tx.execute(
+                &format!("DELETE FROM {table} WHERE user_fingerprint = ?1"),
+                params![fingerprint],
+            )?;
+        }
+        tx.commit()?;
+
+        self.stats_cache.invalidate_all();
+
+        Ok(removed)
+    }
+
     pub async fn insert_test_data(&self, count: usize) -> Result<()> {
         let conn = self.conn.lock().await;
         
@@ -439,7 +891,7 @@ impl AnalyticsDb {
             ("Leipzig", "DE", "178.72.0.0"),
         ];
 
-        let routes = vec![
+        let routes = [
             ("/episode-search", "episodeSearch"),
             ("/search", "search"),
             ("/clusters-river", "clusters-river"),
@@ -448,8 +900,8 @@ impl AnalyticsDb {
             ("/umap", "umap"),
         ];
 
-        let podcasts = vec!["freakshow", "lnp", "cre", "raumzeit"];
-        let user_agents = vec![
+        let podcasts = ["freakshow", "lnp", "cre", "raumzeit"];
+        let user_agents = [
             "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36",
             "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36",
             "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36",
@@ -538,12 +990,9 @@ impl AnalyticsDb {
 
         // Use read-only connection for stats queries (allows concurrent reads)
         let conn = self.read_conn.lock().await;
-        let since = if let Some(d) = days {
-            let cutoff = Utc::now() - chrono::Duration::days(d);
-            Some(cutoff.to_rfc3339())
-        } else {
-            None
-        };
+        let now = Utc::now();
+        let cutoff = days.map(|d| now - chrono::Duration::days(d));
+        let since = cutoff.map(|c| c.to_rfc3339());
 
         // Helper function to map PageStats
         fn map_page_stats(row: &rusqlite::Row<'_>) -> rusqlite::Result<PageStats> {
@@ -571,11 +1020,21 @@ impl AnalyticsDb {
                 episode: row.get(1)?,
                 views: row.get(2)?,
                 unique_users: row.get(3)?,
+                weighted_views: None,
+            })
+        }
+
+        // Helper function to map EventStats
+        fn map_event_stats(row: &rusqlite::Row<'_>) -> rusqlite::Result<EventStats> {
+            Ok(EventStats {
+                event_type: row.get(0)?,
+                count: row.get(1)?,
+                unique_users: row.get(2)?,
             })
         }
 
         // Helper function to map LocationStats (without coordinates - we'll enrich later)
-        fn map_location_stats_raw(row: &rusqlite::Row<'_>) -> rusqlite::Result<(Option<String>, Option<String>, i64, i64)> {
+        fn map_location_stats_raw(row: &rusqlite::Row<'_>) -> rusqlite::Result<LocationRow> {
             Ok((
                 row.get(0)?,
                 row.get(1)?,
@@ -587,13 +1046,13 @@ impl AnalyticsDb {
         // Unique users (excluding stats page)
         let unique_users: i64 = if let Some(ref since_str) = since {
             conn.query_row(
-                "SELECT COUNT(DISTINCT user_fingerprint) FROM page_views WHERE created_at >= ?1 AND path NOT LIKE '/stats%'",
+                "SELECT COUNT(DISTINCT user_fingerprint) FROM page_views WHERE is_bot = 0 AND created_at >= ?1 AND path NOT LIKE '/stats%'",
                 params![since_str],
                 |row| row.get(0),
             )?
         } else {
             conn.query_row(
-                "SELECT COUNT(DISTINCT user_fingerprint) FROM page_views WHERE path NOT LIKE '/stats%'",
+                "SELECT COUNT(DISTINCT user_fingerprint) FROM page_views WHERE is_bot = 0 AND path NOT LIKE '/stats%'",
                 [],
                 |row| row.get(0),
             )?
@@ -602,23 +1061,23 @@ impl AnalyticsDb {
         // Total page views (excluding stats page)
         let total_page_views: i64 = if let Some(ref since_str) = since {
             conn.query_row(
-                "SELECT COUNT(*) FROM page_views WHERE created_at >= ?1 AND path NOT LIKE '/stats%'",
+                "SELECT COUNT(*) FROM page_views WHERE is_bot = 0 AND created_at >= ?1 AND path NOT LIKE '/stats%'",
                 params![since_str],
                 |row| row.get(0),
             )?
         } else {
-            conn.query_row("SELECT COUNT(*) FROM page_views WHERE path NOT LIKE '/stats%'", [], |row| row.get(0))?
+            conn.query_row("SELECT COUNT(*) FROM page_views WHERE is_bot = 0 AND path NOT LIKE '/stats%'", [], |row| row.get(0))?
         };
 
         // Total episode plays
         let total_episode_plays: i64 = if let Some(ref since_str) = since {
             conn.query_row(
-                "SELECT COUNT(*) FROM episode_plays WHERE created_at >= ?1",
+                "SELECT COUNT(*) FROM episode_plays WHERE is_bot = 0 AND created_at >= ?1",
                 params![since_str],
                 |row| row.get(0),
             )?
         } else {
-            conn.query_row("SELECT COUNT(*) FROM episode_plays", [], |row| row.get(0))?
+            conn.query_row("SELECT COUNT(*) FROM episode_plays WHERE is_bot = 0", [], |row| row.get(0))?
         };
 
         // Top pages (excluding stats page)
@@ -626,7 +1085,7 @@ impl AnalyticsDb {
             conn.prepare(
                 "SELECT path, route_name, COUNT(*) as views, COUNT(DISTINCT user_fingerprint) as unique_users
                  FROM page_views
-                 WHERE created_at >= ?1 AND path NOT LIKE '/stats%'
+                 WHERE is_bot = 0 AND created_at >= ?1 AND path NOT LIKE '/stats%'
                  GROUP BY path, route_name
                  ORDER BY views DESC
                  LIMIT 20",
@@ -637,7 +1096,7 @@ impl AnalyticsDb {
             conn.prepare(
                 "SELECT path, route_name, COUNT(*) as views, COUNT(DISTINCT user_fingerprint) as unique_users
                  FROM page_views
-                 WHERE path NOT LIKE '/stats%'
+                 WHERE is_bot = 0 AND path NOT LIKE '/stats%'
                  GROUP BY path, route_name
                  ORDER BY views DESC
                  LIMIT 20",
@@ -651,7 +1110,7 @@ impl AnalyticsDb {
             conn.prepare(
                 "SELECT podcast, COUNT(*) as views, COUNT(DISTINCT user_fingerprint) as unique_users
                  FROM page_views
-                 WHERE podcast IS NOT NULL AND created_at >= ?1
+                 WHERE is_bot = 0 AND podcast IS NOT NULL AND created_at >= ?1
                  GROUP BY podcast
                  ORDER BY views DESC
                  LIMIT 20",
@@ -662,7 +1121,7 @@ impl AnalyticsDb {
             conn.prepare(
                 "SELECT podcast, COUNT(*) as views, COUNT(DISTINCT user_fingerprint) as unique_users
                  FROM page_views
-                 WHERE podcast IS NOT NULL
+                 WHERE is_bot = 0 AND podcast IS NOT NULL
                  GROUP BY podcast
                  ORDER BY views DESC
                  LIMIT 20",
@@ -676,7 +1135,7 @@ impl AnalyticsDb {
             conn.prepare(
                 "SELECT podcast, COUNT(*) as views, COUNT(DISTINCT user_fingerprint) as unique_users
                  FROM episode_plays
-                 WHERE created_at >= ?1
+                 WHERE is_bot = 0 AND created_at >= ?1
                  GROUP BY podcast
                  ORDER BY views DESC
                  LIMIT 20",
@@ -687,6 +1146,7 @@ impl AnalyticsDb {
             conn.prepare(
                 "SELECT podcast, COUNT(*) as views, COUNT(DISTINCT user_fingerprint) as unique_users
                  FROM episode_plays
+                 WHERE is_bot = 0
                  GROUP BY podcast
                  ORDER BY views DESC
                  LIMIT 20",
@@ -700,7 +1160,7 @@ impl AnalyticsDb {
             conn.prepare(
                 "SELECT podcast, episode, COUNT(*) as views, COUNT(DISTINCT user_fingerprint) as unique_users
                  FROM page_views
-                 WHERE podcast IS NOT NULL AND episode IS NOT NULL AND created_at >= ?1
+                 WHERE is_bot = 0 AND podcast IS NOT NULL AND episode IS NOT NULL AND created_at >= ?1
                  GROUP BY podcast, episode
                  ORDER BY views DESC
                  LIMIT 20",
@@ -711,7 +1171,7 @@ impl AnalyticsDb {
             conn.prepare(
                 "SELECT podcast, episode, COUNT(*) as views, COUNT(DISTINCT user_fingerprint) as unique_users
                  FROM page_views
-                 WHERE podcast IS NOT NULL AND episode IS NOT NULL
+                 WHERE is_bot = 0 AND podcast IS NOT NULL AND episode IS NOT NULL
                  GROUP BY podcast, episode
                  ORDER BY views DESC
                  LIMIT 20",
@@ -725,7 +1185,7 @@ impl AnalyticsDb {
             conn.prepare(
                 "SELECT country, city, COUNT(*) as views, COUNT(DISTINCT user_fingerprint) as unique_users
                  FROM page_views
-                 WHERE (country IS NOT NULL OR city IS NOT NULL) AND created_at >= ?1
+                 WHERE is_bot = 0 AND (country IS NOT NULL OR city IS NOT NULL) AND created_at >= ?1
                  GROUP BY country, city
                  ORDER BY views DESC
                  LIMIT 50",
@@ -736,7 +1196,7 @@ impl AnalyticsDb {
             conn.prepare(
                 "SELECT country, city, COUNT(*) as views, COUNT(DISTINCT user_fingerprint) as unique_users
                  FROM page_views
-                 WHERE country IS NOT NULL OR city IS NOT NULL
+                 WHERE is_bot = 0 AND (country IS NOT NULL OR city IS NOT NULL)
                  GROUP BY country, city
                  ORDER BY views DESC
                  LIMIT 50",
@@ -745,8 +1205,9 @@ impl AnalyticsDb {
             .collect::<Result<Vec<_>, _>>()?
         };
         
-        // Enrich locations with coordinates
-        let locations: Vec<LocationStats> = locations_raw
+        // Enrich locations with coordinates; rare locations are only reported in aggregate (k-anonymity)
+        let (locations_raw, other) = bucket_rare_locations(locations_raw, self.location_min_views);
+        let mut locations: Vec<LocationStats> = locations_raw
             .into_iter()
             .map(|(country, city, views, unique_users)| {
                 let coords = self.get_city_coordinates(&country, &city);
@@ -760,13 +1221,35 @@ impl AnalyticsDb {
                 }
             })
             .collect();
-
-        // Top played episodes (from episode_plays table)
-        let top_played_episodes = if let Some(ref since_str) = since {
+        locations.extend(other);
+
+        // Top played episodes (from episode_plays table), optionally time-decayed so recent plays rank higher
+        let top_played_episodes = if let Some(half_life_days) = self.play_half_life_days {
+            fn map_play_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<PlayRow> {
+                Ok(PlayRow {
+                    podcast: row.get(0)?,
+                    episode: row.get(1)?,
+                    user_fingerprint: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            }
+            let rows = if let Some(ref since_str) = since {
+                conn.prepare(
+                    "SELECT podcast, episode, user_fingerprint, created_at FROM episode_plays WHERE is_bot = 0 AND created_at >= ?1",
+                )?
+                .query_map(params![since_str], map_play_row)?
+                .collect::<Result<Vec<_>, _>>()?
+            } else {
+                conn.prepare("SELECT podcast, episode, user_fingerprint, created_at FROM episode_plays WHERE is_bot = 0")?
+                    .query_map([], map_play_row)?
+                    .collect::<Result<Vec<_>, _>>()?
+            };
+            rank_plays_with_decay(&rows, Utc::now(), half_life_days, 20)
+        } else if let Some(ref since_str) = since {
             conn.prepare(
                 "SELECT podcast, episode, COUNT(*) as views, COUNT(DISTINCT user_fingerprint) as unique_users
                  FROM episode_plays
-                 WHERE created_at >= ?1
+                 WHERE is_bot = 0 AND created_at >= ?1
                  GROUP BY podcast, episode
                  ORDER BY views DESC
                  LIMIT 20",
@@ -777,6 +1260,7 @@ impl AnalyticsDb {
             conn.prepare(
                 "SELECT podcast, episode, COUNT(*) as views, COUNT(DISTINCT user_fingerprint) as unique_users
                  FROM episode_plays
+                 WHERE is_bot = 0
                  GROUP BY podcast, episode
                  ORDER BY views DESC
                  LIMIT 20",
@@ -785,6 +1269,81 @@ impl AnalyticsDb {
             .collect::<Result<Vec<_>, _>>()?
         };
 
+        // Top custom events
+        let top_events = if let Some(ref since_str) = since {
+            conn.prepare(
+                "SELECT event_type, COUNT(*) as count, COUNT(DISTINCT user_fingerprint) as unique_users
+                 FROM events
+                 WHERE is_bot = 0 AND created_at >= ?1
+                 GROUP BY event_type
+                 ORDER BY count DESC
+                 LIMIT 20",
+            )?
+            .query_map(params![since_str], map_event_stats)?
+            .collect::<Result<Vec<_>, _>>()?
+        } else {
+            conn.prepare(
+                "SELECT event_type, COUNT(*) as count, COUNT(DISTINCT user_fingerprint) as unique_users
+                 FROM events
+                 WHERE is_bot = 0
+                 GROUP BY event_type
+                 ORDER BY count DESC
+                 LIMIT 20",
+            )?
+            .query_map([], map_event_stats)?
+            .collect::<Result<Vec<_>, _>>()?
+        };
+
+        // Daily time series (excluding stats page views)
+        fn map_daily_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<DailyRow> {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        }
+        let (daily_views, daily_plays) = if let Some(ref since_str) = since {
+            (
+                conn.prepare(
+                    "SELECT substr(created_at, 1, 10) as day, COUNT(*), COUNT(DISTINCT user_fingerprint)
+                     FROM page_views
+                     WHERE is_bot = 0 AND created_at >= ?1 AND path NOT LIKE '/stats%'
+                     GROUP BY day",
+                )?
+                .query_map(params![since_str], map_daily_row)?
+                .collect::<Result<Vec<_>, _>>()?,
+                conn.prepare(
+                    "SELECT substr(created_at, 1, 10) as day, COUNT(*), COUNT(DISTINCT user_fingerprint)
+                     FROM episode_plays
+                     WHERE is_bot = 0 AND created_at >= ?1
+                     GROUP BY day",
+                )?
+                .query_map(params![since_str], map_daily_row)?
+                .collect::<Result<Vec<_>, _>>()?,
+            )
+        } else {
+            (
+                conn.prepare(
+                    "SELECT substr(created_at, 1, 10) as day, COUNT(*), COUNT(DISTINCT user_fingerprint)
+                     FROM page_views
+                     WHERE is_bot = 0 AND path NOT LIKE '/stats%'
+                     GROUP BY day",
+                )?
+                .query_map([], map_daily_row)?
+                .collect::<Result<Vec<_>, _>>()?,
+                conn.prepare(
+                    "SELECT substr(created_at, 1, 10) as day, COUNT(*), COUNT(DISTINCT user_fingerprint)
+                     FROM episode_plays
+                     WHERE is_bot = 0
+                     GROUP BY day",
+                )?
+                .query_map([], map_daily_row)?
+                .collect::<Result<Vec<_>, _>>()?,
+            )
+        };
+        let daily = merge_daily_series(
+            daily_views,
+            daily_plays,
+            cutoff.map(|c| c.date_naive()),
+            now.date_naive(),
+        );
+
         let stats = AnalyticsStats {
             unique_users,
             total_page_views,
@@ -795,6 +1354,8 @@ impl AnalyticsDb {
             top_episodes,
             top_played_episodes,
             locations,
+            top_events,
+            daily,
         };
 
         // Cache the result
@@ -804,7 +1365,7 @@ impl AnalyticsDb {
     }
 }
 
-fn extract_ip_from_headers(headers: &HeaderMap) -> String {
+pub(crate) fn extract_ip_from_headers(headers: &HeaderMap) -> String {
     // Try X-Forwarded-For first (for proxies/load balancers)
     if let Some(forwarded) = headers.get("x-forwarded-for") {
         if let Ok(forwarded_str) = forwarded.to_str() {
@@ -826,6 +1387,31 @@ fn extract_ip_from_headers(headers: &HeaderMap) -> String {
     "unknown".to_string()
 }
 
+/// Rate-limiting key: the peer address, or the client a trusted proxy reports for it. Forwarding
+/// headers from other peers are ignored, since clients can set them to anything.
+pub(crate) fn client_key(peer: SocketAddr, headers: &HeaderMap, trusted_proxies: &[IpAddr]) -> String {
+    if !trusted_proxies.contains(&peer.ip()) {
+        return peer.ip().to_string();
+    }
+    // Proxies append the address they saw, so the last entry not added by a trusted proxy is the client
+    let forwarded = headers
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|chain| {
+            chain
+                .rsplit(',')
+                .filter_map(|ip| ip.trim().parse::<IpAddr>().ok())
+                .find(|ip| !trusted_proxies.contains(ip))
+        });
+    let real_ip = || {
+        headers
+            .get("x-real-ip")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|ip| ip.trim().parse::<IpAddr>().ok())
+    };
+    forwarded.or_else(real_ip).unwrap_or(peer.ip()).to_string()
+}
+
 pub async fn track(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -882,50 +1468,122 @@ pub async fn track_episode_play(
     Json(TrackResponse { success: true })
 }
 
-#[derive(Debug, Deserialize)]
-pub struct StatsQuery {
-    pub days: Option<i64>,
-}
+pub async fn track_event(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<TrackEventRequest>,
+) -> impl IntoResponse {
+    if req.event_type.trim().is_empty() {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "event_type must not be empty" })),
+        )
+            .into_response();
+    }
 
-fn extract_auth_token(headers: &HeaderMap) -> Option<String> {
-    // Prefer explicit x-auth-token, but also accept Authorization: Bearer <token>
-    if let Some(v) = headers.get("x-auth-token").and_then(|v| v.to_str().ok()) {
-        let t = v.trim();
-        if !t.is_empty() {
-            return Some(t.to_string());
+    let ip = extract_ip_from_headers(&headers);
+    let user_agent = req
+        .user_agent
+        .clone()
+        .or_else(|| {
+            headers
+                .get("user-agent")
+                .and_then(|h| h.to_str().ok())
+                .map(|s| s.to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Track the event (fire and forget - don't block response)
+    let analytics_db = state.analytics_db.clone();
+    tokio::spawn(async move {
+        if let Err(e) = analytics_db.track_event(req, ip, user_agent).await {
+            tracing::warn!("Failed to track event: {}", e);
         }
-    }
+    });
 
-    if let Some(v) = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok()) {
-        let s = v.trim();
-        if let Some(rest) = s.strip_prefix("Bearer ").or_else(|| s.strip_prefix("bearer ")) {
-            let t = rest.trim();
-            if !t.is_empty() {
-                return Some(t.to_string());
-            }
+    Json(TrackResponse { success: true }).into_response()
+}
+
+/// Write a stats snapshot every `interval` (first one right away) for trend analysis
+/// without a time-series database
+pub async fn run_stats_snapshots(db: Arc<AnalyticsDb>, dir: PathBuf, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        match db.write_stats_snapshot(&dir).await {
+            Ok(path) => tracing::info!("Wrote analytics snapshot {}", path.display()),
+            Err(e) => tracing::warn!("Failed to write analytics snapshot: {}", e),
         }
     }
+}
 
-    None
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    pub days: Option<i64>,
+    /// "json" (default) or "csv"
+    pub format: Option<String>,
 }
 
-fn is_stats_auth_ok(cfg: &crate::config::AppConfig, headers: &HeaderMap) -> bool {
-    let Some(expected) = cfg.stats_auth_token.as_ref() else {
-        // No auth configured => allow.
-        return true;
+pub async fn stats(
+    Query(params): Query<StatsQuery>,
+    State(state): State<AppState>,
+    uri: Uri,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if !is_auth_ok(&state.cfg, state.cfg.stats_auth_token.as_ref(), None, uri.path(), &headers) {
+        return (
+            axum::http::StatusCode::FORBIDDEN,
+            Json(serde_json::json!({ "error": "permission denied" })),
+        )
+            .into_response();
+    }
+
+    let csv = match params.format.as_deref().map(|f| f.trim().to_ascii_lowercase()).as_deref() {
+        None | Some("json") => false,
+        Some("csv") => true,
+        Some(other) => {
+            return (
+                axum::http::StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": format!("unknown format '{other}' (expected json or csv)") })),
+            )
+                .into_response();
+        }
     };
-    let Some(got) = extract_auth_token(headers) else {
-        return false;
+
+    let response = match state.analytics_db.get_stats(params.days).await {
+        Ok(stats) if csv => stats_csv(&stats).map(|body| {
+            (
+                [
+                    (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                    (header::CONTENT_DISPOSITION, "attachment; filename=\"analytics-stats.csv\""),
+                ],
+                body,
+            )
+                .into_response()
+        }),
+        Ok(stats) => Ok(Json(stats).into_response()),
+        Err(e) => Err(e),
     };
-    got == *expected
+    response.unwrap_or_else(|e| {
+        tracing::error!("Failed to get analytics stats: {}", e);
+        (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "Failed to get analytics stats" })),
+        )
+            .into_response()
+    })
 }
 
-pub async fn stats(
-    Query(params): Query<StatsQuery>,
+/// Delete a user's analytics data by fingerprint (GDPR erasure request). Needs the stats or the
+/// global token; without either configured the endpoint is closed.
+pub async fn delete_user_data(
+    UrlPath(fingerprint): UrlPath<String>,
     State(state): State<AppState>,
     headers: HeaderMap,
 ) -> impl IntoResponse {
-    if !is_stats_auth_ok(&state.cfg, &headers) {
+    let cfg = &state.cfg;
+    if !carries_token(cfg.stats_auth_token.as_ref(), &headers) && !carries_token(cfg.auth_token.as_ref(), &headers) {
         return (
             axum::http::StatusCode::FORBIDDEN,
             Json(serde_json::json!({ "error": "permission denied" })),
@@ -933,13 +1591,13 @@ pub async fn stats(
             .into_response();
     }
 
-    match state.analytics_db.get_stats(params.days).await {
-        Ok(stats) => Json(stats).into_response(),
+    match state.analytics_db.delete_user(&fingerprint).await {
+        Ok(deleted) => Json(serde_json::json!({ "deleted": deleted })).into_response(),
         Err(e) => {
-            tracing::error!("Failed to get analytics stats: {}", e);
+            tracing::error!("Failed to delete analytics data: {}", e);
             (
                 axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({ "error": "Failed to get analytics stats" })),
+                Json(serde_json::json!({ "error": "Failed to delete analytics data" })),
             )
                 .into_response()
         }
@@ -954,9 +1612,10 @@ pub struct TestDataQuery {
 pub async fn insert_test_data_endpoint(
     Query(params): Query<TestDataQuery>,
     State(state): State<AppState>,
+    uri: Uri,
     headers: HeaderMap,
 ) -> impl IntoResponse {
-    if !is_stats_auth_ok(&state.cfg, &headers) {
+    if !is_auth_ok(&state.cfg, state.cfg.stats_auth_token.as_ref(), None, uri.path(), &headers) {
         return (
             axum::http::StatusCode::FORBIDDEN,
             Json(serde_json::json!({ "error": "permission denied" })),
@@ -983,3 +1642,391 @@ pub async fn insert_test_data_endpoint(
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> AnalyticsDb {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static NEXT_DB: AtomicUsize = AtomicUsize::new(0);
+
+        let db_path = std::env::temp_dir().join(format!(
+            "analytics-test-{}-{}.db",
+            std::process::id(),
+            NEXT_DB.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_file(&db_path);
+        AnalyticsDb::new(&db_path, None).unwrap()
+    }
+
+    /// Page view of `path` without route, podcast, referrer or client user agent
+    fn page_view(path: &str) -> TrackRequest {
+        TrackRequest {
+            path: path.to_string(),
+            route_name: None,
+            podcast: None,
+            episode: None,
+            referrer: None,
+            user_agent: None,
+        }
+    }
+
+    #[test]
+    fn test_client_key_only_trusts_forwarding_from_proxies() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "1.1.1.1, 203.0.113.7, 10.0.0.2".parse().unwrap());
+        headers.insert("x-real-ip", "198.51.100.1".parse().unwrap());
+        let client: SocketAddr = "192.0.2.5:40000".parse().unwrap();
+        let proxy: SocketAddr = "10.0.0.1:40000".parse().unwrap();
+        let trusted: Vec<IpAddr> = vec!["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()];
+
+        // A direct client can't pick its own key
+        assert_eq!(client_key(client, &headers, &trusted), "192.0.2.5");
+        assert_eq!(client_key(proxy, &headers, &[]), "10.0.0.1");
+        // Behind trusted proxies: the first hop they didn't add, not the spoofable first entry
+        assert_eq!(client_key(proxy, &headers, &trusted), "203.0.113.7");
+        headers.remove("x-forwarded-for");
+        assert_eq!(client_key(proxy, &headers, &trusted), "198.51.100.1");
+    }
+
+    #[test]
+    fn test_city_coordinates_with_quoted_commas() {
+        let csv = "\"city\",\"city_ascii\",\"lat\",\"lng\",\"country\",\"iso2\"\r\n\
+            \"Washington, D.C.\",\"Washington, D.C.\",\"38.9047\",\"-77.0163\",\"United States\",\"US\"\r\n\
+            \"München\",\"Munchen\",\"48.1375\",\"11.5750\",\"Germany\",\"DE\"\r\n\
+            \n\
+            \"Say \"\"Hi\"\"\",\"Say Hi\",\"1.5\",\"2.5\",\"Nowhere, Land\",\"xx\"\r\n";
+        let coords = AnalyticsDb::parse_city_coordinates(csv).unwrap();
+        assert_eq!(coords.get("US-WASHINGTON, D.C."), Some(&(38.9047, -77.0163)));
+        assert_eq!(coords.get("DE-MÜNCHEN"), Some(&(48.1375, 11.575)));
+        assert_eq!(coords.get("DE-MUNCHEN"), Some(&(48.1375, 11.575)));
+        assert_eq!(coords.get("XX-SAY \"HI\""), Some(&(1.5, 2.5)));
+        assert_eq!(coords.len(), 5);
+
+        // Columns are found by name, not position
+        let reordered = "iso2,lng,lat,city,city_ascii\nFR,2.3522,48.8566,Paris,Paris\n";
+        assert_eq!(AnalyticsDb::parse_city_coordinates(reordered).unwrap().get("FR-PARIS"), Some(&(48.8566, 2.3522)));
+        assert!(AnalyticsDb::parse_city_coordinates("city,lat\nParis,1\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stats_snapshot_written_as_json() {
+        let db = test_db();
+        let req = TrackRequest {
+            podcast: Some("freakshow".to_string()),
+            ..page_view("/episodes")
+        };
+        db.insert_page_view(req, "10.0.0.1".to_string(), "test-agent".to_string(), (None, None))
+            .await
+            .unwrap();
+
+        let dir = std::env::temp_dir().join(format!("analytics-snapshots-test-{}", std::process::id()));
+        let path = db.write_stats_snapshot(&dir).await.unwrap();
+        let date = Utc::now().format("%Y-%m-%d").to_string();
+        assert_eq!(path, dir.join(format!("{date}.json")));
+
+        let snapshot: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(snapshot["date"], date);
+        assert_eq!(snapshot["days"], 1);
+        assert_eq!(snapshot["stats"]["total_page_views"], 1);
+        assert!(snapshot["stats"]["top_pages"].is_array());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    async fn stored_location(granularity: LocationGranularity) -> (Option<String>, Option<String>) {
+        let db = test_db().with_location_granularity(granularity);
+        let req = page_view("/");
+        let location = (Some("DE".to_string()), Some("Berlin".to_string()));
+        db.insert_page_view(req, "10.0.0.1".to_string(), "test-agent".to_string(), location)
+            .await
+            .unwrap();
+
+        let conn = db.conn.lock().await;
+        conn.query_row("SELECT country, city FROM page_views", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_location_granularity() {
+        assert_eq!(
+            stored_location(LocationGranularity::City).await,
+            (Some("DE".to_string()), Some("Berlin".to_string()))
+        );
+        assert_eq!(
+            stored_location(LocationGranularity::Country).await,
+            (Some("DE".to_string()), None)
+        );
+        assert_eq!(stored_location(LocationGranularity::None).await, (None, None));
+    }
+
+    #[tokio::test]
+    async fn test_rare_locations_aggregated_into_other() {
+        let db = test_db().with_location_min_views(3);
+        let views = [("DE", "Berlin", 3), ("DE", "Kleinkleckersdorf", 1), ("AT", "Hintertux", 2)];
+        let mut ip = 0;
+        for (country, city, count) in views {
+            for _ in 0..count {
+                ip += 1;
+                let req = page_view("/");
+                let location = (Some(country.to_string()), Some(city.to_string()));
+                db.insert_page_view(req, format!("10.0.0.{ip}"), "test-agent".to_string(), location)
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let stats = db.get_stats(None).await.unwrap();
+        let cities: Vec<Option<&str>> = stats.locations.iter().map(|l| l.city.as_deref()).collect();
+        assert_eq!(cities, vec![Some("Berlin"), None]);
+
+        let other = &stats.locations[1];
+        assert_eq!(other.country.as_deref(), Some("other"));
+        assert_eq!(other.views, 3);
+        assert_eq!(other.unique_users, 3);
+    }
+
+    #[test]
+    fn test_decayed_play_ranking_prefers_recent_plays() {
+        let now = Utc::now();
+        let play = |episode: &str, days_ago: i64, user: &str| PlayRow {
+            podcast: "freakshow".to_string(),
+            episode: episode.to_string(),
+            user_fingerprint: user.to_string(),
+            created_at: (now - chrono::Duration::days(days_ago)).to_rfc3339(),
+        };
+        // Evergreen: 5 plays a year ago. New: 3 plays this week.
+        let mut rows: Vec<PlayRow> = (0..5).map(|i| play("100", 365, &format!("old-{i}"))).collect();
+        rows.extend((0..3).map(|i| play("281", i, &format!("new-{i}"))));
+
+        // A huge half-life is effectively undecayed: raw counts win
+        let undecayed = rank_plays_with_decay(&rows, now, 1e9, 20);
+        assert_eq!(undecayed[0].episode, "100");
+        assert_eq!(undecayed[0].views, 5);
+
+        let decayed = rank_plays_with_decay(&rows, now, 30.0, 20);
+        assert_eq!(decayed[0].episode, "281");
+        assert_eq!(decayed[0].views, 3);
+        assert_eq!(decayed[0].unique_users, 3);
+        assert!(decayed[1].weighted_views.unwrap() < 0.01);
+    }
+
+    #[tokio::test]
+    async fn test_bot_page_views_flagged_and_excluded() {
+        let patterns: Vec<String> = BOT_UA_PATTERNS.iter().map(|p| p.to_string()).collect();
+        assert!(is_bot("Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)", &patterns));
+        assert!(is_bot("curl/8.4.0", &patterns));
+        assert!(!is_bot("Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36", &patterns));
+
+        let db = test_db();
+        for (ip, user_agent) in [("10.0.0.1", "Mozilla/5.0 (Macintosh)"), ("10.0.0.2", "Mozilla/5.0 (compatible; bingbot/2.0)")] {
+            let req = page_view("/");
+            db.insert_page_view(req, ip.to_string(), user_agent.to_string(), (None, None))
+                .await
+                .unwrap();
+        }
+        let flagged: Vec<i64> = {
+            let conn = db.conn.lock().await;
+            let mut stmt = conn.prepare("SELECT is_bot FROM page_views ORDER BY id").unwrap();
+            stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap()
+        };
+        assert_eq!(flagged, vec![0, 1]);
+
+        let stats = db.get_stats(None).await.unwrap();
+        assert_eq!((stats.total_page_views, stats.unique_users), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_repeated_plays_within_window_counted_once() {
+        let play = |episode: &str| TrackEpisodePlayRequest {
+            podcast: "freakshow".to_string(),
+            episode: episode.to_string(),
+            user_agent: None,
+        };
+        let ua = "Mozilla/5.0 (Macintosh)".to_string();
+        let db = test_db();
+        assert!(db.track_episode_play(play("281"), "10.0.0.1".to_string(), ua.clone()).await.unwrap());
+        assert!(!db.track_episode_play(play("281"), "10.0.0.1".to_string(), ua.clone()).await.unwrap());
+        // Other episode or other user: counted
+        assert!(db.track_episode_play(play("282"), "10.0.0.1".to_string(), ua.clone()).await.unwrap());
+        assert!(db.track_episode_play(play("281"), "10.0.0.2".to_string(), ua.clone()).await.unwrap());
+        assert_eq!(db.get_stats(None).await.unwrap().total_episode_plays, 3);
+
+        // A play older than the window doesn't suppress a new one
+        {
+            let conn = db.conn.lock().await;
+            conn.execute("UPDATE episode_plays SET created_at = '2020-01-01T00:00:00+00:00'", []).unwrap();
+        }
+        assert!(db.track_episode_play(play("281"), "10.0.0.1".to_string(), ua.clone()).await.unwrap());
+
+        let db = test_db().with_play_dedup_minutes(0);
+        assert!(db.track_episode_play(play("281"), "10.0.0.1".to_string(), ua.clone()).await.unwrap());
+        assert!(db.track_episode_play(play("281"), "10.0.0.1".to_string(), ua).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_delete_user_removes_only_that_fingerprint() {
+        let db = test_db();
+        let ua = "Mozilla/5.0 (Macintosh)".to_string();
+        for ip in ["10.0.0.1", "10.0.0.2"] {
+            let req = page_view("/");
+            db.insert_page_view(req, ip.to_string(), ua.clone(), (None, None)).await.unwrap();
+            let play = TrackEpisodePlayRequest {
+                podcast: "freakshow".to_string(),
+                episode: "281".to_string(),
+                user_agent: None,
+            };
+            db.track_episode_play(play, ip.to_string(), ua.clone()).await.unwrap();
+        }
+        let target = AnalyticsDb::get_user_fingerprint("10.0.0.1", &ua);
+
+        assert_eq!(db.delete_user(&target).await.unwrap(), 2);
+        assert_eq!(db.delete_user(&target).await.unwrap(), 0);
+        let stats = db.get_stats(None).await.unwrap();
+        assert_eq!((stats.total_page_views, stats.total_episode_plays, stats.unique_users), (1, 1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_stats_csv_flattens_top_lists() {
+        let db = test_db();
+        for path in ["/episodes", "=HYPERLINK(\"x\"),1"] {
+            let req = TrackRequest {
+                route_name: Some("episodes".to_string()),
+                podcast: Some("freakshow".to_string()),
+                ..page_view(path)
+            };
+            db.insert_page_view(req, "10.0.0.1".to_string(), "Mozilla/5.0".to_string(), (None, None))
+                .await
+                .unwrap();
+        }
+        let play = TrackEpisodePlayRequest {
+            podcast: "freakshow".to_string(),
+            episode: "281".to_string(),
+            user_agent: None,
+        };
+        db.track_episode_play(play, "10.0.0.1".to_string(), "Mozilla/5.0".to_string()).await.unwrap();
+
+        let csv = stats_csv(&db.get_stats(None).await.unwrap()).unwrap();
+        let records: Vec<Vec<String>> = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(csv.as_bytes())
+            .deserialize()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(records[0], vec!["section", "name", "detail", "views", "unique_users"]);
+        assert!(records.contains(&vec!["page".into(), "/episodes".into(), "episodes".into(), "1".into(), "1".into()]));
+        assert!(records.contains(&vec!["page".into(), "'=HYPERLINK(\"x\"),1".into(), "episodes".into(), "1".into(), "1".into()]));
+        assert!(records.contains(&vec!["played_episode".into(), "freakshow".into(), "281".into(), "1".into(), "1".into()]));
+        assert!(records.iter().all(|r| r.len() == 5));
+    }
+
+    #[test]
+    fn test_migrations_are_idempotent() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn).unwrap();
+        conn.execute(
+            "INSERT INTO episode_plays (user_fingerprint, podcast, episode, created_at, is_bot)
+             VALUES ('u', 'freakshow', '281', '2026-10-01T00:00:00+00:00', 1)",
+            [],
+        )
+        .unwrap();
+        run_migrations(&mut conn).unwrap();
+
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0)).unwrap();
+        assert_eq!(version, MIGRATIONS.len() as i64);
+        let plays: i64 = conn.query_row("SELECT COUNT(*) FROM episode_plays WHERE is_bot = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(plays, 1);
+    }
+
+    #[test]
+    fn test_is_bot_column_added_to_existing_db() {
+        let db_path = std::env::temp_dir().join(format!("analytics-migrate-test-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&db_path);
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute(
+                "CREATE TABLE page_views (id INTEGER PRIMARY KEY AUTOINCREMENT, user_fingerprint TEXT NOT NULL, path TEXT NOT NULL,
+                 route_name TEXT, podcast TEXT, episode TEXT, country TEXT, city TEXT, referrer TEXT, user_agent TEXT,
+                 ip_address TEXT, created_at TEXT NOT NULL)",
+                [],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO page_views (user_fingerprint, path, created_at) VALUES ('u', '/', '2026-10-01T00:00:00+00:00')",
+                [],
+            )
+            .unwrap();
+        }
+
+        let db = AnalyticsDb::new(&db_path, None).unwrap();
+        let conn = db.read_conn.try_lock().unwrap();
+        let is_bot: i64 = conn.query_row("SELECT is_bot FROM page_views", [], |row| row.get(0)).unwrap();
+        assert_eq!(is_bot, 0);
+    }
+
+    #[test]
+    fn test_daily_series_fills_gaps() {
+        let day = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        let views = vec![("2026-10-12".to_string(), 4, 2), ("2026-10-14".to_string(), 1, 1)];
+        let plays = vec![("2026-10-14".to_string(), 3, 2), ("2026-10-15".to_string(), 2, 1)];
+        let daily = merge_daily_series(views.clone(), plays.clone(), Some(day("2026-10-11")), day("2026-10-16"));
+        let dates: Vec<&str> = daily.iter().map(|d| d.date.as_str()).collect();
+        assert_eq!(dates, vec!["2026-10-11", "2026-10-12", "2026-10-13", "2026-10-14", "2026-10-15", "2026-10-16"]);
+        assert_eq!(
+            daily[3],
+            DailyStats { date: "2026-10-14".to_string(), page_views: 1, episode_plays: 3, unique_users: 1 }
+        );
+        assert_eq!(daily[2].page_views + daily[2].episode_plays, 0);
+
+        // Without a window the series starts at the first active day
+        let all_time = merge_daily_series(views, plays, None, day("2026-10-16"));
+        assert_eq!(all_time.first().unwrap().date, "2026-10-12");
+        assert_eq!(all_time.len(), 5);
+        assert!(merge_daily_series(Vec::new(), Vec::new(), None, day("2026-10-16")).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_daily_stats_cover_window() {
+        let db = test_db();
+        let req = page_view("/");
+        db.insert_page_view(req, "10.0.0.1".to_string(), "test-agent".to_string(), (None, None))
+            .await
+            .unwrap();
+
+        let stats = db.get_stats(Some(7)).await.unwrap();
+        assert_eq!(stats.daily.len(), 8);
+        let today = stats.daily.last().unwrap();
+        assert_eq!(today.date, Utc::now().format("%Y-%m-%d").to_string());
+        assert_eq!((today.page_views, today.unique_users), (1, 1));
+        assert_eq!(stats.daily.iter().map(|d| d.page_views).sum::<i64>(), stats.total_page_views);
+    }
+
+    #[tokio::test]
+    async fn test_custom_event_appears_in_stats() {
+        let db = test_db();
+        for ip in ["10.0.0.1", "10.0.0.2"] {
+            db.track_event(
+                TrackEventRequest {
+                    event_type: "share_clicked".to_string(),
+                    metadata: Some(serde_json::json!({ "target": "mastodon" })),
+                    path: Some("/episode-search".to_string()),
+                    podcast: Some("freakshow".to_string()),
+                    episode: Some("281".to_string()),
+                    user_agent: None,
+                },
+                ip.to_string(),
+                "test-agent".to_string(),
+            )
+            .await
+            .unwrap();
+        }
+
+        let stats = db.get_stats(None).await.unwrap();
+        assert_eq!(stats.top_events.len(), 1);
+        assert_eq!(stats.top_events[0].event_type, "share_clicked");
+        assert_eq!(stats.top_events[0].count, 2);
+        assert_eq!(stats.top_events[0].unique_users, 2);
+    }
+}
@@ -0,0 +1,148 @@
+use axum::http::{header, HeaderMap};
+
+use crate::config::AppConfig;
+
+fn extract_auth_token(headers: &HeaderMap) -> Option<String> {
+    // Prefer explicit x-auth-token, but also accept Authorization: Bearer <token>
+    if let Some(v) = headers.get("x-auth-token").and_then(|v| v.to_str().ok()) {
+        let t = v.trim();
+        if !t.is_empty() {
+            return Some(t.to_string());
+        }
+    }
+
+    if let Some(v) = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok()) {
+        let s = v.trim();
+        if let Some(rest) = s.strip_prefix("Bearer ").or_else(|| s.strip_prefix("bearer ")) {
+            let t = rest.trim();
+            if !t.is_empty() {
+                return Some(t.to_string());
+            }
+        }
+    }
+
+    None
+}
+
+/// Whether the request carries a configured token: the global one or `podcast_id`'s own.
+/// Unlike `is_auth_ok` this is false when no auth is configured at all.
+pub fn has_valid_token(cfg: &AppConfig, podcast_id: Option<&str>, headers: &HeaderMap) -> bool {
+    let Some(got) = extract_auth_token(headers) else {
+        return false;
+    };
+    let podcast_token = podcast_id.and_then(|id| cfg.podcast_auth_tokens.get(id));
+    cfg.auth_token.as_ref().is_some_and(|e| got == *e) || podcast_token.is_some_and(|t| got == *t)
+}
+
+/// Whether the request carries `expected`; false when `expected` isn't configured. For destructive
+/// and admin-only endpoints, which must not fall open like `is_auth_ok` does without a token.
+pub fn carries_token(expected: Option<&String>, headers: &HeaderMap) -> bool {
+    expected.is_some_and(|e| extract_auth_token(headers).is_some_and(|got| got == *e))
+}
+
+/// Check the request token against `expected` or, for requests scoped to `podcast_id`, that
+/// podcast's own token (`cfg.podcast_auth_tokens`). Exempt paths (probes, metrics) always pass.
+pub fn is_auth_ok(
+    cfg: &AppConfig,
+    expected: Option<&String>,
+    podcast_id: Option<&str>,
+    path: &str,
+    headers: &HeaderMap,
+) -> bool {
+    if cfg.is_auth_exempt(path) {
+        return true;
+    }
+    let podcast_token = podcast_id.and_then(|id| cfg.podcast_auth_tokens.get(id));
+    if expected.is_none() && podcast_token.is_none() {
+        // No auth configured => allow.
+        return true;
+    }
+    let Some(got) = extract_auth_token(headers) else {
+        return false;
+    };
+    expected.is_some_and(|e| got == *e) || podcast_token.is_some_and(|t| got == *t)
+}
+
+/// The podcasts of `podcast_ids` the request may read: each one is checked on its own, so a
+/// podcast's token only unlocks that podcast and protected podcasts drop out without it
+pub fn readable_podcasts(cfg: &AppConfig, podcast_ids: Vec<String>, path: &str, headers: &HeaderMap) -> Vec<String> {
+    podcast_ids
+        .into_iter()
+        .filter(|id| is_auth_ok(cfg, cfg.auth_token.as_ref(), Some(id), path, headers))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers_with_token(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-auth-token", HeaderValue::from_str(token).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_podcast_token_scoped_to_its_podcast() {
+        let mut cfg = AppConfig::for_tests();
+        cfg.auth_token = Some("admin".to_string());
+        cfg.podcast_auth_tokens.insert("lnp".to_string(), "lnp-token".to_string());
+        let global = cfg.auth_token.clone();
+
+        let lnp = headers_with_token("lnp-token");
+        assert!(is_auth_ok(&cfg, global.as_ref(), Some("lnp"), "/api/chat", &lnp));
+        assert!(!is_auth_ok(&cfg, global.as_ref(), Some("freakshow"), "/api/chat", &lnp));
+
+        // The global token keeps working everywhere
+        let admin = headers_with_token("admin");
+        assert!(is_auth_ok(&cfg, global.as_ref(), Some("lnp"), "/api/chat", &admin));
+        assert!(is_auth_ok(&cfg, global.as_ref(), Some("freakshow"), "/api/chat", &admin));
+
+        // Without a global token, only podcasts with their own token are protected
+        assert!(!is_auth_ok(&cfg, None, Some("lnp"), "/api/chat", &HeaderMap::new()));
+        assert!(is_auth_ok(&cfg, None, Some("freakshow"), "/api/chat", &HeaderMap::new()));
+    }
+
+    #[test]
+    fn test_valid_token_needs_configured_auth() {
+        let mut cfg = AppConfig::for_tests();
+        let admin = headers_with_token("admin");
+        // Open server: everyone passes auth, but nobody carries a valid token
+        assert!(!has_valid_token(&cfg, Some("lnp"), &admin));
+
+        cfg.auth_token = Some("admin".to_string());
+        cfg.podcast_auth_tokens.insert("lnp".to_string(), "lnp-token".to_string());
+        assert!(has_valid_token(&cfg, None, &admin));
+        assert!(has_valid_token(&cfg, Some("lnp"), &headers_with_token("lnp-token")));
+        assert!(!has_valid_token(&cfg, None, &headers_with_token("lnp-token")));
+        assert!(!has_valid_token(&cfg, Some("lnp"), &HeaderMap::new()));
+    }
+
+    #[test]
+    fn test_carries_token_fails_closed() {
+        let admin = headers_with_token("admin");
+        assert!(!carries_token(None, &admin));
+        assert!(!carries_token(Some(&"admin".to_string()), &HeaderMap::new()));
+        assert!(!carries_token(Some(&"other".to_string()), &admin));
+        assert!(carries_token(Some(&"admin".to_string()), &admin));
+    }
+
+    #[test]
+    fn test_readable_podcasts_with_only_podcast_tokens() {
+        let mut cfg = AppConfig::for_tests();
+        cfg.podcast_auth_tokens.insert("lnp".to_string(), "lnp-token".to_string());
+        cfg.podcast_auth_tokens.insert("cre".to_string(), "cre-token".to_string());
+        let ids = || vec!["freakshow".to_string(), "lnp".to_string(), "cre".to_string()];
+
+        // No token: only the unprotected podcast
+        assert_eq!(readable_podcasts(&cfg, ids(), "/api/chat", &HeaderMap::new()), vec!["freakshow"]);
+        // lnp's token doesn't unlock cre
+        let lnp = headers_with_token("lnp-token");
+        assert_eq!(readable_podcasts(&cfg, ids(), "/api/chat", &lnp), vec!["freakshow", "lnp"]);
+
+        cfg.auth_token = Some("admin".to_string());
+        assert_eq!(readable_podcasts(&cfg, ids(), "/api/chat", &lnp), vec!["lnp"]);
+        assert_eq!(readable_podcasts(&cfg, ids(), "/api/chat", &headers_with_token("admin")).len(), 3);
+    }
+}
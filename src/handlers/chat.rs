@@ -1,22 +1,35 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::LazyLock;
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use axum::{
-    extract::State,
-    http::{header, HeaderMap, StatusCode},
-    response::IntoResponse,
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode, Uri},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
     Json,
 };
+use futures::{stream, StreamExt};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 
 use crate::cache::{
-    load_speaker_profile_cached, load_speakers_index_cached,
+    load_speaker_profile_cached, load_speakers_index_cached, SpeakerInfo,
 };
-use crate::config::AppConfig;
+use crate::handlers::auth::readable_podcasts;
+use crate::handlers::guard::{guard, llm_unavailable, Access};
+use crate::handlers::episodes::{cross_podcast_ids, group_by_embedding_model, PodcastIndices};
 use crate::cache::load_rag_index_cached;
-use crate::rag::{embeddings::llm_answer, retrieval::retrieve};
+use crate::config::AppConfig;
+use crate::rag::{
+    embeddings::{answer_language, build_answer_prompt, llm_answer, llm_answer_stream, llm_verify_answer, AnswerPrompt, ChatRole, ChatTurn, DEFAULT_ANSWER_LANGUAGE},
+    retrieval::{llm_rerank_order, retrieve, Hit, RagItem},
+};
 use crate::transcript::{excerpt_for_window, load_transcript_entries};
-use crate::utils::seconds_to_hms;
+use crate::utils::{seconds_to_hms, strip_markdown, validate_query};
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -30,6 +43,69 @@ pub struct ChatRequest {
     pub speaker_slug2: Option<String>,
     #[serde(default)]
     pub podcast_id: Option<String>,
+    /// Retrieve from every podcast's index; speakers are still looked up in `podcastId`
+    #[serde(default)]
+    pub cross_podcast: Option<bool>,
+    /// Maximum number of sources a single episode may contribute
+    #[serde(default)]
+    pub max_per_episode: Option<usize>,
+    /// Prefix each source block with the segment summary (if the index has one)
+    #[serde(default)]
+    pub include_summary_in_context: bool,
+    /// Only quote transcript lines in this language ("de"/"en")
+    #[serde(default)]
+    pub excerpt_language: Option<String>,
+    #[serde(default)]
+    pub output_format: OutputFormat,
+    /// Skip the query-embedding cache for this request
+    #[serde(default)]
+    pub no_embed_cache: bool,
+    /// Ask the LLM a second time which answer sentences the sources don't support
+    #[serde(default)]
+    pub verify_answer: bool,
+    /// Rerank hits with Maximal Marginal Relevance to avoid near-duplicate sources
+    #[serde(default)]
+    pub use_mmr: Option<bool>,
+    /// MMR trade-off: 1.0 = pure relevance, 0.0 = pure diversity (default 0.7)
+    #[serde(default)]
+    pub mmr_lambda: Option<f32>,
+    /// Drop hits with a lower cosine score; can't go below the server's `RAG_MIN_SCORE`
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    /// Blend cosine similarity with BM25 keyword scores, so exact terms (names, abbreviations) count
+    #[serde(default)]
+    pub hybrid: Option<bool>,
+    /// Hybrid weight: 1.0 = pure cosine, 0.0 = pure BM25 (default 0.5)
+    #[serde(default)]
+    pub hybrid_alpha: Option<f32>,
+    /// Earlier turns of the conversation, oldest first; the oldest are dropped if the prompt gets too long
+    #[serde(default)]
+    pub history: Vec<ChatTurn>,
+    /// Prefix the retrieval query with the last user turns of `history`, for follow-ups like "und warum?"
+    #[serde(default)]
+    pub history_in_retrieval: bool,
+    /// Sampling temperature (0-2) instead of the server's per-mode default
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Answer length cap; can't exceed the server's `RAG_CHAT_MAX_TOKENS`
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// Answer language code ("de", "en", ...); German if unset
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Let the chat model reorder the top 3×topK vector hits by relevance (one extra LLM call);
+    /// defaults to the server's `RAG_RERANK`
+    #[serde(default)]
+    pub rerank: Option<bool>,
+}
+
+/// Answer format; `plain` strips markdown for clients that render raw text
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Markdown,
+    Plain,
 }
 
 #[derive(Debug, Serialize)]
@@ -37,11 +113,38 @@ pub struct ChatRequest {
 pub struct ChatResponse {
     pub answer: String,
     pub sources: Vec<ChatSource>,
+    /// Only present with `verifyAnswer` and a successful verification pass
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unsupported_sentences: Option<Vec<String>>,
+    /// Episodes cited in the answer that are not among `sources` (likely hallucinated)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unverified_citations: Vec<CitedEpisode>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// What `/api/chat/prompt` returns instead of an answer
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatPromptResponse {
+    pub model: String,
+    pub temperature: f32,
+    pub messages: Vec<PromptMessage>,
+    pub sources: Vec<ChatSource>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PromptMessage {
+    pub role: &'static str,
+    pub content: String,
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ChatSource {
+    pub podcast_id: String,
     pub episode_number: u32,
     pub episode_title: Option<String>,
     pub start_sec: f64,
@@ -55,52 +158,191 @@ pub struct ChatSource {
     pub excerpt: String,
 }
 
-fn extract_auth_token(headers: &HeaderMap) -> Option<String> {
-    // Prefer explicit x-auth-token, but also accept Authorization: Bearer <token>
-    if let Some(v) = headers.get("x-auth-token").and_then(|v| v.to_str().ok()) {
-        let t = v.trim();
-        if !t.is_empty() {
-            return Some(t.to_string());
-        }
+/// Warning when one of the selected speakers has fewer than `min_utterances` utterances in the
+/// speakers index (unknown slugs are not checked); the caller then drops persona mode.
+fn sparse_speaker_warning(speakers: &[SpeakerInfo], slugs: &[&str], min_utterances: u32) -> Option<String> {
+    let sparse: Vec<String> = slugs
+        .iter()
+        .filter_map(|slug| speakers.iter().find(|s| s.slug == *slug))
+        .filter(|s| s.utterances_count < min_utterances)
+        .map(|s| format!("{} ({} utterances)", s.speaker, s.utterances_count))
+        .collect();
+    (!sparse.is_empty()).then(|| {
+        format!(
+            "Persona mode disabled: too little material for {} (minimum {}); answering neutrally",
+            sparse.join(", "),
+            min_utterances
+        )
+    })
+}
+
+/// Answer returned without an LLM call when no source passes the filters
+const NO_SOURCES_ANSWER: &str = "No relevant sources found for this question.";
+
+/// Effective score floor: the stricter of the server default and the request's `minScore`
+fn effective_min_score(server: Option<f32>, request: Option<f32>) -> Option<f32> {
+    let request = request.filter(|v| v.is_finite());
+    match (server, request) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
     }
+}
 
-    if let Some(v) = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok()) {
-        let s = v.trim();
-        if let Some(rest) = s.strip_prefix("Bearer ").or_else(|| s.strip_prefix("bearer ")) {
-            let t = rest.trim();
-            if !t.is_empty() {
-                return Some(t.to_string());
+/// Keep only hits scoring at least `min_score`
+fn filter_hits_by_score(hits: Vec<Hit>, min_score: Option<f32>) -> Vec<Hit> {
+    let Some(min) = min_score else {
+        return hits;
+    };
+    hits.into_iter().filter(|h| h.score >= min).collect()
+}
+
+/// Merge per-model hit lists, keeping the best `keep`. With several embedding models each list
+/// is scaled so its best hit scores 1.0, since raw cosine scores of different models don't compare.
+fn merge_podcast_hits(groups: Vec<Vec<(String, Hit)>>, keep: usize) -> Vec<(String, Hit)> {
+    let rescale = groups.len() > 1;
+    let mut merged: Vec<(String, Hit)> = Vec::new();
+    for mut group in groups {
+        let max = group.iter().map(|(_, h)| h.score).fold(f32::NEG_INFINITY, f32::max);
+        if rescale && max > 0.0 {
+            for (_, h) in group.iter_mut() {
+                h.score /= max;
             }
         }
+        merged.extend(group);
     }
+    merged.sort_by(|a, b| b.1.score.total_cmp(&a.1.score));
+    merged.truncate(keep);
+    merged
+}
 
-    None
+/// `items` in the given index order; indices must be a permutation of `0..items.len()`
+fn reorder<T>(items: Vec<T>, order: &[usize]) -> Vec<T> {
+    let mut slots: Vec<Option<T>> = items.into_iter().map(Some).collect();
+    order.iter().filter_map(|&i| slots.get_mut(i).and_then(Option::take)).collect()
 }
 
-fn is_auth_ok(cfg: &AppConfig, headers: &HeaderMap) -> bool {
-    let Some(expected) = cfg.auth_token.as_ref() else {
-        // No auth configured => allow.
-        return true;
-    };
-    let Some(got) = extract_auth_token(headers) else {
-        return false;
+/// Drop hits once their episode already contributed `max_per_episode` hits, preserving rank order
+fn cap_hits_per_episode(hits: Vec<Hit>, max_per_episode: Option<usize>) -> Vec<Hit> {
+    let Some(max) = max_per_episode else {
+        return hits;
     };
-    got == *expected
+    let mut per_episode: HashMap<u32, usize> = HashMap::new();
+    hits.into_iter()
+        .filter(|h| {
+            let count = per_episode.entry(h.item.episode_number).or_insert(0);
+            *count += 1;
+            *count <= max
+        })
+        .collect()
+}
+
+/// One SOURCE block for the LLM context, optionally led by the segment summary
+fn format_source_block(item: &RagItem, podcast_id: Option<&str>, excerpt: &str, include_summary: bool) -> String {
+    let ep = item.episode_number;
+    let start = item
+        .start_hms
+        .clone()
+        .unwrap_or_else(|| seconds_to_hms(item.start_sec));
+    let end = item
+        .end_hms
+        .clone()
+        .unwrap_or_else(|| seconds_to_hms(item.end_sec));
+    let topic = item
+        .topic
+        .as_ref()
+        .filter(|s| !s.trim().is_empty())
+        .map(|t| format!(" | Topic: {t}"))
+        .unwrap_or_default();
+    let summary = item
+        .summary
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| include_summary && !s.is_empty())
+        .map(|s| format!("Summary: {s}\n"))
+        .unwrap_or_default();
+
+    let podcast = podcast_id.map(|id| format!("{id}, ")).unwrap_or_default();
+
+    format!("SOURCE: {podcast}Episode {ep} ({start} - {end}){topic}\n{summary}{excerpt}\n")
+}
+
+/// Parenthesized text that may hold citations, e.g. "(Episode 281, 12:38-17:19; Episode 282, ...)"
+static PARENTHESIZED: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\(([^()]*)\)").unwrap());
+/// `Episode N`, optionally preceded by the podcast as in cross-podcast source blocks ("lnp, Episode N")
+static EPISODE_REF: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)(?:\b([a-z][\w-]*)\s*,\s*)?\bEpisode\s+(\d+)\b").unwrap());
+
+/// An episode cited in the answer; `podcast_id` only when the citation names one
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CitedEpisode {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub podcast_id: Option<String>,
+    pub episode_number: u32,
+}
+
+/// Episodes cited as `(Episode N, ...)` in `answer` but missing from `sources`, in order of first
+/// appearance. Episode numbers repeat across podcasts, so in `cross_podcast` mode a citation that
+/// names its podcast must match that podcast's sources; one that doesn't can't be told apart and
+/// matches any source with its number.
+fn unverified_citations(answer: &str, sources: &[ChatSource], cross_podcast: bool) -> Vec<CitedEpisode> {
+    let mut unverified = Vec::new();
+    for group in PARENTHESIZED.captures_iter(answer) {
+        for episode in EPISODE_REF.captures_iter(&group[1]) {
+            let Ok(episode_number) = episode[2].parse::<u32>() else {
+                continue;
+            };
+            let podcast_id = episode
+                .get(1)
+                .map(|m| m.as_str().to_lowercase())
+                .filter(|_| cross_podcast);
+            let cited = sources.iter().any(|s| {
+                s.episode_number == episode_number
+                    && podcast_id.as_ref().is_none_or(|p| s.podcast_id.eq_ignore_ascii_case(p))
+            });
+            let citation = CitedEpisode { podcast_id, episode_number };
+            if !cited && !unverified.contains(&citation) {
+                unverified.push(citation);
+            }
+        }
+    }
+    unverified
+}
+
+/// Join source blocks and cut the result to `max_chars` (at a UTF-8 char boundary)
+fn assemble_context(parts: &[String], max_chars: usize) -> String {
+    let mut context = parts.join("\n");
+    if context.len() > max_chars {
+        // Truncate at a valid UTF-8 char boundary
+        let mut truncate_pos = max_chars;
+        while truncate_pos > 0 && !context.is_char_boundary(truncate_pos) {
+            truncate_pos -= 1;
+        }
+        context.truncate(truncate_pos);
+        context.push_str("\n\n[context truncated]\n");
+    }
+    context
 }
 
 pub async fn chat(
     State(st): State<crate::config::AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    uri: Uri,
     headers: HeaderMap,
     Json(req): Json<ChatRequest>,
 ) -> impl IntoResponse {
-    if !is_auth_ok(&st.cfg, &headers) {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(serde_json::json!({ "error": "permission denied" })),
-        )
-            .into_response();
+    // A podcast's own token doesn't unlock a cross-podcast chat when a global token is set;
+    // without one, `prepare_chat` leaves out the podcasts the caller holds no token for
+    let podcast_id = req.podcast_id.as_deref().unwrap_or("freakshow");
+    let auth_scope = Some(podcast_id).filter(|_| !req.cross_podcast.unwrap_or(false));
+    let access = Access::Podcast { podcast: auth_scope, peer };
+    if let Err(resp) = guard(&st, access, uri.path(), &headers).await {
+        return resp;
     }
-    match chat_impl(&st, req).await {
+    if let Some(resp) = llm_unavailable(&st) {
+        return resp;
+    }
+    match chat_impl(&st, req, uri.path(), &headers).await {
         Ok(resp) => (StatusCode::OK, Json(resp)).into_response(),
         Err(e) => {
             tracing::error!("{:?}", e);
@@ -114,68 +356,351 @@ pub async fn chat(
     }
 }
 
-async fn chat_impl(st: &crate::config::AppState, req: ChatRequest) -> Result<ChatResponse> {
-    let query = req.query.trim();
-    if query.is_empty() {
-        return Err(anyhow!("query must not be empty"));
+/// Result of `prepare_chat`: the assembled context and the persona choice for the prompt
+struct PreparedChat {
+    query: String,
+    context: String,
+    sources: Vec<ChatSource>,
+    speaker_profile: Option<String>,
+    speaker2_profile: Option<String>,
+    speaker_name: Option<String>,
+    speaker2_name: Option<String>,
+    /// Earlier turns that fit next to the context
+    history: Vec<ChatTurn>,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+    /// Prompt name of the answer language, e.g. "German"
+    language: &'static str,
+    warnings: Vec<String>,
+}
+
+/// User turns of the history that are prefixed to the retrieval query with `historyInRetrieval`
+const RETRIEVAL_HISTORY_TURNS: usize = 2;
+
+/// The newest turns of `history` whose contents fit into `budget` chars together, oldest first
+fn cap_history(history: &[ChatTurn], budget: usize) -> Vec<ChatTurn> {
+    let mut used = 0;
+    let keep = history
+        .iter()
+        .rev()
+        .take_while(|t| {
+            used += t.content.len();
+            used <= budget
+        })
+        .count();
+    history[history.len() - keep..].to_vec()
+}
+
+/// Retrieval query for a follow-up: the last user turns of the history, then the current question
+fn retrieval_query(history: &[ChatTurn], query: &str) -> String {
+    let mut previous: Vec<&str> = history
+        .iter()
+        .rev()
+        .filter(|t| t.role == ChatRole::User)
+        .map(|t| t.content.trim())
+        .filter(|c| !c.is_empty())
+        .take(RETRIEVAL_HISTORY_TURNS)
+        .collect();
+    previous.reverse();
+    previous.push(query);
+    previous.join("\n")
+}
+
+/// Answer token cap: the request's value bounded by the server's, else the server default
+fn effective_max_tokens(server: Option<u32>, requested: Option<u32>) -> Option<u32> {
+    match (server, requested.filter(|&n| n > 0)) {
+        (Some(server), Some(requested)) => Some(requested.min(server)),
+        (server, requested) => requested.or(server),
+    }
+}
+
+/// The LLM prompt for a prepared chat: answer-mode prompt plus the capped history
+fn answer_prompt(cfg: &AppConfig, prepared: &PreparedChat) -> AnswerPrompt {
+    let mut prompt = build_answer_prompt(
+        cfg.answer_temperatures,
+        prepared.language,
+        &prepared.query,
+        &prepared.context,
+        prepared.speaker_profile.as_deref(),
+        prepared.speaker2_profile.as_deref(),
+        prepared.speaker_name.as_deref(),
+        prepared.speaker2_name.as_deref(),
+    );
+    prompt.history = prepared.history.clone();
+    if let Some(temperature) = prepared.temperature {
+        prompt.temperature = temperature;
+    }
+    prompt.max_tokens = prepared.max_tokens;
+    prompt
+}
+
+/// Streaming variant of `/api/chat` as `text/event-stream`: answer token deltas as `message`
+/// events, then one `event: sources` frame with the sources as JSON. Persona fallbacks are
+/// announced up front as `event: warnings`; a failure mid-answer ends with `event: error`.
+/// `outputFormat` and `verifyAnswer` need the complete answer and are ignored here.
+pub async fn chat_stream(
+    State(st): State<crate::config::AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    uri: Uri,
+    headers: HeaderMap,
+    Json(req): Json<ChatRequest>,
+) -> impl IntoResponse {
+    // A podcast's own token doesn't unlock a cross-podcast chat when a global token is set;
+    // without one, `prepare_chat` leaves out the podcasts the caller holds no token for
+    let podcast_id = req.podcast_id.as_deref().unwrap_or("freakshow");
+    let auth_scope = Some(podcast_id).filter(|_| !req.cross_podcast.unwrap_or(false));
+    let access = Access::Podcast { podcast: auth_scope, peer };
+    if let Err(resp) = guard(&st, access, uri.path(), &headers).await {
+        return resp;
+    }
+    if let Some(resp) = llm_unavailable(&st) {
+        return resp;
+    }
+
+    // Errors before the first token still get a regular JSON error response
+    let started = async {
+        let prepared = prepare_chat(&st, &req, uri.path(), &headers).await?;
+        let tokens = if prepared.sources.is_empty() {
+            stream::once(async { Ok(NO_SOURCES_ANSWER.to_string()) }).boxed()
+        } else {
+            llm_answer_stream(&st, &answer_prompt(&st.cfg, &prepared)).await?.boxed()
+        };
+        anyhow::Ok((prepared.sources, prepared.warnings, tokens))
+    };
+    match started.await {
+        Ok((sources, warnings, tokens)) => Sse::new(answer_events(sources, warnings, tokens))
+            .keep_alive(KeepAlive::default())
+            .into_response(),
+        Err(e) => {
+            tracing::error!("{:?}", e);
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// SSE frames for a streamed answer: optional warnings, token deltas, then sources.
+/// An upstream error ends the token part with an `error` frame.
+fn answer_events(
+    sources: Vec<ChatSource>,
+    warnings: Vec<String>,
+    tokens: impl futures::Stream<Item = Result<String>> + Send + 'static,
+) -> impl futures::Stream<Item = Result<Event, std::convert::Infallible>> {
+    let warnings = (!warnings.is_empty()).then(|| json_event("warnings", &warnings));
+    let tokens = tokens.scan(false, |failed, delta| {
+        if *failed {
+            return futures::future::ready(None);
+        }
+        let event = match delta {
+            // SSE can't carry carriage returns; axum splits newlines into data lines
+            Ok(delta) => Event::default().data(delta.replace('\r', "")),
+            Err(e) => {
+                tracing::warn!("Chat stream failed: {:?}", e);
+                *failed = true;
+                Event::default().event("error").data(e.to_string().replace(['\r', '\n'], " "))
+            }
+        };
+        futures::future::ready(Some(event))
+    });
+    stream::iter(warnings)
+        .chain(tokens)
+        .chain(stream::once(async move { json_event("sources", &sources) }))
+        .map(Ok)
+}
+
+/// Named SSE event with a JSON payload
+fn json_event<T: Serialize>(name: &str, payload: &T) -> Event {
+    Event::default()
+        .event(name)
+        .data(serde_json::to_string(payload).unwrap_or_else(|_| "null".to_string()))
+}
+
+/// Prompt debugging: the messages `/api/chat` would send for this request, without calling the LLM.
+/// Admin only: needs the global token, so it is closed when none is configured; podcast-scoped
+/// tokens are not accepted.
+pub async fn chat_prompt(
+    State(st): State<crate::config::AppState>,
+    uri: Uri,
+    headers: HeaderMap,
+    Json(req): Json<ChatRequest>,
+) -> impl IntoResponse {
+    if let Err(resp) = guard(&st, Access::Admin, uri.path(), &headers).await {
+        return resp;
+    }
+    if let Some(resp) = llm_unavailable(&st) {
+        return resp;
+    }
+    match prepare_chat(&st, &req, uri.path(), &headers).await {
+        Ok(prepared) => (StatusCode::OK, Json(prompt_preview(&st.cfg, prepared))).into_response(),
+        Err(e) => {
+            tracing::error!("{:?}", e);
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Build the answer prompt for `prepared`; the API key is redacted should it appear anywhere
+fn prompt_preview(cfg: &AppConfig, prepared: PreparedChat) -> ChatPromptResponse {
+    let prompt = answer_prompt(cfg, &prepared);
+    let redact = |s: String| {
+        if cfg.llm_api_key.is_empty() {
+            s
+        } else {
+            s.replace(&cfg.llm_api_key, "[redacted]")
+        }
+    };
+    ChatPromptResponse {
+        model: cfg.llm_model.clone(),
+        temperature: prompt.temperature,
+        messages: std::iter::once(PromptMessage { role: "system", content: redact(prompt.system) })
+            .chain(prompt.history.into_iter().map(|t| PromptMessage { role: t.role.as_str(), content: redact(t.content) }))
+            .chain(std::iter::once(PromptMessage { role: "user", content: redact(prompt.user) }))
+            .collect(),
+        sources: prepared.sources,
+        warnings: prepared.warnings,
     }
+}
+
+/// Retrieval and context assembly for a chat request: everything up to the LLM call.
+/// `path` and `headers` decide which podcasts a cross-podcast chat may read.
+async fn prepare_chat(st: &crate::config::AppState, req: &ChatRequest, path: &str, headers: &HeaderMap) -> Result<PreparedChat> {
+    // Reject empty/too-short queries before spending an embedding call
+    let query = validate_query(&req.query, st.cfg.min_query_len)?;
+    let language = match req.language.as_deref() {
+        Some(code) => answer_language(code)
+            .ok_or_else(|| anyhow::anyhow!("unsupported answer language: {}", code))?,
+        None => DEFAULT_ANSWER_LANGUAGE,
+    };
 
     // Determine podcast ID from request or use default
     let podcast_id = req.podcast_id.as_deref().unwrap_or("freakshow");
-    
-    // Load RAG database for this podcast (with caching)
-    let rag = load_rag_index_cached(st, podcast_id).await?;
-    
-    // Use podcast-specific episodes directory
-    let episodes_dir = PathBuf::from(format!("podcasts/{}/episodes", podcast_id));
+    let cross_podcast = req.cross_podcast.unwrap_or(false);
+
+    // Load RAG databases (with caching); in cross-podcast mode unloadable indices are skipped
+    let rag_indices: PodcastIndices = if cross_podcast {
+        let readable = readable_podcasts(&st.cfg, cross_podcast_ids(st).await?, path, headers);
+        if readable.is_empty() {
+            return Err(anyhow::anyhow!("No podcasts are accessible with the given token"));
+        }
+        let mut indices = Vec::new();
+        for id in readable {
+            match load_rag_index_cached(st, &id).await {
+                Ok(rag) => indices.push((id, rag)),
+                Err(e) => tracing::warn!("Failed to load RAG index for {}: {}", id, e),
+            }
+        }
+        if indices.is_empty() {
+            return Err(anyhow::anyhow!("No RAG indices could be loaded"));
+        }
+        indices
+    } else {
+        vec![(podcast_id.to_string(), load_rag_index_cached(st, podcast_id).await?)]
+    };
 
     let top_k = req.top_k.unwrap_or(st.cfg.top_k).clamp(1, 20);
 
-    // Get speaker name from slug if requested (using cached speakers index)
-    let speaker_name = if let Some(slug) = req.speaker_slug.as_ref() {
+    // Speakers index is only needed for persona/discussion mode
+    let speakers = if req.speaker_slug.is_some() || req.speaker_slug2.is_some() {
         load_speakers_index_cached(st, podcast_id).await.ok()
-            .and_then(|speakers| speakers.iter().find(|s| s.slug == *slug).map(|s| s.speaker.clone()))
     } else {
         None
     };
-    
-    // Get second speaker name from slug if requested (discussion mode)
-    let speaker2_name = if let Some(slug) = req.speaker_slug2.as_ref() {
-        load_speakers_index_cached(st, podcast_id).await.ok()
-            .and_then(|speakers| speakers.iter().find(|s| s.slug == *slug).map(|s| s.speaker.clone()))
-    } else {
-        None
+    let speakers = speakers.as_deref().unwrap_or(&[]);
+
+    // Personas of speakers with too few utterances are unreliable: answer neutrally instead
+    let selected_slugs: Vec<&str> = [req.speaker_slug.as_deref(), req.speaker_slug2.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect();
+    let mut warnings = Vec::new();
+    let persona_allowed = match sparse_speaker_warning(speakers, &selected_slugs, st.cfg.min_persona_utterances) {
+        Some(warning) => {
+            warnings.push(warning);
+            false
+        }
+        None => true,
     };
+    let speaker_slug = req.speaker_slug.as_ref().filter(|_| persona_allowed);
+    let speaker_slug2 = req.speaker_slug2.as_ref().filter(|_| persona_allowed);
+
+    // Get speaker names from slugs if requested (second one: discussion mode)
+    let speaker_name = speaker_slug
+        .and_then(|slug| speakers.iter().find(|s| s.slug == *slug).map(|s| s.speaker.clone()));
+    let speaker2_name = speaker_slug2
+        .and_then(|slug| speakers.iter().find(|s| s.slug == *slug).map(|s| s.speaker.clone()));
 
     // Load speaker profile if requested (with caching)
-    let speaker_profile = if let Some(slug) = req.speaker_slug.as_ref() {
+    let speaker_profile = if let Some(slug) = speaker_slug {
         load_speaker_profile_cached(st, podcast_id, slug).await.ok()
     } else {
         None
     };
     
     // Load second speaker profile if requested (discussion mode, with caching)
-    let speaker2_profile = if let Some(slug) = req.speaker_slug2.as_ref() {
+    let speaker2_profile = if let Some(slug) = speaker_slug2 {
         load_speaker_profile_cached(st, podcast_id, slug).await.ok()
     } else {
         None
     };
 
-    // 1) Retrieve - get more results if we need to filter by speaker
-    let search_k = if speaker_name.is_some() || speaker2_name.is_some() {
+    let max_per_episode = req.max_per_episode.map(|m| m.max(1));
+
+    // 1) Retrieve - get more results if we need to filter by speaker or spread across episodes
+    // Reranking also picks from a 3×K candidate pool
+    let rerank = req.rerank.unwrap_or(st.cfg.rerank_default);
+    let search_k = if speaker_name.is_some() || speaker2_name.is_some() || max_per_episode.is_some() || rerank {
         top_k * 3
     } else {
         top_k
     };
-    let hits = retrieve(st, &rag, query, search_k).await?;
+    let mmr_lambda = req
+        .use_mmr
+        .unwrap_or(false)
+        .then(|| req.mmr_lambda.filter(|l| l.is_finite()).unwrap_or(0.7).clamp(0.0, 1.0));
+    let hybrid_alpha = req
+        .hybrid
+        .unwrap_or(false)
+        .then(|| req.hybrid_alpha.filter(|a| a.is_finite()).unwrap_or(0.5).clamp(0.0, 1.0));
+    let search_query = if req.history_in_retrieval {
+        retrieval_query(&req.history, query)
+    } else {
+        query.to_string()
+    };
+    // Score filter and episode cap apply per index, before scores of different models are rescaled
+    let min_score = effective_min_score(st.cfg.min_score, req.min_score);
+    let mut model_groups: Vec<Vec<(String, Hit)>> = Vec::new();
+    for group in group_by_embedding_model(&rag_indices, st.cfg.cross_model_policy)? {
+        let mut group_hits = Vec::new();
+        for (id, rag) in group {
+            let hits = retrieve(st, &rag, &search_query, search_k, !req.no_embed_cache, mmr_lambda, hybrid_alpha).await?;
+            let hits = cap_hits_per_episode(filter_hits_by_score(hits, min_score), max_per_episode);
+            group_hits.extend(hits.into_iter().map(|h| (id.clone(), h)));
+        }
+        model_groups.push(group_hits);
+    }
+    let mut hits = merge_podcast_hits(model_groups, search_k);
+    if rerank {
+        hits.truncate(top_k * 3);
+        let items: Vec<&RagItem> = hits.iter().map(|(_, h)| &h.item).collect();
+        let order = llm_rerank_order(st, query, &items).await;
+        hits = reorder(hits, &order);
+    }
 
     // 2) Build context from transcripts
     let mut sources: Vec<ChatSource> = Vec::with_capacity(hits.len());
     let mut context_parts: Vec<String> = Vec::with_capacity(hits.len());
 
-    for h in hits {
+    for (hit_podcast_id, h) in hits {
+        let episodes_dir = st.cfg.episodes_dir_of(&hit_podcast_id);
         let transcript =
-            load_transcript_entries(st, podcast_id, &episodes_dir, h.item.episode_number).await?;
+            load_transcript_entries(st, &hit_podcast_id, &episodes_dir, h.item.episode_number).await?;
 
         // If discussion mode is active (two speakers), build per-speaker excerpts so each position
         // is grounded in that speaker's actual transcript lines.
@@ -188,6 +713,7 @@ async fn chat_impl(st: &crate::config::AppState, req: ChatRequest) -> Result<Cha
                 h.item.end_sec,
                 2200,
                 Some(name1),
+                req.excerpt_language.as_deref(),
             );
             let ex2 = excerpt_for_window(
                 &transcript,
@@ -195,6 +721,7 @@ async fn chat_impl(st: &crate::config::AppState, req: ChatRequest) -> Result<Cha
                 h.item.end_sec,
                 2200,
                 Some(name2),
+                req.excerpt_language.as_deref(),
             );
 
             let empty1 = ex1.contains("[no transcript entries found");
@@ -209,6 +736,7 @@ async fn chat_impl(st: &crate::config::AppState, req: ChatRequest) -> Result<Cha
                 h.item.end_sec,
                 4000,
                 speaker_name.as_deref(),
+                req.excerpt_language.as_deref(),
             );
             // Skip empty excerpts when filtering by a single speaker
             let should_skip = speaker_name.is_some() && ex.contains("[no transcript entries found");
@@ -219,28 +747,13 @@ async fn chat_impl(st: &crate::config::AppState, req: ChatRequest) -> Result<Cha
             continue;
         }
 
-        let ep = h.item.episode_number;
-        let start = h
-            .item
-            .start_hms
-            .clone()
-            .unwrap_or_else(|| seconds_to_hms(h.item.start_sec));
-        let end = h
-            .item
-            .end_hms
-            .clone()
-            .unwrap_or_else(|| seconds_to_hms(h.item.end_sec));
         let topic = h.item.topic.clone().filter(|s| !s.trim().is_empty());
-
-        context_parts.push(format!(
-            "SOURCE: Episode {ep} ({start} - {end}){}\n{excerpt}\n",
-            topic
-                .as_ref()
-                .map(|t| format!(" | Topic: {t}"))
-                .unwrap_or_default()
-        ));
+        // Episode numbers repeat across podcasts, so cross-podcast sources name their podcast
+        let podcast_label = cross_podcast.then_some(hit_podcast_id.as_str());
+        context_parts.push(format_source_block(&h.item, podcast_label, &excerpt, req.include_summary_in_context));
 
         sources.push(ChatSource {
+            podcast_id: hit_podcast_id.clone(),
             episode_number: h.item.episode_number,
             episode_title: h
                 .item
@@ -264,29 +777,388 @@ async fn chat_impl(st: &crate::config::AppState, req: ChatRequest) -> Result<Cha
         }
     }
 
-    // Keep prompt bounded.
-    let mut context = context_parts.join("\n");
-    if context.len() > st.cfg.max_context_chars {
-        // Truncate at a valid UTF-8 char boundary
-        let mut truncate_pos = st.cfg.max_context_chars;
-        while truncate_pos > 0 && !context.is_char_boundary(truncate_pos) {
-            truncate_pos -= 1;
-        }
-        context.truncate(truncate_pos);
-        context.push_str("\n\n[context truncated]\n");
+    // Keep prompt bounded; history only gets what the context leaves over
+    let context = assemble_context(&context_parts, st.cfg.max_context_chars);
+    let history = cap_history(&req.history, st.cfg.max_context_chars.saturating_sub(context.len()));
+
+    Ok(PreparedChat {
+        query: query.to_string(),
+        context,
+        sources,
+        speaker_profile,
+        speaker2_profile,
+        speaker_name,
+        speaker2_name,
+        history,
+        temperature: req.temperature.filter(|t| t.is_finite()).map(|t| t.clamp(0.0, 2.0)),
+        max_tokens: effective_max_tokens(st.cfg.chat_max_tokens, req.max_tokens),
+        language,
+        warnings,
+    })
+}
+
+async fn chat_impl(st: &crate::config::AppState, req: ChatRequest, path: &str, headers: &HeaderMap) -> Result<ChatResponse> {
+    let prepared = prepare_chat(st, &req, path, headers).await?;
+
+    // Nothing relevant left: don't let the LLM answer from an empty context
+    if prepared.sources.is_empty() {
+        return Ok(ChatResponse {
+            answer: NO_SOURCES_ANSWER.to_string(),
+            sources: prepared.sources,
+            unsupported_sentences: None,
+            unverified_citations: Vec::new(),
+            warnings: prepared.warnings,
+        });
     }
 
     // 3) Ask LLM
-    let answer = llm_answer(
-        st, 
-        query, 
-        &context, 
-        speaker_profile.as_deref(),
-        speaker2_profile.as_deref(),
-        speaker_name.as_deref(),
-        speaker2_name.as_deref(),
-    ).await?;
-
-    Ok(ChatResponse { answer, sources })
+    let answer = llm_answer(st, &answer_prompt(&st.cfg, &prepared)).await?;
+    let PreparedChat { context, sources, warnings, .. } = prepared;
+    let answer = match req.output_format {
+        OutputFormat::Markdown => answer,
+        OutputFormat::Plain => strip_markdown(&answer),
+    };
+
+    // 4) Optional entailment check; a failed check keeps the answer but omits the field
+    let unsupported_sentences = if req.verify_answer {
+        match llm_verify_answer(st, &answer, &context).await {
+            Ok(unsupported) => Some(unsupported),
+            Err(e) => {
+                tracing::warn!("Answer verification failed: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let unverified_citations = unverified_citations(&answer, &sources, req.cross_podcast.unwrap_or(false));
+    if !unverified_citations.is_empty() {
+        tracing::warn!("Answer cites episodes outside its sources: {:?}", unverified_citations);
+    }
+
+    Ok(ChatResponse { answer, sources, unsupported_sentences, unverified_citations, warnings })
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+
+    fn hit(episode_number: u32, start_sec: f64, score: f32) -> Hit {
+        Hit {
+            item: RagItem::test_item(episode_number, start_sec),
+            score,
+        }
+    }
+
+    /// Neutral German chat over `context` without persona, history or limits
+    fn prepared(query: &str, context: String) -> PreparedChat {
+        PreparedChat {
+            query: query.to_string(),
+            context,
+            sources: Vec::new(),
+            speaker_profile: None,
+            speaker2_profile: None,
+            speaker_name: None,
+            speaker2_name: None,
+            history: Vec::new(),
+            temperature: None,
+            max_tokens: None,
+            language: DEFAULT_ANSWER_LANGUAGE,
+            warnings: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_sends_tokens_then_sources() {
+        let source = ChatSource {
+            podcast_id: "freakshow".to_string(),
+            episode_number: 281,
+            episode_title: None,
+            start_sec: 758.0,
+            end_sec: 1039.0,
+            start_hms: None,
+            end_hms: None,
+            score: 0.9,
+            topic: None,
+            subject_coarse: None,
+            subject_fine: None,
+            excerpt: "Universal Control".to_string(),
+        };
+        let tokens = stream::iter(vec![Ok("Hallo".to_string()), Ok(" Welt".to_string())]);
+        let resp = Sse::new(answer_events(vec![source], Vec::new(), tokens)).into_response();
+        assert_eq!(resp.headers()["content-type"], "text/event-stream");
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        let hallo = body.find("data: Hallo").unwrap();
+        let welt = body.find("data:  Welt").unwrap();
+        let sources = body.find("event: sources").unwrap();
+        assert!(hallo < welt && welt < sources);
+        assert!(body[sources..].contains("\"episodeNumber\":281"));
+
+        // An upstream error ends the tokens with an error frame; sources still close the stream
+        let tokens = stream::iter(vec![Ok("Hallo".to_string()), Err(anyhow::anyhow!("upstream gone")), Ok("nie".to_string())]);
+        let resp = Sse::new(answer_events(Vec::new(), Vec::new(), tokens)).into_response();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("event: error\ndata: upstream gone"));
+        assert!(!body.contains("nie"));
+        assert!(body.contains("event: sources\ndata: []"));
+    }
+
+    #[test]
+    fn test_prompt_preview_contains_sources_and_language() {
+        let mut cfg = AppConfig::for_tests();
+        cfg.llm_api_key = "sk-secret".to_string();
+        let mut item = RagItem::test_item(281, 758.0);
+        item.end_sec = 1039.0;
+        let excerpt = "[0:12:38] Tim: Universal Control funktioniert erstaunlich gut.";
+        let context = assemble_context(&[format_source_block(&item, None, excerpt, false)], 24_000);
+        let prepared = prepared("Was ist Universal Control? sk-secret", context);
+
+        let preview = prompt_preview(&cfg, prepared);
+        assert_eq!(preview.messages[0].role, "system");
+        assert!(preview.messages[0].content.contains("in German unless the user asks otherwise"));
+        let user = &preview.messages[1].content;
+        assert!(user.contains("SOURCE: Episode 281 (12:38 - 17:19)") && user.contains(excerpt));
+        assert!(!user.contains("sk-secret") && user.contains("[redacted]"));
+        assert_eq!(preview.temperature, cfg.answer_temperatures.neutral);
+    }
+
+    #[test]
+    fn test_unverified_citations_flags_episodes_outside_sources() {
+        let source = |episode_number| ChatSource {
+            podcast_id: "freakshow".to_string(),
+            episode_number,
+            episode_title: None,
+            start_sec: 0.0,
+            end_sec: 60.0,
+            start_hms: None,
+            end_hms: None,
+            score: 0.9,
+            topic: None,
+            subject_coarse: None,
+            subject_fine: None,
+            excerpt: String::new(),
+        };
+        let sources = vec![source(281), source(282)];
+        let answer = "Tim nutzt Universal Control (Episode 281, 12:38-17:19). Apple hat es erfunden \
+            (Episode 999, 1:00-2:00; episode 282, 3:00-4:00). Siehe auch (Episode 17) und nochmal \
+            (Episode 999, 5:00). Die Episode 500 wird nur erwähnt.";
+
+        let episode = |episode_number| CitedEpisode { podcast_id: None, episode_number };
+        assert_eq!(unverified_citations(answer, &sources, false), vec![episode(999), episode(17)]);
+        assert!(unverified_citations("(Episode 281, 12:38-17:19)", &sources, false).is_empty());
+
+        // Cross-podcast: a named podcast has to match, not just the number
+        let cross = "Siehe (lnp, Episode 281, 1:00-2:00) und (Freakshow, Episode 282).";
+        assert_eq!(
+            unverified_citations(cross, &sources, true),
+            vec![CitedEpisode { podcast_id: Some("lnp".to_string()), episode_number: 281 }]
+        );
+        assert!(unverified_citations("(Episode 281)", &sources, true).is_empty());
+    }
+
+    #[test]
+    fn test_request_overrides_temperature_and_caps_max_tokens() {
+        assert_eq!(effective_max_tokens(None, None), None);
+        assert_eq!(effective_max_tokens(Some(800), None), Some(800));
+        assert_eq!(effective_max_tokens(None, Some(300)), Some(300));
+        assert_eq!(effective_max_tokens(Some(800), Some(5000)), Some(800));
+        assert_eq!(effective_max_tokens(Some(800), Some(0)), Some(800));
+
+        let cfg = AppConfig::for_tests();
+        let mut prepared = PreparedChat {
+            speaker_profile: Some("Profil".to_string()),
+            speaker_name: Some("Tim".to_string()),
+            max_tokens: Some(300),
+            ..prepared("Frage", "SOURCE".to_string())
+        };
+        let prompt = answer_prompt(&cfg, &prepared);
+        assert_eq!((prompt.temperature, prompt.max_tokens), (cfg.answer_temperatures.persona, Some(300)));
+        prepared.temperature = Some(1.1);
+        assert_eq!(answer_prompt(&cfg, &prepared).temperature, 1.1);
+    }
+
+    #[test]
+    fn test_answer_language_is_templated_into_every_mode() {
+        assert_eq!(answer_language("EN"), Some("English"));
+        assert_eq!(answer_language(" de "), Some("German"));
+        assert_eq!(answer_language("xx"), None);
+
+        let cfg = AppConfig::for_tests();
+        let mut prepared = PreparedChat {
+            language: "English",
+            ..prepared("Frage", "SOURCE".to_string())
+        };
+        let modes = [(None, None), (Some("Tim"), None), (Some("Tim"), Some("Clemens"))];
+        for (name, name2) in modes {
+            prepared.speaker_profile = name.map(|_| "Profil".to_string());
+            prepared.speaker_name = name.map(str::to_string);
+            prepared.speaker2_profile = name2.map(|_| "Profil 2".to_string());
+            prepared.speaker2_name = name2.map(str::to_string);
+            let system = answer_prompt(&cfg, &prepared).system;
+            assert!(system.contains("Answer in English unless the user asks otherwise"), "{system}");
+            assert!(!system.contains("German"), "{system}");
+        }
+    }
+
+    #[test]
+    fn test_history_keeps_newest_turns_within_budget() {
+        let turn = |role, content: &str| ChatTurn { role, content: content.to_string() };
+        let history = vec![
+            turn(ChatRole::User, "Was nutzt Tim?"),
+            turn(ChatRole::Assistant, "Universal Control."),
+            turn(ChatRole::User, "Seit wann?"),
+            turn(ChatRole::Assistant, "Seit 2022."),
+        ];
+
+        let kept = cap_history(&history, 25);
+        assert_eq!(kept.iter().map(|t| t.content.as_str()).collect::<Vec<_>>(), vec!["Seit wann?", "Seit 2022."]);
+        assert_eq!(cap_history(&history, 1_000).len(), 4);
+        assert!(cap_history(&history, 5).is_empty());
+
+        assert_eq!(retrieval_query(&history, "Und Clemens?"), "Was nutzt Tim?\nSeit wann?\nUnd Clemens?");
+        assert_eq!(retrieval_query(&[], "Und Clemens?"), "Und Clemens?");
+    }
+
+    #[test]
+    fn test_sparse_speaker_falls_back_to_neutral_with_warning() {
+        let speaker = |name: &str, slug: &str, utterances: u32| SpeakerInfo {
+            speaker: name.to_string(),
+            slug: slug.to_string(),
+            episodes_count: 1,
+            utterances_count: utterances,
+            total_words: 0,
+            has_profile: true,
+            image: None,
+        };
+        let speakers = vec![speaker("Tim Pritlove", "tim-pritlove", 5000), speaker("Gast", "gast", 12)];
+
+        let warning = sparse_speaker_warning(&speakers, &["gast"], 100).unwrap();
+        assert!(warning.contains("Gast (12 utterances)"));
+        assert!(sparse_speaker_warning(&speakers, &["tim-pritlove", "gast"], 100).is_some());
+        assert!(sparse_speaker_warning(&speakers, &["tim-pritlove"], 100).is_none());
+        // Disabled by default
+        assert!(sparse_speaker_warning(&speakers, &["gast"], 0).is_none());
+    }
+
+    #[test]
+    fn test_cap_hits_per_episode() {
+        // Episode 1 dominates the top of the ranking
+        let hits = vec![
+            hit(1, 0.0, 0.99),
+            hit(1, 60.0, 0.98),
+            hit(1, 120.0, 0.97),
+            hit(1, 180.0, 0.96),
+            hit(2, 0.0, 0.80),
+            hit(1, 240.0, 0.79),
+            hit(3, 0.0, 0.70),
+        ];
+
+        let capped = cap_hits_per_episode(hits.clone(), Some(2));
+        let episodes: Vec<u32> = capped.iter().map(|h| h.item.episode_number).collect();
+        assert_eq!(episodes, vec![1, 1, 2, 3]);
+
+        let uncapped = cap_hits_per_episode(hits, None);
+        assert_eq!(uncapped.len(), 7);
+    }
+
+    #[test]
+    fn test_reorder_follows_rerank_order() {
+        assert_eq!(reorder(vec!["a", "b", "c"], &[2, 0, 1]), vec!["c", "a", "b"]);
+        assert_eq!(reorder(vec!["a", "b"], &[0, 1]), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_merge_podcast_hits_rescales_only_across_models() {
+        let tagged = |podcast: &str, episode, score| (podcast.to_string(), hit(episode, 0.0, score));
+        let order = |hits: &[(String, Hit)]| {
+            hits.iter().map(|(p, h)| (p.clone(), h.item.episode_number, h.score)).collect::<Vec<_>>()
+        };
+
+        // One model: raw scores compare directly, same episode number stays apart per podcast
+        let same_model = vec![vec![tagged("freakshow", 281, 0.6), tagged("lnp", 281, 0.8), tagged("lnp", 5, 0.4)]];
+        assert_eq!(
+            order(&merge_podcast_hits(same_model, 2)),
+            vec![("lnp".to_string(), 281, 0.8), ("freakshow".to_string(), 281, 0.6)]
+        );
+
+        // Two models: each group's best hit becomes 1.0
+        let two_models = vec![
+            vec![tagged("freakshow", 1, 0.5), tagged("freakshow", 2, 0.25)],
+            vec![tagged("lnp", 7, 0.8), tagged("lnp", 8, 0.6)],
+        ];
+        assert_eq!(
+            order(&merge_podcast_hits(two_models, 10)),
+            vec![
+                ("freakshow".to_string(), 1, 1.0),
+                ("lnp".to_string(), 7, 1.0),
+                ("lnp".to_string(), 8, 0.75),
+                ("freakshow".to_string(), 2, 0.5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cross_podcast_source_block_names_podcast() {
+        let item = RagItem::test_item(281, 0.0);
+        let block = format_source_block(&item, Some("lnp"), "[0:00:00] Tim: Hallo", false);
+        assert!(block.starts_with("SOURCE: lnp, Episode 281 (0:00 - 1:00)"), "{block}");
+    }
+
+    #[test]
+    fn test_min_score_filters_weak_hits() {
+        let hits = vec![hit(1, 0.0, 0.82), hit(2, 0.0, 0.41), hit(3, 0.0, 0.29)];
+
+        // The request can raise the server floor but not lower it
+        assert_eq!(effective_min_score(Some(0.3), Some(0.1)), Some(0.3));
+        assert_eq!(effective_min_score(Some(0.3), Some(0.5)), Some(0.5));
+        assert_eq!(effective_min_score(None, Some(f32::NAN)), None);
+
+        let kept = filter_hits_by_score(hits.clone(), effective_min_score(Some(0.3), None));
+        let episodes: Vec<u32> = kept.iter().map(|h| h.item.episode_number).collect();
+        assert_eq!(episodes, vec![1, 2]);
+        assert!(filter_hits_by_score(hits.clone(), Some(0.9)).is_empty());
+        assert_eq!(filter_hits_by_score(hits, None).len(), 3);
+    }
+
+    #[test]
+    fn test_summary_in_context_when_enabled() {
+        let item = RagItem {
+            summary: Some("Tim erklärt Universal Control".to_string()),
+            ..RagItem::test_item(281, 758.0)
+        };
+        let excerpt = "[0:12:38] Tim: Das funktioniert einfach.";
+
+        let parts = vec![format_source_block(&item, None, excerpt, true)];
+        let context = assemble_context(&parts, 24_000);
+        assert!(context.contains("Summary: Tim erklärt Universal Control\n[0:12:38]"));
+
+        let parts = vec![format_source_block(&item, None, excerpt, false)];
+        let context = assemble_context(&parts, 24_000);
+        assert!(!context.contains("Summary:"));
+        assert!(context.contains(excerpt));
+    }
+
+    #[tokio::test]
+    async fn test_short_query_rejected_before_embedding() {
+        use crate::rag::embeddings::tests::mock_counting_embeddings_server;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut cfg = AppConfig::for_tests();
+        cfg.embedding_base_url = mock_counting_embeddings_server(vec![1.0, 0.0], calls.clone()).await;
+        cfg.min_query_len = 3;
+        let st = crate::config::AppState::for_tests(cfg);
+
+        let req: ChatRequest = serde_json::from_value(serde_json::json!({ "query": "  ab  " })).unwrap();
+        let err = chat_impl(&st, req, "/api/chat", &HeaderMap::new()).await.unwrap_err();
+        assert!(err.to_string().contains("at least 3 characters"));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+}
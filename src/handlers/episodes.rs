@@ -1,11 +1,14 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{ConnectInfo, Path, Query, State},
+    http::{header, HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Response},
     Json,
 };
 use rayon::prelude::*;
@@ -13,11 +16,48 @@ use serde::{Deserialize, Serialize};
 
 use crate::cache::{
     check_episode_files_batch_cached, load_episode_list_cached, load_episode_metadata_batch_cached, load_episode_topics_map_cached,
+    EpisodeMetadata, EpisodeTopicsMap,
 };
-use crate::config::AppState as AppStateType;
-use crate::cache::load_rag_index_cached;
-use crate::rag::embeddings::embed_query;
-use crate::utils::{dot, l2_norm};
+use crate::config::{AppState as AppStateType, CrossModelPolicy};
+use crate::handlers::guard::{guard, llm_unavailable, Access};
+use crate::cache::{load_rag_index_cached, load_title_embeddings_cached};
+use crate::rag::embeddings::{embed_query, embed_query_with_model};
+use crate::transcript::{load_transcript_entries, transcript_to_vtt, transcript_window, TranscriptEntry};
+use crate::rag::retrieval::item_cosine;
+use crate::utils::{l2_norm, normalize_for_match, validate_query};
+
+/// Episode key across podcasts: (podcast_id, episode_number)
+type EpisodeKey = (String, u32);
+/// Matching positions within an episode as (start_sec, score)
+type ScoredPositions = Vec<(f64, f32)>;
+/// Scored item across podcasts: (podcast_id, item index, score)
+type ScoredItem = (String, usize, f32);
+/// Episodes allowed per podcast (date range of a search)
+type EpisodeFilter = HashMap<String, HashSet<u32>>;
+/// Loaded indices as (podcast_id, index)
+pub(crate) type PodcastIndices = Vec<(String, Arc<crate::rag::RagIndex>)>;
+
+/// Scoring exceeded `cfg.search_timeout`; answered with 408
+#[derive(Debug)]
+struct SearchTimeout(Duration);
+
+impl std::fmt::Display for SearchTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "search timed out after {} ms", self.0.as_millis())
+    }
+}
+
+impl std::error::Error for SearchTimeout {}
+
+/// Sets the flag when dropped, so blocking work stops once the request is gone
+/// (timeout or client disconnect).
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, AtomicOrdering::Relaxed);
+    }
+}
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -33,6 +73,30 @@ pub struct EpisodesSearchRequest {
     pub offset: Option<usize>,
     #[serde(default)]
     pub limit: Option<usize>,
+    /// Skip the query-embedding cache for this request
+    #[serde(default)]
+    pub no_embed_cache: bool,
+    /// Positions closer than this (seconds) count as one match position (default 1.0)
+    #[serde(default)]
+    pub position_dedup_sec: Option<f64>,
+    /// Only episodes published on or after this day (YYYY-MM-DD)
+    #[serde(default)]
+    pub from_date: Option<String>,
+    /// Only episodes published on or before this day (YYYY-MM-DD)
+    #[serde(default)]
+    pub to_date: Option<String>,
+    /// Only keep matches whose text or summary contains every one of these terms (case-insensitive)
+    #[serde(default)]
+    pub must_contain: Option<Vec<String>>,
+    /// Only keep matches with this coarse subject (case-insensitive)
+    #[serde(default)]
+    pub filter_coarse: Option<String>,
+    /// Only keep matches with this fine subject (case-insensitive)
+    #[serde(default)]
+    pub filter_fine: Option<String>,
+    /// Add subject facets of the candidate episodes to the response
+    #[serde(default)]
+    pub include_facets: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -44,6 +108,36 @@ pub struct EpisodesLatestRequest {
     pub limit: Option<usize>,
     #[serde(default)]
     pub offset: Option<usize>,
+    #[serde(default)]
+    pub sort: EpisodeSort,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarEpisodesQuery {
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Optional playback window (seconds) for the JSON transcript
+#[derive(Debug, Deserialize)]
+pub struct TranscriptQuery {
+    #[serde(default)]
+    pub from_sec: Option<f64>,
+    #[serde(default)]
+    pub to_sec: Option<f64>,
+}
+
+/// Scanned items per requested similar episode, on top of the source episode's own items
+const SIMILAR_ITEMS_PER_RESULT: usize = 20;
+
+/// Ordering for `episodes_latest` (newest first in both modes)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EpisodeSort {
+    #[default]
+    Number,
+    Date,
 }
 
 #[derive(Debug, Serialize)]
@@ -52,6 +146,63 @@ pub struct EpisodesSearchResponse {
     pub episodes: Vec<EpisodeSearchResult>,
     pub has_more: bool,
     pub total: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<SubjectFacets>,
+}
+
+/// Subject values over the candidate episodes of a search, before subject filters and paging
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SubjectFacets {
+    pub coarse: Vec<FacetCount>,
+    pub fine: Vec<FacetCount>,
+}
+
+/// Number of candidate episodes with at least one matching item of this subject
+#[derive(Debug, Serialize, PartialEq)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Collects the episodes per subject value for `SubjectFacets`
+#[derive(Default)]
+struct FacetCounter {
+    coarse: HashMap<String, HashSet<EpisodeKey>>,
+    fine: HashMap<String, HashSet<EpisodeKey>>,
+}
+
+impl FacetCounter {
+    fn add(&mut self, key: &EpisodeKey, subject: Option<&crate::rag::retrieval::RagSubject>) {
+        let Some(subject) = subject else {
+            return;
+        };
+        for (values, value) in [(&mut self.coarse, &subject.coarse), (&mut self.fine, &subject.fine)] {
+            if let Some(value) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+                values.entry(value.to_string()).or_default().insert(key.clone());
+            }
+        }
+    }
+
+    /// Most frequent first, ties by value
+    fn finish(self) -> SubjectFacets {
+        let counts = |values: HashMap<String, HashSet<EpisodeKey>>| {
+            let mut counts: Vec<FacetCount> = values
+                .into_iter()
+                .map(|(value, episodes)| FacetCount { value, count: episodes.len() })
+                .collect();
+            counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+            counts
+        };
+        SubjectFacets { coarse: counts(self.coarse), fine: counts(self.fine) }
+    }
+}
+
+/// Whether `value` passes an optional subject filter (trimmed, case-insensitive)
+fn subject_matches(value: Option<&str>, filter: Option<&str>) -> bool {
+    match filter.map(str::trim).filter(|f| !f.is_empty()) {
+        None => true,
+        Some(filter) => value.is_some_and(|v| v.trim().to_lowercase() == filter.to_lowercase()),
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -76,20 +227,342 @@ pub struct EpisodeSearchResult {
 
 pub async fn episodes_search(
     State(st): State<AppStateType>,
+    uri: Uri,
+    headers: HeaderMap,
     Json(req): Json<EpisodesSearchRequest>,
 ) -> impl IntoResponse {
+    if let Err(resp) = guard(&st, Access::Public, uri.path(), &headers).await {
+        return resp;
+    }
+    if let Some(resp) = llm_unavailable(&st) {
+        return resp;
+    }
     match episodes_search_impl(&st, req).await {
         Ok(resp) => (StatusCode::OK, Json(resp)).into_response(),
-        Err(e) => {
-            tracing::error!("{:?}", e);
-            let msg = format!("{}", e);
-            (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({ "error": msg })),
-            )
-                .into_response()
+        Err(e) => search_error_response(e),
+    }
+}
+
+fn search_error_response(e: anyhow::Error) -> Response {
+    tracing::error!("{:?}", e);
+    let status = if e.is::<SearchTimeout>() {
+        StatusCode::REQUEST_TIMEOUT
+    } else {
+        StatusCode::BAD_REQUEST
+    };
+    (status, Json(serde_json::json!({ "error": e.to_string() }))).into_response()
+}
+
+/// Run CPU-bound `work` on the blocking pool, bounded by `timeout`. `work` gets a cancel flag
+/// that is set on timeout or when the caller is dropped, and should check it regularly.
+async fn run_cancellable<T, F>(timeout: Duration, work: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&AtomicBool) -> T + Send + 'static,
+{
+    let cancel = Arc::new(AtomicBool::new(false));
+    let _guard = CancelOnDrop(cancel.clone());
+    let handle = tokio::task::spawn_blocking(move || work(&cancel));
+    match tokio::time::timeout(timeout, handle).await {
+        Ok(joined) => Ok(joined?),
+        Err(_) => Err(SearchTimeout(timeout).into()),
+    }
+}
+
+/// Restrictions applied while scoring, before the top-K cut, so pagination, `total` and
+/// `hasMore` only ever see items that pass them
+#[derive(Debug, Default, Clone)]
+struct ItemFilter {
+    /// Only these episodes per podcast (date range of a search); podcasts missing here are excluded
+    episodes: Option<EpisodeFilter>,
+    /// Text or summary must contain every term (`mustContain`, already `normalize_for_match`ed)
+    required_terms: Vec<String>,
+    /// Subject filters (`filterCoarse`/`filterFine`), see `subject_matches`
+    coarse: Option<String>,
+    fine: Option<String>,
+}
+
+impl ItemFilter {
+    fn is_empty(&self) -> bool {
+        self.episodes.is_none() && self.required_terms.is_empty() && self.coarse.is_none() && self.fine.is_none()
+    }
+
+    /// The same filter without the subject filters, for counting facets
+    fn without_subjects(&self) -> Self {
+        Self { coarse: None, fine: None, ..self.clone() }
+    }
+
+    fn allows(&self, podcast_id: &str, item: &crate::rag::retrieval::RagItem) -> bool {
+        let episode_allowed = self
+            .episodes
+            .as_ref()
+            .is_none_or(|e| e.get(podcast_id).is_some_and(|eps| eps.contains(&item.episode_number)));
+        let subject = item.subject.as_ref();
+        episode_allowed
+            && subject_matches(subject.and_then(|s| s.coarse.as_deref()), self.coarse.as_deref())
+            && subject_matches(subject.and_then(|s| s.fine.as_deref()), self.fine.as_deref())
+            && item_contains_all(item, &self.required_terms)
+    }
+
+    /// Whether any item of the episode passes the filter
+    fn allows_episode(&self, podcast_id: &str, rag: &crate::rag::RagIndex, episode_number: u32) -> bool {
+        self.is_empty()
+            || rag
+                .items
+                .iter()
+                .any(|item| item.episode_number == episode_number && self.allows(podcast_id, item))
+    }
+}
+
+/// Best items facets are counted over; fixed, so the counts don't change from page to page
+const FACET_POOL_ITEMS: usize = 250;
+
+/// Title-only episodes that may join the title blend; fixed, so `total` doesn't change from page to page
+const TITLE_ONLY_CANDIDATES: usize = 20;
+
+/// Cosine-score all items of all indices and keep the best `keep_count`, best first.
+/// With a non-empty `filter`, only items passing it are scored (exact scan, since the ANN
+/// top-K could consist of excluded items only).
+/// Returns early (with partial results) once `cancel` is set.
+fn score_items(
+    rag_indices: &[(String, Arc<crate::rag::RagIndex>)],
+    q: &[f32],
+    qn: f32,
+    keep_count: usize,
+    filter: &ItemFilter,
+    cancel: &AtomicBool,
+) -> Vec<ScoredItem> {
+    use std::cmp::Ordering;
+
+    let mut scored: Vec<ScoredItem> = Vec::new();
+    for (podcast_id, rag) in rag_indices {
+        if cancel.load(AtomicOrdering::Relaxed) {
+            break;
+        }
+        // Indices with an ANN graph only contribute their approximate top-K
+        if filter.is_empty() {
+            if let Some(approx) = rag.ann_search(q, qn, keep_count) {
+                scored.extend(approx.into_iter().map(|(i, s)| (podcast_id.clone(), i, s)));
+                continue;
+            }
+        }
+        let podcast_scores: Vec<ScoredItem> = (0..rag.items.len())
+            .into_par_iter()
+            .map(|i| {
+                if cancel.load(AtomicOrdering::Relaxed) {
+                    return None;
+                }
+                if !filter.allows(podcast_id, &rag.items[i]) {
+                    return Some(None);
+                }
+                Some(item_cosine(rag, i, q, qn).map(|s| (podcast_id.clone(), i, s)))
+            })
+            .while_some()
+            .flatten()
+            .collect();
+        scored.extend(podcast_scores);
+    }
+
+    // Use partial sort to get top-K without sorting everything
+    if scored.len() > keep_count {
+        scored.select_nth_unstable_by(keep_count - 1, |a, b| {
+            b.2.partial_cmp(&a.2).unwrap_or(Ordering::Equal)
+        });
+        scored.truncate(keep_count);
+    }
+    scored.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(Ordering::Equal));
+    scored
+}
+
+/// Whether the item's text or summary contains every term; terms must already be `normalize_for_match`ed
+fn item_contains_all(item: &crate::rag::retrieval::RagItem, terms: &[String]) -> bool {
+    if terms.is_empty() {
+        return true;
+    }
+    let haystack = normalize_for_match(&format!(
+        "{} {}",
+        item.text.as_deref().unwrap_or(""),
+        item.summary.as_deref().unwrap_or("")
+    ));
+    terms.iter().all(|t| haystack.contains(t.as_str()))
+}
+
+/// Best-scoring positions, skipping any within `tolerance_sec` of one already kept, at most `max`
+fn top_unique_positions(positions: &[(f64, f32)], tolerance_sec: f64, max: usize) -> ScoredPositions {
+    use std::cmp::Ordering;
+
+    let mut sorted_positions: Vec<(f64, f32)> = positions.to_vec();
+    sorted_positions.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+    let mut unique_positions: Vec<(f64, f32)> = Vec::new();
+    for (pos, score) in sorted_positions {
+        if !unique_positions.iter().any(|(p, _)| (p - pos).abs() < tolerance_sec) {
+            unique_positions.push((pos, score));
+            if unique_positions.len() >= max {
+                break;
+            }
+        }
+    }
+    unique_positions
+}
+
+/// Blend each episode's best segment score with its title similarity as
+/// `(1 - weight) * segment + weight * title`, best first. Up to `extra_candidates` episodes
+/// whose title matches but that had no kept segment join with the weakest kept segment
+/// score, so a query equal to a title surfaces that episode; `accepts` keeps the search
+/// filters on them. Episodes without a title embedding keep their segment score.
+fn blend_title_scores(
+    mut results: Vec<(EpisodeKey, f32, ScoredPositions)>,
+    title_scores: &HashMap<EpisodeKey, f32>,
+    weight: f32,
+    extra_candidates: usize,
+    accepts: impl Fn(&EpisodeKey) -> bool,
+) -> Vec<(EpisodeKey, f32, ScoredPositions)> {
+    use std::cmp::Ordering;
+
+    let floor = results.iter().map(|r| r.1).reduce(f32::min).unwrap_or(0.0);
+    let mut title_only: Vec<(&EpisodeKey, f32)> = title_scores
+        .iter()
+        .filter(|(key, _)| !results.iter().any(|r| &r.0 == *key))
+        .map(|(key, score)| (key, *score))
+        .collect();
+    title_only.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+    results.extend(
+        title_only
+            .into_iter()
+            .filter(|(key, _)| accepts(key))
+            .take(extra_candidates)
+            .map(|(key, _)| (key.clone(), floor, Vec::new())),
+    );
+
+    for (key, score, _) in results.iter_mut() {
+        let title = title_scores.get(key).copied().unwrap_or(*score);
+        *score = (1.0 - weight) * *score + weight * title;
+    }
+    results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+    results
+}
+
+/// Group indices by the embedding model they were built with (first-seen order). Indices that
+/// don't record a model join the first group. With several models, `Strict` refuses the search.
+pub(crate) fn group_by_embedding_model(rag_indices: &[(String, Arc<crate::rag::RagIndex>)], policy: CrossModelPolicy) -> Result<Vec<PodcastIndices>> {
+    let mut models: Vec<&str> = Vec::new();
+    for (_, rag) in rag_indices {
+        if let Some(m) = rag.embedding_model.as_deref() {
+            if !models.contains(&m) {
+                models.push(m);
+            }
         }
     }
+    if models.len() <= 1 {
+        return Ok(vec![rag_indices.to_vec()]);
+    }
+
+    let describe: Vec<String> = rag_indices
+        .iter()
+        .map(|(pid, rag)| format!("{}: {}", pid, rag.embedding_model.as_deref().unwrap_or("unknown")))
+        .collect();
+    tracing::warn!("Cross-podcast search over different embedding models ({})", describe.join(", "));
+    if policy == CrossModelPolicy::Strict {
+        return Err(anyhow!(
+            "Indices use different embedding models ({}); scores are not comparable",
+            describe.join(", ")
+        ));
+    }
+
+    let mut groups: Vec<PodcastIndices> = vec![Vec::new(); models.len()];
+    for (podcast_id, rag) in rag_indices {
+        let group = rag.embedding_model.as_deref()
+            .and_then(|m| models.iter().position(|x| *x == m))
+            .unwrap_or(0);
+        groups[group].push((podcast_id.clone(), rag.clone()));
+    }
+    Ok(groups)
+}
+
+/// Pair each model group with the query embedded by that group's model, since cosine scores
+/// against another model's vectors mean nothing. `query_vec` (configured model) is reused where
+/// it fits; groups whose model can't embed the query are left out with a warning.
+async fn with_group_queries(
+    st: &AppStateType,
+    groups: Vec<PodcastIndices>,
+    query: &str,
+    query_vec: (Vec<f32>, f32),
+    use_cache: bool,
+) -> Result<Vec<(PodcastIndices, Vec<f32>, f32)>> {
+    let mut with_queries = Vec::with_capacity(groups.len());
+    for group in groups {
+        let model = group.iter()
+            .find_map(|(_, rag)| rag.embedding_model.clone())
+            .unwrap_or_else(|| st.cfg.embedding_model.clone());
+        if model == st.cfg.embedding_model {
+            with_queries.push((group, query_vec.0.clone(), query_vec.1));
+            continue;
+        }
+        let expected_dim = group.iter().find_map(|(_, rag)| rag.embedding_dim);
+        match embed_query_with_model(st, &model, query, expected_dim, use_cache).await {
+            Ok(q) if l2_norm(&q) > 0.0 => {
+                let qn = l2_norm(&q);
+                with_queries.push((group, q, qn));
+            }
+            Ok(_) => tracing::warn!("Skipping indices of {}: query embedding norm is 0", model),
+            Err(e) => tracing::warn!("Skipping indices of {}: query embedding failed: {}", model, e),
+        }
+    }
+    if with_queries.is_empty() {
+        return Err(anyhow!("The query could not be embedded for any index"));
+    }
+    Ok(with_queries)
+}
+
+/// Merge per-model results after scaling each group so its best hit scores 1.0,
+/// keeping the best `keep_count`.
+fn merge_model_groups(groups: Vec<Vec<ScoredItem>>, keep_count: usize) -> Vec<ScoredItem> {
+    use std::cmp::Ordering;
+
+    let mut merged: Vec<ScoredItem> = Vec::new();
+    for mut group in groups {
+        let max = group.iter().map(|s| s.2).fold(f32::NEG_INFINITY, f32::max);
+        if max > 0.0 {
+            for item in group.iter_mut() {
+                item.2 /= max;
+            }
+        }
+        merged.extend(group);
+    }
+    merged.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(Ordering::Equal));
+    merged.truncate(keep_count);
+    merged
+}
+
+/// Podcasts with at least `min_episodes` episodes; smaller ones are left out of cross-podcast search
+fn podcasts_with_min_episodes(counts: Vec<(String, usize)>, min_episodes: usize) -> Vec<String> {
+    counts
+        .into_iter()
+        .filter_map(|(podcast_id, count)| {
+            if count >= min_episodes {
+                Some(podcast_id)
+            } else {
+                tracing::debug!("Skipping {} in cross-podcast search ({} episodes)", podcast_id, count);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Podcasts searched in cross-podcast mode: every podcast with a RAG index and at least
+/// `cfg.cross_podcast_min_episodes` episodes
+pub(crate) async fn cross_podcast_ids(st: &AppStateType) -> Result<Vec<String>> {
+    let all_ids = get_all_podcast_ids().await?;
+    let min_episodes = st.cfg.cross_podcast_min_episodes;
+    if min_episodes == 0 {
+        return Ok(all_ids);
+    }
+    let mut counts = Vec::with_capacity(all_ids.len());
+    for podcast_id in all_ids {
+        let count = load_episode_list_cached(st, &podcast_id).await.map(|eps| eps.len()).unwrap_or(0);
+        counts.push((podcast_id, count));
+    }
+    Ok(podcasts_with_min_episodes(counts, min_episodes))
 }
 
 // Helper function to get all available podcast IDs from db directory
@@ -107,7 +580,7 @@ async fn get_all_podcast_ids() -> Result<Vec<String>> {
         let path = entry.path();
         if path.is_dir() {
             if let Some(podcast_id) = path.file_name().and_then(|n| n.to_str()) {
-                let rag_path = path.join("rag-embeddings.json");
+                let rag_path = crate::gzip::prefer_gz(path.join("rag-embeddings.json")).await;
                 if tokio::fs::metadata(&rag_path).await.is_ok() {
                     podcast_ids.push(podcast_id.to_string());
                 }
@@ -121,10 +594,9 @@ async fn get_all_podcast_ids() -> Result<Vec<String>> {
 async fn episodes_search_impl(st: &AppStateType, req: EpisodesSearchRequest) -> Result<EpisodesSearchResponse> {
     use std::cmp::Ordering;
     
-    let query = req.query.trim();
-    if query.is_empty() {
-        return Err(anyhow!("query must not be empty"));
-    }
+    // Reject empty/too-short queries before spending an embedding call
+    let query = validate_query(&req.query, st.cfg.min_query_len)?;
+    let date_range = DateRange::parse(req.from_date.as_deref(), req.to_date.as_deref())?;
 
     let cross_podcast = req.cross_podcast.unwrap_or(false);
     let page_size = req.limit.unwrap_or(req.top_k.unwrap_or(10)).clamp(1, 50);
@@ -132,7 +604,7 @@ async fn episodes_search_impl(st: &AppStateType, req: EpisodesSearchRequest) ->
     
     // Determine which podcasts to search
     let podcast_ids: Vec<String> = if cross_podcast {
-        get_all_podcast_ids().await?
+        cross_podcast_ids(st).await?
     } else {
         vec![req.podcast_id.as_deref().unwrap_or("freakshow").to_string()]
     };
@@ -159,65 +631,104 @@ async fn episodes_search_impl(st: &AppStateType, req: EpisodesSearchRequest) ->
         return Err(anyhow!("No RAG indices could be loaded"));
     }
     
-    // Get embedding for query
-    let q = embed_query(st, query).await?;
+    // Get embedding for query with the configured model; indices of other models get their own below
+    let model = st.cfg.embedding_model.as_str();
+    let expected_dim = rag_indices.iter()
+        .filter(|(_, rag)| rag.embedding_model.as_deref().is_none_or(|m| m == model))
+        .find_map(|(_, rag)| rag.embedding_dim);
+    let q = embed_query(st, query, expected_dim, !req.no_embed_cache).await?;
     let qn = l2_norm(&q);
     if qn <= 0.0 {
         return Err(anyhow!("Query embedding norm is 0"));
     }
 
-    // Score all items across all podcasts with parallel computation
-    let keep_count = (offset + page_size) * 5;
-    
-    // Parallel computation of all scores across all podcasts
-    let mut scored: Vec<(String, usize, f32)> = Vec::new();
-    for (podcast_id, rag) in &rag_indices {
-        let podcast_id_clone = podcast_id.clone();
-        let podcast_scores: Vec<(String, usize, f32)> = rag.items
-            .par_iter()
-            .enumerate()
-            .filter_map(|(i, it)| {
-                let v = it.embedding.as_ref()?;
-                let dn = rag.norms[i];
-                if dn <= 0.0 {
-                    return None;
+    // Title similarity per episode, only needed when blending is enabled
+    let title_weight = st.cfg.title_blend_weight;
+    let mut title_scores: HashMap<EpisodeKey, f32> = HashMap::new();
+    if title_weight > 0.0 {
+        for (podcast_id, rag) in &rag_indices {
+            match load_title_embeddings_cached(st, podcast_id, rag).await {
+                Ok(titles) => {
+                    for (ep_num, score) in titles.scores(&q, qn) {
+                        title_scores.insert((podcast_id.clone(), ep_num), score);
+                    }
                 }
-                let s = dot(&q, v) / (qn * dn);
-                if s.is_finite() {
-                    Some((podcast_id_clone.clone(), i, s))
-                } else {
-                    None
+                Err(e) => {
+                    tracing::warn!("Failed to load title embeddings for {}: {}", podcast_id, e);
                 }
-            })
-            .collect();
-        scored.extend(podcast_scores);
+            }
+        }
     }
-    
-    // Use partial sort to get top-K without sorting everything
-    let mut scored = scored;
-    if scored.len() > keep_count {
-        let (top_part, _, _) = scored.select_nth_unstable_by(keep_count - 1, |a, b| {
-            b.2.partial_cmp(&a.2).unwrap_or(Ordering::Equal)
-        });
-        top_part.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(Ordering::Equal));
-        scored = top_part.to_vec();
+
+    // A date range, mustContain and the subject filters restrict scoring itself, so pages aren't starved
+    let subject_filter = |f: &Option<String>| f.as_deref().map(str::trim).filter(|f| !f.is_empty()).map(str::to_string);
+    let filter = ItemFilter {
+        episodes: match &date_range {
+            Some(range) => Some(episodes_in_range(st, &podcast_ids, range).await?),
+            None => None,
+        },
+        required_terms: req.must_contain.iter().flatten()
+            .map(|t| normalize_for_match(t))
+            .filter(|t| !t.is_empty())
+            .collect(),
+        coarse: subject_filter(&req.filter_coarse),
+        fine: subject_filter(&req.filter_fine),
+    };
+    // Facets see every candidate, so the sidebar doesn't shrink to the selected subject
+    let facet_filter = req.include_facets.then(|| filter.without_subjects());
+    let title_filter = filter.clone();
+
+    // Score all items across all podcasts in parallel, bounded by the search timeout
+    let keep_count = (offset + page_size) * 5;
+    let model_groups = if cross_podcast {
+        group_by_embedding_model(&rag_indices, st.cfg.cross_model_policy)?
     } else {
-        scored.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(Ordering::Equal));
-    }
-    
+        vec![rag_indices.clone()]
+    };
+    let model_groups = with_group_queries(st, model_groups, query, (q.clone(), qn), !req.no_embed_cache).await?;
+    let (scored, facet_pool) = run_cancellable(st.cfg.search_timeout, move |cancel| {
+        let score = |filter: &ItemFilter, keep_count: usize| {
+            if let [(group, gq, gqn)] = model_groups.as_slice() {
+                score_items(group, gq, *gqn, keep_count, filter, cancel)
+            } else {
+                let groups = model_groups.iter()
+                    .map(|(group, gq, gqn)| score_items(group, gq, *gqn, keep_count, filter, cancel))
+                    .collect();
+                merge_model_groups(groups, keep_count)
+            }
+        };
+        let scored = score(&filter, keep_count);
+        let facet_pool = facet_filter.map(|f| score(&f, FACET_POOL_ITEMS));
+        (scored, facet_pool)
+    })
+    .await?;
+    let item_of = |podcast_id: &str, idx: usize| {
+        rag_indices.iter()
+            .find(|(pid, _)| pid == podcast_id)
+            .map(|(_, rag_arc)| &rag_arc.items[idx])
+            .ok_or_else(|| anyhow!("RAG index not found for podcast {}", podcast_id))
+    };
+
+    let facets = match &facet_pool {
+        Some(pool) => {
+            let mut counter = FacetCounter::default();
+            for (podcast_id, idx, _) in pool {
+                let item = item_of(podcast_id, *idx)?;
+                counter.add(&(podcast_id.clone(), item.episode_number), item.subject.as_ref());
+            }
+            Some(counter)
+        }
+        None => None,
+    };
+
     // Group by (podcast_id, episode_number) and get best score per episode
     // Also track multiple positions (start_sec) of matching items (top 3 per episode)
-    let mut episode_data: HashMap<(String, u32), (f32, Vec<(f64, f32)>)> = HashMap::new();
+    let mut episode_data: HashMap<EpisodeKey, (f32, ScoredPositions)> = HashMap::new();
     
-    // Take more items than page_size to ensure we have enough episodes after grouping
-    for (podcast_id, idx, score) in scored.iter().take((offset + page_size) * 5) {
-        let rag = rag_indices.iter()
-            .find(|(pid, _)| pid == podcast_id)
-            .map(|(_, rag_arc)| rag_arc.as_ref())
-            .ok_or_else(|| anyhow!("RAG index not found for podcast {}", podcast_id))?;
-        let item = &rag.items[*idx];
-        let ep_num = item.episode_number;
-        let key = (podcast_id.clone(), ep_num);
+    // `scored` holds more items than page_size to ensure we have enough episodes after grouping
+    for (podcast_id, idx, score) in &scored {
+        let item = item_of(podcast_id, *idx)?;
+        let key = (podcast_id.clone(), item.episode_number);
         
         // Track best score per episode and collect positions with their scores
         let entry = episode_data.entry(key).or_insert((*score, Vec::new()));
@@ -230,37 +741,35 @@ async fn episodes_search_impl(st: &AppStateType, req: EpisodesSearchRequest) ->
     }
     
     // Sort positions by score and keep top 3 per episode, preserving both positions and scores
-    let mut episode_positions: HashMap<(String, u32), Vec<(f64, f32)>> = HashMap::new();
+    let position_tolerance = req.position_dedup_sec.filter(|t| t.is_finite() && *t >= 0.0).unwrap_or(1.0);
+    let mut episode_positions: HashMap<EpisodeKey, ScoredPositions> = HashMap::new();
     for ((podcast_id, ep_num), (_, positions_with_scores)) in &episode_data {
-        let mut sorted_positions: Vec<(f64, f32)> = positions_with_scores.clone();
-        sorted_positions.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
-        let mut unique_positions: Vec<(f64, f32)> = Vec::new();
-        for (pos, score) in sorted_positions {
-            // Check if position is already in list (within 1 second tolerance)
-            if !unique_positions.iter().any(|(p, _)| (p - pos).abs() < 1.0) {
-                unique_positions.push((pos, score));
-                if unique_positions.len() >= 3 {
-                    break;
-                }
-            }
-        }
+        let unique_positions = top_unique_positions(positions_with_scores, position_tolerance, 3);
         episode_positions.insert((podcast_id.clone(), *ep_num), unique_positions);
     }
     
     // Convert to vector and sort by score
-    let mut episode_results: Vec<((String, u32), f32, Vec<(f64, f32)>)> = episode_data.into_iter()
+    let mut episode_results: Vec<(EpisodeKey, f32, ScoredPositions)> = episode_data.into_iter()
         .map(|(key, (score, _))| {
             let positions = episode_positions.get(&key).cloned().unwrap_or_default();
             (key, score, positions)
         })
         .collect();
     episode_results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+    if title_weight > 0.0 {
+        let accepts = |(podcast_id, ep_num): &EpisodeKey| {
+            rag_indices.iter()
+                .find(|(pid, _)| pid == podcast_id)
+                .is_some_and(|(_, rag)| title_filter.allows_episode(podcast_id, rag, *ep_num))
+        };
+        episode_results = blend_title_scores(episode_results, &title_scores, title_weight, TITLE_ONLY_CANDIDATES, accepts);
+    }
     
     let total = episode_results.len();
     let has_more = (offset + page_size) < total;
     
     // Apply pagination
-    let paginated_results: Vec<((String, u32), f32, Vec<(f64, f32)>)> = episode_results
+    let paginated_results: Vec<(EpisodeKey, f32, ScoredPositions)> = episode_results
         .into_iter()
         .skip(offset)
         .take(page_size)
@@ -294,11 +803,11 @@ async fn episodes_search_impl(st: &AppStateType, req: EpisodesSearchRequest) ->
         }
     }
     
-    // Load episode topics maps for all podcasts
-    let mut all_topics_maps: HashMap<String, HashMap<u32, std::collections::HashSet<String>>> = HashMap::new();
+    // Load episode topics maps for all podcasts (keyed by podcast and episode, so shared numbers don't collide)
+    let mut all_topics: EpisodeTopicsMap = HashMap::new();
     for podcast_id in &podcast_ids {
         if let Ok(topics_map) = load_episode_topics_map_cached(st, podcast_id).await {
-            all_topics_maps.insert(podcast_id.clone(), topics_map);
+            all_topics.extend(topics_map);
         }
     }
     
@@ -343,9 +852,8 @@ async fn episodes_search_impl(st: &AppStateType, req: EpisodesSearchRequest) ->
             }
         }
         
-        let topics: Vec<String> = all_topics_maps
-            .get(&podcast_id)
-            .and_then(|map| map.get(&ep_num))
+        let topics: Vec<String> = all_topics
+            .get(&(podcast_id.clone(), ep_num))
             .map(|s| s.iter().cloned().collect())
             .unwrap_or_default();
         
@@ -379,9 +887,229 @@ async fn episodes_search_impl(st: &AppStateType, req: EpisodesSearchRequest) ->
         episodes: results,
         has_more,
         total: Some(total),
+        facets: facets.map(FacetCounter::finish),
     })
 }
 
+/// "More like this": episodes whose items are closest to the centroid of the given episode
+pub async fn episodes_similar(
+    State(st): State<AppStateType>,
+    Path((podcast_id, episode_number)): Path<(String, u32)>,
+    Query(params): Query<SimilarEpisodesQuery>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    uri: Uri,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let access = Access::Podcast { podcast: Some(&podcast_id), peer };
+    if let Err(resp) = guard(&st, access, uri.path(), &headers).await {
+        return resp;
+    }
+    match episodes_similar_impl(&st, &podcast_id, episode_number, params).await {
+        Ok(Some(resp)) => (StatusCode::OK, Json(resp)).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": format!("No embedded items for episode {} of {}", episode_number, podcast_id)
+            })),
+        )
+            .into_response(),
+        Err(e) => search_error_response(e),
+    }
+}
+
+/// Transcript entries of an episode, or the error response (400 for a bad podcast id, 404 when
+/// there is no transcript)
+pub(crate) async fn load_episode_transcript(
+    st: &AppStateType,
+    podcast_id: &str,
+    episode_number: u32,
+) -> Result<Arc<Vec<TranscriptEntry>>, Response> {
+    if podcast_id.is_empty() || !podcast_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": format!("Invalid podcast_id '{}'", podcast_id) })),
+        )
+            .into_response());
+    }
+    let episodes_dir = st.cfg.episodes_dir_of(podcast_id);
+    match load_transcript_entries(st, podcast_id, &episodes_dir, episode_number).await {
+        // A missing transcript loads as empty
+        Ok(entries) if entries.is_empty() => Err((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": format!("No transcript for episode {} of {}", episode_number, podcast_id)
+            })),
+        )
+            .into_response()),
+        Ok(entries) => Ok(entries),
+        Err(e) => {
+            tracing::error!("Failed to load transcript: {:?}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": format!("Failed to load transcript: {}", e) })),
+            )
+                .into_response())
+        }
+    }
+}
+
+/// Transcript of an episode as WebVTT captions for the player
+pub async fn episode_transcript_vtt(
+    State(st): State<AppStateType>,
+    Path((podcast_id, episode_number)): Path<(String, u32)>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    uri: Uri,
+    headers: HeaderMap,
+) -> Response {
+    let access = Access::Podcast { podcast: Some(&podcast_id), peer };
+    if let Err(resp) = guard(&st, access, uri.path(), &headers).await {
+        return resp;
+    }
+    match load_episode_transcript(&st, &podcast_id, episode_number).await {
+        Ok(entries) => (
+            [(header::CONTENT_TYPE, "text/vtt; charset=utf-8")],
+            transcript_to_vtt(&entries),
+        )
+            .into_response(),
+        Err(response) => response,
+    }
+}
+
+/// Transcript of an episode as JSON entries, optionally limited to `from_sec`..`to_sec`
+pub async fn episode_transcript(
+    State(st): State<AppStateType>,
+    Path((podcast_id, episode_number)): Path<(String, u32)>,
+    Query(params): Query<TranscriptQuery>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    uri: Uri,
+    headers: HeaderMap,
+) -> Response {
+    let access = Access::Podcast { podcast: Some(&podcast_id), peer };
+    if let Err(resp) = guard(&st, access, uri.path(), &headers).await {
+        return resp;
+    }
+    match load_episode_transcript(&st, &podcast_id, episode_number).await {
+        Ok(entries) => Json(transcript_window(&entries, params.from_sec, params.to_sec)).into_response(),
+        Err(response) => response,
+    }
+}
+
+/// Mean embedding of all items of `episode_number` with the index dimension; None if there are none
+fn episode_centroid(rag: &crate::rag::RagIndex, episode_number: u32) -> Option<Vec<f32>> {
+    let dim = rag.embedding_dim?;
+    let mut centroid = vec![0.0f32; dim];
+    let mut count = 0usize;
+    for (item, &norm) in rag.items.iter().zip(&rag.norms) {
+        let Some(v) = item.embedding.as_ref() else {
+            continue;
+        };
+        if item.episode_number != episode_number || v.len() != dim || norm <= 0.0 {
+            continue;
+        }
+        for (c, x) in centroid.iter_mut().zip(v) {
+            *c += x;
+        }
+        count += 1;
+    }
+    if count == 0 {
+        return None;
+    }
+    for c in centroid.iter_mut() {
+        *c /= count as f32;
+    }
+    Some(centroid)
+}
+
+/// Best item score per episode, best first, leaving out `exclude`
+fn best_score_per_episode(scored: &[ScoredItem], rag: &crate::rag::RagIndex, exclude: u32, limit: usize) -> Vec<(u32, f32)> {
+    use std::cmp::Ordering;
+
+    let mut best: HashMap<u32, f32> = HashMap::new();
+    for (_, idx, score) in scored {
+        let ep_num = rag.items[*idx].episode_number;
+        if ep_num == exclude {
+            continue;
+        }
+        let entry = best.entry(ep_num).or_insert(*score);
+        if *score > *entry {
+            *entry = *score;
+        }
+    }
+    let mut ranked: Vec<(u32, f32)> = best.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal).then(b.0.cmp(&a.0)));
+    ranked.truncate(limit);
+    ranked
+}
+
+async fn episodes_similar_impl(
+    st: &AppStateType,
+    podcast_id: &str,
+    episode_number: u32,
+    params: SimilarEpisodesQuery,
+) -> Result<Option<EpisodesSearchResponse>> {
+    let limit = params.limit.unwrap_or(10).clamp(1, 50);
+    let rag = load_rag_index_cached(st, podcast_id).await?;
+    let Some(centroid) = episode_centroid(&rag, episode_number) else {
+        return Ok(None);
+    };
+    let cn = l2_norm(&centroid);
+    if cn <= 0.0 {
+        return Ok(None);
+    }
+
+    // The source episode's own items score highest; scan far enough past them
+    let own_items = rag.items.iter().filter(|it| it.episode_number == episode_number).count();
+    let keep_count = own_items + limit * SIMILAR_ITEMS_PER_RESULT;
+    let indices = vec![(podcast_id.to_string(), rag.clone())];
+    let scored = run_cancellable(st.cfg.search_timeout, move |cancel| {
+        score_items(&indices, &centroid, cn, keep_count, &ItemFilter::default(), cancel)
+    })
+    .await?;
+    let ranked = best_score_per_episode(&scored, &rag, episode_number, limit);
+
+    let episode_numbers: Vec<u32> = ranked.iter().map(|(ep, _)| *ep).collect();
+    let metadata_map = load_episode_metadata_batch_cached(st, podcast_id, &episode_numbers).await?;
+    let episode_topics_map = load_episode_topics_map_cached(st, podcast_id).await.unwrap_or_default();
+    let files_map = check_episode_files_batch_cached(st, podcast_id, &episode_numbers).await.unwrap_or_default();
+
+    let mut results = Vec::with_capacity(ranked.len());
+    for (ep_num, score) in ranked {
+        let meta = metadata_map.get(&ep_num);
+        let duration_sec = meta
+            .and_then(|m| m.duration.as_ref())
+            .filter(|dur| dur.len() >= 3)
+            .map(|dur| dur[0] * 3600 + dur[1] * 60 + dur[2]);
+        let (has_image, has_transcript) = files_map.get(&ep_num).copied().unwrap_or((false, false));
+        results.push(EpisodeSearchResult {
+            episode_number: ep_num,
+            podcast_id: podcast_id.to_string(),
+            title: meta
+                .and_then(|m| m.title.clone())
+                .unwrap_or_else(|| format!("Episode {}", ep_num)),
+            date: meta.and_then(|m| m.date.clone()),
+            duration_sec,
+            speakers: meta.and_then(|m| m.speakers.clone()).unwrap_or_default(),
+            description: meta.and_then(|m| m.description.clone()),
+            score,
+            topics: episode_topics_map
+                .get(&(podcast_id.to_string(), ep_num))
+                .map(|s| s.iter().cloned().collect())
+                .unwrap_or_default(),
+            positions_sec: Vec::new(),
+            position_scores: Vec::new(),
+            has_image,
+            has_transcript,
+        });
+    }
+
+    Ok(Some(EpisodesSearchResponse {
+        total: Some(results.len()),
+        episodes: results,
+        has_more: false,
+        facets: None,
+    }))
+}
+
 pub async fn episodes_latest(
     State(st): State<AppStateType>,
     Json(req): Json<EpisodesLatestRequest>,
@@ -400,6 +1128,80 @@ pub async fn episodes_latest(
     }
 }
 
+fn parse_episode_date(date: &str) -> Option<chrono::NaiveDate> {
+    let date = date.trim();
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()
+        .or_else(|| chrono::DateTime::parse_from_rfc3339(date).ok().map(|d| d.date_naive()))
+}
+
+/// Inclusive publication date bounds of an episode search (`fromDate`/`toDate`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DateRange {
+    from: Option<chrono::NaiveDate>,
+    to: Option<chrono::NaiveDate>,
+}
+
+impl DateRange {
+    /// None without bounds; malformed bounds are an error
+    fn parse(from: Option<&str>, to: Option<&str>) -> Result<Option<Self>> {
+        let bound = |name: &str, value: Option<&str>| -> Result<Option<chrono::NaiveDate>> {
+            match value.map(str::trim).filter(|v| !v.is_empty()) {
+                Some(v) => chrono::NaiveDate::parse_from_str(v, "%Y-%m-%d")
+                    .map(Some)
+                    .map_err(|_| anyhow!("Invalid {} '{}' (expected YYYY-MM-DD)", name, v)),
+                None => Ok(None),
+            }
+        };
+        let range = Self { from: bound("fromDate", from)?, to: bound("toDate", to)? };
+        Ok((range.from.is_some() || range.to.is_some()).then_some(range))
+    }
+
+    /// Episodes without a parseable date are outside every range
+    fn contains(&self, date: Option<&str>) -> bool {
+        let Some(date) = date.and_then(parse_episode_date) else {
+            return false;
+        };
+        self.from.is_none_or(|from| date >= from) && self.to.is_none_or(|to| date <= to)
+    }
+}
+
+/// Episodes per podcast whose metadata date lies in `range`
+async fn episodes_in_range(st: &AppStateType, podcast_ids: &[String], range: &DateRange) -> Result<EpisodeFilter> {
+    let mut filter = EpisodeFilter::new();
+    for podcast_id in podcast_ids {
+        let episode_numbers = match load_episode_list_cached(st, podcast_id).await {
+            Ok(numbers) => numbers,
+            Err(e) => {
+                tracing::warn!("Failed to list episodes of {}: {}", podcast_id, e);
+                continue;
+            }
+        };
+        let metadata = load_episode_metadata_batch_cached(st, podcast_id, &episode_numbers).await?;
+        let allowed = metadata
+            .into_iter()
+            .filter(|(_, meta)| range.contains(meta.date.as_deref()))
+            .map(|(ep_num, _)| ep_num)
+            .collect();
+        filter.insert(podcast_id.clone(), allowed);
+    }
+    Ok(filter)
+}
+
+/// Newest first. Date sort puts episodes without a parseable date last and breaks ties by number.
+fn sort_episodes(episodes: &mut [u32], sort: EpisodeSort, metadata: &HashMap<u32, EpisodeMetadata>) {
+    match sort {
+        EpisodeSort::Number => episodes.sort_by(|a, b| b.cmp(a)),
+        EpisodeSort::Date => episodes.sort_by_cached_key(|ep| {
+            let date = metadata
+                .get(ep)
+                .and_then(|m| m.date.as_deref())
+                .and_then(parse_episode_date);
+            std::cmp::Reverse((date, *ep))
+        }),
+    }
+}
+
 async fn episodes_latest_impl(st: &AppStateType, req: EpisodesLatestRequest) -> Result<EpisodesSearchResponse> {
     let podcast_id = req.podcast_id.as_deref().unwrap_or("freakshow");
     let podcast_id_string = podcast_id.to_string();
@@ -407,7 +1209,13 @@ async fn episodes_latest_impl(st: &AppStateType, req: EpisodesLatestRequest) ->
     let offset = req.offset.unwrap_or(0);
     
     // Load episode list (with caching)
-    let episode_numbers = load_episode_list_cached(st, podcast_id).await?;
+    let mut episode_numbers = load_episode_list_cached(st, podcast_id).await?;
+    // Date order needs metadata for every episode, not just the requested page
+    let sort_metadata = match req.sort {
+        EpisodeSort::Date => load_episode_metadata_batch_cached(st, podcast_id, &episode_numbers).await?,
+        EpisodeSort::Number => HashMap::new(),
+    };
+    sort_episodes(&mut episode_numbers, req.sort, &sort_metadata);
     
     let total = episode_numbers.len();
     let has_more = (offset + page_size) < total;
@@ -419,18 +1227,8 @@ async fn episodes_latest_impl(st: &AppStateType, req: EpisodesLatestRequest) ->
         .take(page_size)
         .collect();
     
-    // Load RAG database once to get topics for all episodes (with caching)
-    let mut episode_topics_map: HashMap<u32, std::collections::HashSet<String>> = HashMap::new();
-    if let Ok(rag) = load_rag_index_cached(st, podcast_id).await {
-        for item in &rag.items {
-            if let Some(topic) = &item.topic {
-                episode_topics_map
-                    .entry(item.episode_number)
-                    .or_default()
-                    .insert(topic.clone());
-            }
-        }
-    }
+    // Load topics for all episodes (with caching)
+    let episode_topics_map = load_episode_topics_map_cached(st, podcast_id).await.unwrap_or_default();
     
     // Load episode metadata in parallel (batch loading with caching)
     let metadata_map = load_episode_metadata_batch_cached(st, podcast_id, &paginated_episodes).await?;
@@ -465,7 +1263,7 @@ async fn episodes_latest_impl(st: &AppStateType, req: EpisodesLatestRequest) ->
         
         // Get topics from pre-loaded map
         let topics: Vec<String> = episode_topics_map
-            .get(&ep_num)
+            .get(&(podcast_id_string.clone(), ep_num))
             .map(|s| s.iter().cloned().collect())
             .unwrap_or_default();
         
@@ -495,6 +1293,373 @@ async fn episodes_latest_impl(st: &AppStateType, req: EpisodesLatestRequest) ->
         episodes: results,
         has_more,
         total: Some(total),
+        facets: None,
     })
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rag::embeddings::tests::mock_embeddings_api;
+
+    fn metadata(date: Option<&str>) -> EpisodeMetadata {
+        EpisodeMetadata {
+            title: None,
+            number: None,
+            date: date.map(|d| d.to_string()),
+            duration: None,
+            description: None,
+            speakers: None,
+        }
+    }
+
+    /// Item of `episode_number` at 0 s with `embedding`
+    fn embedded_item(episode_number: u32, embedding: Vec<f32>) -> crate::rag::retrieval::RagItem {
+        crate::rag::retrieval::RagItem {
+            embedding: Some(embedding),
+            ..crate::rag::retrieval::RagItem::test_item(episode_number, 0.0)
+        }
+    }
+
+    /// `items` as the only index, of podcast "freakshow"
+    fn freakshow_index(items: Vec<crate::rag::retrieval::RagItem>) -> PodcastIndices {
+        vec![("freakshow".to_string(), Arc::new(crate::rag::RagIndex::test_index(items)))]
+    }
+
+    #[tokio::test]
+    async fn test_slow_scoring_times_out_with_408() {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let observed = cancelled.clone();
+        let err = run_cancellable(Duration::from_millis(50), move |cancel| {
+            // Forced-slow scoring: spin until cancelled
+            while !cancel.load(AtomicOrdering::Relaxed) {
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            observed.store(true, AtomicOrdering::Relaxed);
+        })
+        .await
+        .unwrap_err();
+
+        assert!(err.is::<SearchTimeout>());
+        assert_eq!(search_error_response(err).status(), StatusCode::REQUEST_TIMEOUT);
+
+        // The blocking work notices the cancellation and stops
+        for _ in 0..100 {
+            if cancelled.load(AtomicOrdering::Relaxed) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(cancelled.load(AtomicOrdering::Relaxed));
+    }
+
+    #[test]
+    fn test_exact_title_query_ranks_episode_first_with_title_blend() {
+        use crate::rag::retrieval::{TitleEmbedding, TitleEmbeddings};
+
+        let titles = TitleEmbeddings {
+            embedding_model: "test-embedding".to_string(),
+            titles: vec![
+                TitleEmbedding { episode_number: 1, title: "Allgemeines".to_string(), embedding: vec![0.0, 1.0] },
+                TitleEmbedding { episode_number: 2, title: "Raumfahrt".to_string(), embedding: vec![1.0, 0.0] },
+            ],
+        };
+        // The query is the exact title of episode 2, but episode 1 has the better segment
+        let q = vec![1.0, 0.0];
+        let title_scores: HashMap<EpisodeKey, f32> = titles
+            .scores(&q, l2_norm(&q))
+            .into_iter()
+            .map(|(ep, s)| (("fs".to_string(), ep), s))
+            .collect();
+        let segments = || {
+            vec![
+                (("fs".to_string(), 1), 0.8, Vec::new()),
+                (("fs".to_string(), 2), 0.6, Vec::new()),
+            ]
+        };
+
+        let off = blend_title_scores(segments(), &title_scores, 0.0, 10, |_| true);
+        assert_eq!(off[0].0 .1, 1);
+
+        let on = blend_title_scores(segments(), &title_scores, 0.5, 10, |_| true);
+        assert_eq!(on[0].0 .1, 2);
+        assert!((on[0].1 - 0.8).abs() < 1e-6);
+
+        // A title-only match joins the candidates even without a kept segment
+        let on = blend_title_scores(vec![(("fs".to_string(), 1), 0.8, Vec::new())], &title_scores, 0.5, 10, |_| true);
+        assert_eq!(on.iter().map(|r| r.0 .1).collect::<Vec<_>>(), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_title_only_match_respects_date_filter() {
+        use crate::rag::retrieval::RagItem;
+
+        let rag = crate::rag::RagIndex::test_index(vec![RagItem::test_item(1, 0.0), RagItem::test_item(2, 0.0)]);
+        // The date range only covers episode 1
+        let filter = ItemFilter {
+            episodes: Some(HashMap::from([("fs".to_string(), HashSet::from([1]))])),
+            ..Default::default()
+        };
+        let title_scores: HashMap<EpisodeKey, f32> =
+            HashMap::from([(("fs".to_string(), 1), 0.2), (("fs".to_string(), 2), 1.0)]);
+        let accepts = |(podcast_id, ep): &EpisodeKey| filter.allows_episode(podcast_id, &rag, *ep);
+
+        // Episode 2 matches its title exactly but lies outside the range
+        let blended = blend_title_scores(vec![(("fs".to_string(), 1), 0.8, Vec::new())], &title_scores, 0.5, 10, accepts);
+        assert_eq!(blended.iter().map(|r| r.0 .1).collect::<Vec<_>>(), vec![1]);
+
+        let unfiltered = blend_title_scores(vec![(("fs".to_string(), 1), 0.8, Vec::new())], &title_scores, 0.5, 10, |_| true);
+        assert_eq!(unfiltered.iter().map(|r| r.0 .1).collect::<Vec<_>>(), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_similar_episodes_exclude_source_episode() {
+        let indices = freakshow_index(vec![
+            embedded_item(1, vec![1.0, 0.2]),
+            embedded_item(1, vec![1.0, -0.2]),
+            embedded_item(2, vec![0.9, 0.1]),
+            embedded_item(3, vec![0.0, 1.0]),
+            embedded_item(3, vec![0.7, 0.7]),
+            embedded_item(4, vec![0.0, 0.0]),
+        ]);
+        let rag = indices[0].1.clone();
+
+        // Zero vectors (episode 4) are left out of centroids and the scan
+        assert_eq!(episode_centroid(&rag, 1), Some(vec![1.0, 0.0]));
+        assert_eq!(episode_centroid(&rag, 4), None);
+        assert_eq!(episode_centroid(&rag, 99), None);
+
+        let scored = score_items(&indices, &[1.0, 0.0], 1.0, 10, &ItemFilter::default(), &AtomicBool::new(false));
+        let ranked = best_score_per_episode(&scored, &rag, 1, 10);
+        assert_eq!(ranked.iter().map(|r| r.0).collect::<Vec<_>>(), vec![2, 3]);
+        // Episode 3 ranks by its best item
+        assert!((ranked[1].1 - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+        assert_eq!(best_score_per_episode(&scored, &rag, 1, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_score_items_stops_when_cancelled() {
+        let indices = freakshow_index(vec![embedded_item(1, vec![1.0, 0.0]), embedded_item(1, vec![1.0, 0.0])]);
+
+        assert_eq!(score_items(&indices, &[1.0, 0.0], 1.0, 10, &ItemFilter::default(), &AtomicBool::new(false)).len(), 2);
+        assert!(score_items(&indices, &[1.0, 0.0], 1.0, 10, &ItemFilter::default(), &AtomicBool::new(true)).is_empty());
+    }
+
+    #[test]
+    fn test_date_range_restricts_scored_episodes() {
+        let range = DateRange::parse(Some("2023-01-01"), Some(" 2023-12-31 ")).unwrap().unwrap();
+        assert!(range.contains(Some("2023-06-15")));
+        assert!(range.contains(Some("2023-12-31T20:00:00+01:00")));
+        assert!(!range.contains(Some("2024-01-01")));
+        assert!(!range.contains(Some("irgendwann")));
+        assert!(!range.contains(None));
+        let open_end = DateRange::parse(Some("2023-01-01"), None).unwrap().unwrap();
+        assert!(open_end.contains(Some("2030-01-01")));
+        assert_eq!(DateRange::parse(None, Some("")).unwrap(), None);
+        assert!(DateRange::parse(Some("15.06.2023"), None).is_err());
+
+        let indices = freakshow_index([1, 2, 3].into_iter().map(|ep| embedded_item(ep, vec![1.0, 0.0])).collect());
+
+        let episodes: EpisodeFilter = [("freakshow".to_string(), [1, 3].into_iter().collect())].into_iter().collect();
+        let filter = ItemFilter { episodes: Some(episodes), ..Default::default() };
+        let scored = score_items(&indices, &[1.0, 0.0], 1.0, 10, &filter, &AtomicBool::new(false));
+        let mut episodes: Vec<u32> = scored.iter().map(|(_, i, _)| indices[0].1.items[*i].episode_number).collect();
+        episodes.sort_unstable();
+        assert_eq!(episodes, vec![1, 3]);
+
+        // Podcasts missing from the filter contribute nothing
+        let other = ItemFilter { episodes: Some([("lnp".to_string(), HashSet::from([1]))].into_iter().collect()), ..Default::default() };
+        assert!(score_items(&indices, &[1.0, 0.0], 1.0, 10, &other, &AtomicBool::new(false)).is_empty());
+    }
+
+    #[test]
+    fn test_cross_model_search_strict_errors_and_lenient_normalizes() {
+        let index = |model: &str, scale: f32| {
+            let mut rag = crate::rag::RagIndex::test_index(vec![embedded_item(1, vec![1.0, 0.0]), embedded_item(2, vec![scale, 1.0])]);
+            rag.embedding_model = Some(model.to_string());
+            Arc::new(rag)
+        };
+        let indices = vec![
+            ("freakshow".to_string(), index("model-a", 1.0)),
+            ("lnp".to_string(), index("model-b", 0.0)),
+        ];
+
+        let err = group_by_embedding_model(&indices, CrossModelPolicy::Strict).err().unwrap();
+        assert!(err.to_string().contains("different embedding models"));
+
+        let groups = group_by_embedding_model(&indices, CrossModelPolicy::Lenient).unwrap();
+        assert_eq!(groups.len(), 2);
+        // Query [0.6, 0.8]: each model's best hit scores below 1.0 before normalization
+        let q = [0.6, 0.8];
+        let cancel = AtomicBool::new(false);
+        let scored: Vec<Vec<ScoredItem>> = groups.iter().map(|g| score_items(g, &q, 1.0, 10, &ItemFilter::default(), &cancel)).collect();
+        assert!(scored.iter().all(|g| g[0].2 < 0.99));
+
+        let merged = merge_model_groups(scored, 10);
+        assert_eq!(merged.len(), 4);
+        for podcast in ["freakshow", "lnp"] {
+            let best = merged.iter().filter(|s| s.0 == podcast).map(|s| s.2).fold(f32::MIN, f32::max);
+            assert!((best - 1.0).abs() < 1e-6);
+        }
+
+        // Same model everywhere: one group, nothing refused
+        let same = vec![indices[0].clone(), ("other".to_string(), index("model-a", 0.0))];
+        assert_eq!(group_by_embedding_model(&same, CrossModelPolicy::Strict).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_each_model_group_gets_its_own_query_embedding() {
+        // model-b embeds into its own space; "broken" isn't served by the provider
+        let mut cfg = crate::config::AppConfig::for_tests();
+        cfg.embedding_model = "model-a".to_string();
+        cfg.embedding_base_url = mock_embeddings_api(|model, inputs| {
+            (model == "model-b").then(|| vec![vec![0.0, 2.0]; inputs.len()])
+        })
+        .await;
+        let st = AppStateType::for_tests(cfg);
+
+        let index = |model: &str| {
+            let mut rag = crate::rag::RagIndex::test_index(Vec::new());
+            rag.embedding_dim = Some(2);
+            rag.embedding_model = Some(model.to_string());
+            Arc::new(rag)
+        };
+        let groups = vec![
+            vec![("freakshow".to_string(), index("model-a"))],
+            vec![("lnp".to_string(), index("model-b"))],
+            vec![("cre".to_string(), index("broken"))],
+        ];
+        let with_queries = with_group_queries(&st, groups, "hallo", (vec![1.0, 0.0], 1.0), true).await.unwrap();
+
+        let summary: Vec<(&str, &[f32], f32)> =
+            with_queries.iter().map(|(g, q, qn)| (g[0].0.as_str(), q.as_slice(), *qn)).collect();
+        assert_eq!(summary, vec![("freakshow", &[1.0, 0.0][..], 1.0), ("lnp", &[0.0, 2.0][..], 2.0)]);
+    }
+
+    #[test]
+    fn test_cross_podcast_skips_podcasts_below_min_episodes() {
+        let counts = vec![
+            ("freakshow".to_string(), 280),
+            ("test-podcast".to_string(), 2),
+            ("lnp".to_string(), 500),
+        ];
+        assert_eq!(podcasts_with_min_episodes(counts, 10), vec!["freakshow".to_string(), "lnp".to_string()]);
+    }
+
+    #[test]
+    fn test_position_tolerance_collapses_close_positions() {
+        let positions = vec![(100.0, 0.9), (104.0, 0.85), (108.0, 0.8), (600.0, 0.7)];
+
+        let narrow = top_unique_positions(&positions, 1.0, 3);
+        assert_eq!(narrow.iter().map(|p| p.0).collect::<Vec<_>>(), vec![100.0, 104.0, 108.0]);
+
+        let wide = top_unique_positions(&positions, 30.0, 3);
+        assert_eq!(wide.iter().map(|p| p.0).collect::<Vec<_>>(), vec![100.0, 600.0]);
+    }
+
+    #[test]
+    fn test_sort_episodes_by_number_and_date() {
+        // Episode 3 is a late "special" numbered before episodes 4 and 5
+        let mut episodes = vec![1, 2, 3, 4, 5];
+        let metadata: HashMap<u32, EpisodeMetadata> = [
+            (1, metadata(Some("2020-01-01"))),
+            (2, metadata(Some("2020-02-01"))),
+            (3, metadata(Some("2021-06-01"))),
+            (4, metadata(Some("2020-03-01T10:00:00+01:00"))),
+            (5, metadata(None)),
+        ]
+        .into_iter()
+        .collect();
+
+        sort_episodes(&mut episodes, EpisodeSort::Number, &metadata);
+        assert_eq!(episodes, vec![5, 4, 3, 2, 1]);
+
+        sort_episodes(&mut episodes, EpisodeSort::Date, &metadata);
+        assert_eq!(episodes, vec![3, 4, 2, 1, 5]);
+    }
+
+    #[test]
+    fn test_item_contains_all_terms_in_text_or_summary() {
+        let mut item = crate::rag::retrieval::RagItem::test_item(281, 0.0);
+        item.text = Some("Tim: Universal Control funktioniert erstaunlich gut.".to_string());
+        item.summary = Some("Apple-Geräte im Zusammenspiel".to_string());
+        let terms = |ts: &[&str]| ts.iter().map(|t| normalize_for_match(t)).collect::<Vec<_>>();
+
+        assert!(item_contains_all(&item, &[]));
+        assert!(item_contains_all(&item, &terms(&["universal control", "APPLE"])));
+        assert!(!item_contains_all(&item, &terms(&["universal control", "Android"])));
+    }
+
+    #[test]
+    fn test_must_contain_filters_before_the_top_k_cut() {
+        // Twenty close matches without the term, then the one item that has it, scoring worst
+        let items: Vec<_> = (1..=21)
+            .map(|ep| {
+                let mut item = embedded_item(ep, if ep == 21 { vec![0.1, 1.0] } else { vec![1.0, 0.0] });
+                item.text = Some(if ep == 21 { "Mein Zettelkasten" } else { "Universal Control" }.to_string());
+                item
+            })
+            .collect();
+        let indices = freakshow_index(items);
+
+        let filter = ItemFilter { required_terms: vec![normalize_for_match("zettelkasten")], ..Default::default() };
+        let scored = score_items(&indices, &[1.0, 0.0], 1.0, 5, &filter, &AtomicBool::new(false));
+        assert_eq!(scored.len(), 1);
+        assert_eq!(indices[0].1.items[scored[0].1].episode_number, 21);
+    }
+
+    #[test]
+    fn test_subject_filter_applies_before_the_top_k_cut() {
+        use crate::rag::retrieval::RagSubject;
+        let items: Vec<_> = (1..=21)
+            .map(|ep| {
+                let mut item = embedded_item(ep, if ep == 21 { vec![0.1, 1.0] } else { vec![1.0, 0.0] });
+                let coarse = if ep == 21 { "Wissenschaft" } else { "Technik" };
+                item.subject = Some(RagSubject { coarse: Some(coarse.to_string()), fine: None });
+                item
+            })
+            .collect();
+        let indices = freakshow_index(items);
+
+        let filter = ItemFilter { coarse: Some("wissenschaft".to_string()), ..Default::default() };
+        let scored = score_items(&indices, &[1.0, 0.0], 1.0, 5, &filter, &AtomicBool::new(false));
+        assert_eq!(scored.len(), 1);
+        assert_eq!(indices[0].1.items[scored[0].1].episode_number, 21);
+        // Facets are counted without the subject filter
+        assert!(filter.without_subjects().is_empty());
+    }
+
+    #[test]
+    fn test_subject_facets_count_episodes_per_value() {
+        use crate::rag::retrieval::RagSubject;
+        let subject = |coarse: &str, fine: Option<&str>| RagSubject {
+            coarse: Some(coarse.to_string()),
+            fine: fine.map(str::to_string),
+        };
+        let key = |podcast: &str, ep| (podcast.to_string(), ep);
+
+        let mut counter = FacetCounter::default();
+        counter.add(&key("freakshow", 281), Some(&subject("Technik", Some("Apple"))));
+        // A second item of the same episode doesn't count twice
+        counter.add(&key("freakshow", 281), Some(&subject("Technik ", Some("Universal Control"))));
+        counter.add(&key("lnp", 281), Some(&subject("Politik", None)));
+        counter.add(&key("freakshow", 282), Some(&subject("Technik", Some("Apple"))));
+        counter.add(&key("freakshow", 283), None);
+
+        let facet = |value: &str, count| FacetCount { value: value.to_string(), count };
+        assert_eq!(
+            counter.finish(),
+            SubjectFacets {
+                coarse: vec![facet("Technik", 2), facet("Politik", 1)],
+                fine: vec![facet("Apple", 2), facet("Universal Control", 1)],
+            }
+        );
+
+        assert!(subject_matches(Some("Technik"), None));
+        assert!(subject_matches(Some(" technik"), Some("TECHNIK ")));
+        assert!(subject_matches(None, Some("  ")));
+        assert!(!subject_matches(None, Some("Technik")));
+        assert!(!subject_matches(Some("Politik"), Some("Technik")));
+    }
+}
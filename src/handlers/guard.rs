@@ -0,0 +1,69 @@
+use std::net::SocketAddr;
+
+use axum::{
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::config::AppState;
+use crate::handlers::analytics::client_key;
+use crate::handlers::auth::{carries_token, has_valid_token, is_auth_ok};
+use crate::handlers::rate_limit::too_many_requests;
+
+/// Who may call an endpoint checked by `guard`
+pub enum Access<'a> {
+    /// Anyone, without rate limiting
+    Public,
+    /// The global token or `podcast`'s own (open when neither is configured); callers without
+    /// a valid token are rate limited per client
+    Podcast { podcast: Option<&'a str>, peer: SocketAddr },
+    /// Only the global token; closed when none is configured
+    Admin,
+}
+
+/// 403 for a request without the required token
+pub fn permission_denied() -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(serde_json::json!({ "error": "permission denied" })),
+    )
+        .into_response()
+}
+
+/// Auth, then the per-client chat rate limit. Err is the response to send instead of
+/// handling the request.
+pub async fn guard(st: &AppState, access: Access<'_>, path: &str, headers: &HeaderMap) -> Result<(), Response> {
+    match access {
+        Access::Public => {}
+        Access::Podcast { podcast, peer } => {
+            if !is_auth_ok(&st.cfg, st.cfg.auth_token.as_ref(), podcast, path, headers) {
+                return Err(permission_denied());
+            }
+            // Token holders (internal tooling) are not throttled
+            if !has_valid_token(&st.cfg, podcast, headers) {
+                st.chat_rate_limiter
+                    .check(&client_key(peer, headers, &st.cfg.trusted_proxies))
+                    .map_err(too_many_requests)?;
+            }
+        }
+        Access::Admin => {
+            if !carries_token(st.cfg.auth_token.as_ref(), headers) {
+                return Err(permission_denied());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 503 without an LLM key, for endpoints that embed queries or call the chat model.
+/// Checked after `guard`, so unauthorized callers still get 403.
+pub fn llm_unavailable(st: &AppState) -> Option<Response> {
+    (!st.cfg.llm_available).then(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({ "error": "semantic search is unavailable: no LLM API key configured" })),
+        )
+            .into_response()
+    })
+}
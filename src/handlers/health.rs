@@ -0,0 +1,158 @@
+use std::future::Future;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::Serialize;
+
+use crate::config::AppState;
+
+/// Upper bound for the LLM reachability probe, so a hanging upstream can't stall health checks
+const LLM_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize)]
+pub struct HealthResponse {
+    /// "ok", or "unavailable" while no RAG index with items is loaded
+    pub status: &'static str,
+    pub podcasts: Vec<PodcastHealth>,
+    pub llm_ok: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PodcastHealth {
+    pub id: String,
+    pub items: usize,
+}
+
+/// Health check: the loaded RAG indices and whether the LLM API answers. 503 while no index
+/// has items, so load balancers drain the instance.
+pub async fn health(State(st): State<AppState>) -> impl IntoResponse {
+    let mut podcasts: Vec<PodcastHealth> = st
+        .rag_cache
+        .iter()
+        .map(|(id, cached)| PodcastHealth {
+            id: id.as_str().to_string(),
+            items: cached.rag.items.len(),
+        })
+        .collect();
+    podcasts.sort_by(|a, b| a.id.cmp(&b.id));
+    let serving = podcasts.iter().any(|p| p.items > 0);
+    let response = HealthResponse {
+        status: if serving { "ok" } else { "unavailable" },
+        podcasts,
+        llm_ok: llm_reachable(&st).await,
+    };
+    let code = if serving { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (code, Json(response))
+}
+
+/// Whether the LLM base URL accepts connections; any HTTP status counts. The result is cached
+/// (`llm_health_cache`) so frequent probes don't hit the upstream API each time.
+async fn llm_reachable(st: &AppState) -> bool {
+    if !st.cfg.llm_available {
+        return false;
+    }
+    st.llm_health_cache
+        .get_with((), async {
+            st.http
+                .head(&st.cfg.llm_base_url)
+                .timeout(LLM_PROBE_TIMEOUT)
+                .send()
+                .await
+                .is_ok()
+        })
+        .await
+}
+
+/// Readiness probe: 503 until cache warming has finished, so load balancers hold traffic back
+pub async fn health_ready(State(st): State<AppState>) -> impl IntoResponse {
+    if st.ready.load(Ordering::Acquire) {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "warming up")
+    }
+}
+
+/// Configured embedding model and the podcasts whose index was built with a different one
+pub async fn health_embeddings(State(st): State<AppState>) -> impl IntoResponse {
+    let mut mismatches: Vec<serde_json::Value> = st
+        .embedding_model_mismatches
+        .iter()
+        .map(|(podcast_id, index_model)| {
+            serde_json::json!({ "podcastId": podcast_id.as_str(), "indexModel": index_model })
+        })
+        .collect();
+    mismatches.sort_by(|a, b| a["podcastId"].as_str().cmp(&b["podcastId"].as_str()));
+    Json(serde_json::json!({
+        "embeddingModel": st.cfg.embedding_model,
+        "mismatches": mismatches,
+    }))
+}
+
+/// Run the warming future, then mark the server as ready
+pub async fn warm_then_ready<F: Future<Output = ()>>(st: &AppState, warm: F) {
+    warm.await;
+    st.ready.store(true, Ordering::Release);
+    tracing::info!("Cache warming finished, server is ready");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+
+    #[tokio::test]
+    async fn test_health_reports_indices_and_needs_one() {
+        // Nothing listens on the test config's LLM port
+        let st = AppState::for_tests(AppConfig::for_tests());
+        let resp = health(State(st.clone())).await.into_response();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let rag = crate::rag::RagIndex::test_index(vec![
+            crate::rag::retrieval::RagItem::test_item(281, 0.0),
+            crate::rag::retrieval::RagItem::test_item(281, 60.0),
+        ]);
+        st.rag_cache
+            .insert(
+                "freakshow".to_string(),
+                crate::cache::CachedRagIndex {
+                    rag: std::sync::Arc::new(rag),
+                    loaded_at: std::time::SystemTime::now(),
+                    file_path: "db/freakshow/rag-embeddings.json".into(),
+                },
+            )
+            .await;
+
+        let resp = health(State(st)).await.into_response();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({ "status": "ok", "podcasts": [{ "id": "freakshow", "items": 2 }], "llm_ok": false })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ready_flips_after_warming() {
+        let st = AppState::for_tests(AppConfig::for_tests());
+        let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let warming_state = st.clone();
+        let warming = tokio::spawn(async move {
+            warm_then_ready(&warming_state, async {
+                let _ = release_rx.await;
+            })
+            .await;
+        });
+
+        let resp = health_ready(State(st.clone())).await.into_response();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        release_tx.send(()).unwrap();
+        warming.await.unwrap();
+
+        let resp = health_ready(State(st.clone())).await.into_response();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,147 @@
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metrics::{counter, describe_counter, describe_histogram, histogram, with_local_recorder, Unit};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle, PrometheusRecorder};
+
+use crate::config::AppState;
+
+/// Histogram bucket bounds in seconds; the upper ones cover slow LLM calls
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// Request, retrieval and RAG cache metrics, rendered in the Prometheus text format on `/metrics`.
+/// Each instance owns its recorder instead of installing a global one, so tests don't share counts.
+pub struct Metrics {
+    recorder: PrometheusRecorder,
+    handle: PrometheusHandle,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        let recorder = PrometheusBuilder::new()
+            .set_buckets(LATENCY_BUCKETS)
+            .expect("latency buckets are not empty")
+            .build_recorder();
+        let handle = recorder.handle();
+        with_local_recorder(&recorder, || {
+            describe_counter!("http_requests_total", "HTTP responses by route, method and status.");
+            describe_counter!("http_request_errors_total", "HTTP responses with a 5xx status by route.");
+            describe_histogram!(
+                "http_request_duration_seconds",
+                Unit::Seconds,
+                "Time until the response headers were sent, by route."
+            );
+            describe_histogram!(
+                "rag_retrieve_duration_seconds",
+                Unit::Seconds,
+                "Query embedding and vector search time."
+            );
+            describe_counter!("rag_index_cache_hits_total", "RAG index lookups served from the cache.");
+            describe_counter!("rag_index_cache_misses_total", "RAG index lookups that loaded the index from disk.");
+            // Exported from the start rather than after the first lookup
+            counter!("rag_index_cache_hits_total").increment(0);
+            counter!("rag_index_cache_misses_total").increment(0);
+        });
+        Self { recorder, handle }
+    }
+}
+
+impl Metrics {
+    /// `route` is the route pattern (`/api/episodes/:podcast_id/...`), so ids don't blow up the label set
+    pub fn observe_request(&self, route: &str, method: &str, status: u16, latency: Duration) {
+        with_local_recorder(&self.recorder, || {
+            let route = route.to_string();
+            counter!(
+                "http_requests_total",
+                "route" => route.clone(),
+                "method" => method.to_string(),
+                "status" => status.to_string()
+            )
+            .increment(1);
+            // Registered for every route, so a route without errors reports 0
+            counter!("http_request_errors_total", "route" => route.clone()).increment(u64::from(status >= 500));
+            histogram!("http_request_duration_seconds", "route" => route).record(latency);
+        });
+    }
+
+    pub fn observe_retrieve(&self, latency: Duration) {
+        with_local_recorder(&self.recorder, || histogram!("rag_retrieve_duration_seconds").record(latency));
+    }
+
+    pub fn rag_cache_hit(&self) {
+        with_local_recorder(&self.recorder, || counter!("rag_index_cache_hits_total").increment(1));
+    }
+
+    pub fn rag_cache_miss(&self) {
+        with_local_recorder(&self.recorder, || counter!("rag_index_cache_misses_total").increment(1));
+    }
+
+    pub fn render(&self) -> String {
+        self.handle.render()
+    }
+}
+
+/// Route layer: count and time every request by its route pattern. Streaming responses are
+/// timed until their headers are sent.
+pub async fn track_requests(State(st): State<AppState>, req: Request, next: Next) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = req.method().to_string();
+    let started = Instant::now();
+    let response = next.run(req).await;
+    st.metrics
+        .observe_request(&route, &method, response.status().as_u16(), started.elapsed());
+    response
+}
+
+/// `/metrics` in the Prometheus text format (no auth; see `METRICS_BIND_ADDR`)
+pub async fn metrics(State(st): State<AppState>) -> Response {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        st.metrics.render(),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counts_errors_and_cumulative_buckets() {
+        let metrics = Metrics::default();
+        metrics.observe_request("/api/chat", "POST", 200, Duration::from_millis(3));
+        metrics.observe_request("/api/chat", "POST", 200, Duration::from_millis(700));
+        metrics.observe_request("/api/chat", "POST", 502, Duration::from_secs(120));
+        metrics.observe_request("/api/episodes/search", "POST", 400, Duration::from_millis(20));
+        metrics.observe_retrieve(Duration::from_millis(40));
+        metrics.rag_cache_hit();
+        metrics.rag_cache_hit();
+        metrics.rag_cache_miss();
+
+        let text = metrics.render();
+        let has = |line: &str| text.lines().any(|l| l == line);
+        assert!(has(r#"http_requests_total{route="/api/chat",method="POST",status="200"} 2"#));
+        assert!(has(r#"http_requests_total{route="/api/chat",method="POST",status="502"} 1"#));
+        assert!(has(r#"http_request_errors_total{route="/api/chat"} 1"#));
+        // 4xx is the client's fault, not an error of the service
+        assert!(has(r#"http_request_errors_total{route="/api/episodes/search"} 0"#));
+        assert!(has(r#"http_request_duration_seconds_bucket{route="/api/chat",le="0.005"} 1"#));
+        assert!(has(r#"http_request_duration_seconds_bucket{route="/api/chat",le="1"} 2"#));
+        assert!(has(r#"http_request_duration_seconds_bucket{route="/api/chat",le="60"} 2"#));
+        assert!(has(r#"http_request_duration_seconds_bucket{route="/api/chat",le="+Inf"} 3"#));
+        assert!(has(r#"http_request_duration_seconds_count{route="/api/chat"} 3"#));
+        assert!(has(r#"rag_retrieve_duration_seconds_bucket{le="0.05"} 1"#));
+        assert!(has("rag_retrieve_duration_seconds_count 1"));
+        assert!(has("rag_index_cache_hits_total 2"));
+        assert!(has("rag_index_cache_misses_total 1"));
+    }
+}
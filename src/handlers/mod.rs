@@ -1,12 +1,19 @@
 pub mod analytics;
+pub mod auth;
 pub mod chat;
 pub mod episodes;
+pub mod guard;
+pub mod health;
+pub mod metrics;
+pub mod rate_limit;
 pub mod speakers;
+pub mod topics;
 
-pub use chat::chat;
-pub use episodes::{episodes_search, episodes_latest};
-pub use speakers::speakers_list;
-pub use analytics::{track, track_episode_play, stats, insert_test_data_endpoint};
+pub use chat::{chat, chat_prompt, chat_stream};
+pub use episodes::{episode_transcript, episode_transcript_vtt, episodes_search, episodes_latest, episodes_similar};
+pub use health::{health, health_embeddings, health_ready};
+pub use speakers::{speakers_cooccurrence, speakers_list, speakers_talk_time};
+pub use topics::{topic_cluster_episodes, topics_taxonomy};
 
 
 
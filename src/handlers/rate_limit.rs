@@ -0,0 +1,77 @@
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use governor::clock::{Clock, DefaultClock};
+use governor::{DefaultKeyedRateLimiter, Quota};
+
+/// Idle clients are dropped from the limiter once it tracks more than this many
+const MAX_TRACKED_CLIENTS: usize = 100_000;
+
+/// Requests per minute per client IP (`RAG_RATE_LIMIT_PER_MIN`); 0 disables limiting.
+/// A client may burst the whole minute's quota at once, which then refills evenly.
+pub struct RateLimiter {
+    limiter: Option<DefaultKeyedRateLimiter<String>>,
+}
+
+impl RateLimiter {
+    pub fn new(per_min: u32) -> Self {
+        Self {
+            limiter: NonZeroU32::new(per_min).map(|n| DefaultKeyedRateLimiter::keyed(Quota::per_minute(n))),
+        }
+    }
+
+    /// Count a request of `client`; Err holds the wait until the next one is allowed
+    pub fn check(&self, client: &str) -> Result<(), Duration> {
+        let Some(limiter) = &self.limiter else {
+            return Ok(());
+        };
+        if limiter.len() > MAX_TRACKED_CLIENTS {
+            limiter.retain_recent();
+        }
+        limiter
+            .check_key(&client.to_string())
+            .map_err(|not_until| not_until.wait_time_from(DefaultClock::default().now()))
+    }
+}
+
+/// 429 with `Retry-After` in whole seconds (rounded up)
+pub fn too_many_requests(retry_after: Duration) -> Response {
+    let secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, secs.to_string())],
+        Json(serde_json::json!({ "error": format!("rate limit exceeded, retry in {} s", secs) })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limiter_is_per_client_and_zero_disables() {
+        let limiter = RateLimiter::new(3);
+        for _ in 0..3 {
+            assert!(limiter.check("10.0.0.1").is_ok());
+        }
+        // 3/min refills one request every 20 s
+        let wait = limiter.check("10.0.0.1").unwrap_err();
+        assert!(wait > Duration::from_secs(19) && wait <= Duration::from_secs(20), "{wait:?}");
+        assert!(limiter.check("10.0.0.2").is_ok());
+
+        let unlimited = RateLimiter::new(0);
+        for _ in 0..100 {
+            assert!(unlimited.check("10.0.0.1").is_ok());
+        }
+
+        let response = too_many_requests(Duration::from_millis(1500));
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+    }
+}
@@ -1,20 +1,38 @@
 use std::collections::HashMap;
 
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
 };
 use serde::Serialize;
 
-use crate::cache::{load_speakers_index_cached, SpeakerInfo};
+use crate::cache::{
+    load_episode_list_cached, load_episode_metadata_batch_cached, load_episode_metadata_cached,
+    load_speakers_index_cached, SpeakerInfo,
+};
 use crate::config::AppState as AppStateType;
+use crate::handlers::episodes::load_episode_transcript;
+use crate::transcript::TranscriptEntry;
+use crate::utils::hms_to_seconds;
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct SpeakersListResponse {
     speakers: Vec<SpeakerInfo>,
+    /// Number of speakers before paging
+    total: usize,
+    has_more: bool,
+}
+
+/// The `offset`/`limit` page of `speakers` (already in listing order); no limit means all
+fn paginate_speakers(speakers: Vec<SpeakerInfo>, offset: usize, limit: Option<usize>) -> SpeakersListResponse {
+    let total = speakers.len();
+    let limit = limit.unwrap_or(usize::MAX);
+    let has_more = offset.saturating_add(limit) < total;
+    let speakers = speakers.into_iter().skip(offset).take(limit).collect();
+    SpeakersListResponse { speakers, total, has_more }
 }
 
 pub async fn speakers_list(
@@ -23,9 +41,11 @@ pub async fn speakers_list(
 ) -> impl IntoResponse {
     // Get podcast_id from query parameter or use default
     let podcast_id = params.get("podcast_id").map(|s| s.as_str()).unwrap_or("freakshow");
+    let offset = params.get("offset").and_then(|s| s.parse::<usize>().ok()).unwrap_or(0);
+    let limit = params.get("limit").and_then(|s| s.parse::<usize>().ok()).map(|l| l.max(1));
     
     match load_speakers_index_cached(&st, podcast_id).await {
-        Ok(speakers) => (StatusCode::OK, Json(SpeakersListResponse { speakers })).into_response(),
+        Ok(speakers) => (StatusCode::OK, Json(paginate_speakers(speakers, offset, limit))).into_response(),
         Err(e) => {
             tracing::error!("Failed to load speakers: {:?}", e);
             (
@@ -37,3 +57,239 @@ pub async fn speakers_list(
     }
 }
 
+/// Two speakers and the number of episodes they both appear in
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeakerPair {
+    pub speaker_a: String,
+    pub speaker_b: String,
+    pub shared_episodes: u32,
+}
+
+/// Speaker pairs by shared episodes (from episode metadata), for a co-occurrence graph.
+/// `limit` caps the number of pairs (default 50, max 1000).
+pub async fn speakers_cooccurrence(
+    State(st): State<AppStateType>,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let podcast_id = params.get("podcast_id").map(|s| s.as_str()).unwrap_or("freakshow");
+    let limit = params
+        .get("limit")
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(50)
+        .clamp(1, 1000);
+
+    let pairs = async {
+        let episodes = load_episode_list_cached(&st, podcast_id).await?;
+        let metadata = load_episode_metadata_batch_cached(&st, podcast_id, &episodes).await?;
+        anyhow::Ok(count_cooccurrences(metadata.values().filter_map(|m| m.speakers.as_deref())))
+    };
+    match pairs.await {
+        Ok(mut pairs) => {
+            pairs.truncate(limit);
+            (StatusCode::OK, Json(pairs)).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to build speaker co-occurrence: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": format!("Failed to load episodes: {}", e) })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Count shared episodes per speaker pair (each pair once, names in order); most shared first,
+/// ties by name
+fn count_cooccurrences<'a>(episodes: impl Iterator<Item = &'a [String]>) -> Vec<SpeakerPair> {
+    let mut counts: HashMap<(String, String), u32> = HashMap::new();
+    for speakers in episodes {
+        let mut names: Vec<&str> = speakers.iter().map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+        names.sort_unstable();
+        names.dedup();
+        for (i, a) in names.iter().enumerate() {
+            for b in &names[i + 1..] {
+                *counts.entry((a.to_string(), b.to_string())).or_default() += 1;
+            }
+        }
+    }
+    let mut pairs: Vec<SpeakerPair> = counts
+        .into_iter()
+        .map(|((speaker_a, speaker_b), shared_episodes)| SpeakerPair { speaker_a, speaker_b, shared_episodes })
+        .collect();
+    pairs.sort_by(|x, y| {
+        y.shared_episodes
+            .cmp(&x.shared_episodes)
+            .then_with(|| x.speaker_a.cmp(&y.speaker_a))
+            .then_with(|| x.speaker_b.cmp(&y.speaker_b))
+    });
+    pairs
+}
+
+/// How long a speaker talked in one episode
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeakerTalkTime {
+    pub speaker: String,
+    pub seconds: f64,
+    pub utterance_count: u32,
+    pub word_count: u32,
+}
+
+/// Per-speaker talk time of an episode (`?podcast_id=`), longest first
+pub async fn speakers_talk_time(
+    State(st): State<AppStateType>,
+    Path(episode_number): Path<u32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let podcast_id = params.get("podcast_id").map(|s| s.as_str()).unwrap_or("freakshow");
+    let entries = match load_episode_transcript(&st, podcast_id, episode_number).await {
+        Ok(entries) => entries,
+        Err(response) => return response,
+    };
+
+    // Without a known episode length the last utterance has no end and is not timed
+    let episode_duration = load_episode_metadata_cached(&st, podcast_id, episode_number)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|m| m.duration)
+        .filter(|dur| dur.len() >= 3)
+        .map(|dur| f64::from(dur[0] * 3600 + dur[1] * 60 + dur[2]));
+
+    (StatusCode::OK, Json(talk_time(&entries, episode_duration))).into_response()
+}
+
+/// Sum each speaker's time from their entry to the next timed entry (the last one until
+/// `episode_duration`). Entries without a speaker only end the previous utterance.
+fn talk_time(entries: &[TranscriptEntry], episode_duration: Option<f64>) -> Vec<SpeakerTalkTime> {
+    let timed: Vec<(f64, &TranscriptEntry)> = entries
+        .iter()
+        .filter_map(|e| Some((hms_to_seconds(&e.time)?, e)))
+        .collect();
+
+    let mut per_speaker: HashMap<&str, SpeakerTalkTime> = HashMap::new();
+    for (i, (start, entry)) in timed.iter().enumerate() {
+        let Some(speaker) = entry.speaker.as_deref().map(str::trim).filter(|s| !s.is_empty()) else {
+            continue;
+        };
+        let end = timed.get(i + 1).map(|(t, _)| *t).or(episode_duration);
+        let stats = per_speaker.entry(speaker).or_insert_with(|| SpeakerTalkTime {
+            speaker: speaker.to_string(),
+            seconds: 0.0,
+            utterance_count: 0,
+            word_count: 0,
+        });
+        stats.seconds += end.map_or(0.0, |end| (end - start).max(0.0));
+        stats.utterance_count += 1;
+        stats.word_count += entry.text.split_whitespace().count() as u32;
+    }
+
+    let mut result: Vec<SpeakerTalkTime> = per_speaker.into_values().collect();
+    result.sort_by(|a, b| {
+        b.seconds
+            .total_cmp(&a.seconds)
+            .then_with(|| a.speaker.cmp(&b.speaker))
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn speaker(name: &str, utterances_count: u32) -> SpeakerInfo {
+        SpeakerInfo {
+            speaker: name.to_string(),
+            slug: name.to_lowercase(),
+            episodes_count: 1,
+            utterances_count,
+            total_words: 0,
+            has_profile: true,
+            image: None,
+        }
+    }
+
+    #[test]
+    fn test_speaker_pages_are_consistent() {
+        let speakers = vec![speaker("Tim", 5000), speaker("Clemens", 900), speaker("Denis", 900), speaker("Gast", 12)];
+
+        let first = paginate_speakers(speakers.clone(), 0, Some(2));
+        let names = |r: &SpeakersListResponse| r.speakers.iter().map(|s| s.speaker.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&first), vec!["Tim", "Clemens"]);
+        assert_eq!((first.total, first.has_more), (4, true));
+
+        let last = paginate_speakers(speakers.clone(), 2, Some(2));
+        assert_eq!(names(&last), vec!["Denis", "Gast"]);
+        assert!(!last.has_more && last.speakers.iter().all(|s| s.has_profile));
+
+        let all = paginate_speakers(speakers.clone(), 0, None);
+        assert_eq!((all.speakers.len(), all.has_more), (4, false));
+        assert!(paginate_speakers(speakers, 10, Some(2)).speakers.is_empty());
+    }
+
+    #[test]
+    fn test_cooccurrence_counts_each_pair_once_per_episode() {
+        let episodes: Vec<Vec<String>> = [
+            vec!["Tim", "Clemens", "Denis"],
+            vec!["Clemens", "Tim", "Tim"],
+            vec!["Tim", " ", "Roddi"],
+            vec!["Tim"],
+        ]
+        .iter()
+        .map(|e| e.iter().map(|s| s.to_string()).collect())
+        .collect();
+
+        let pairs = count_cooccurrences(episodes.iter().map(|e| e.as_slice()));
+        let pair = |a: &str, b: &str, n| SpeakerPair { speaker_a: a.to_string(), speaker_b: b.to_string(), shared_episodes: n };
+        assert_eq!(
+            pairs,
+            vec![
+                pair("Clemens", "Tim", 2),
+                pair("Clemens", "Denis", 1),
+                pair("Denis", "Tim", 1),
+                pair("Roddi", "Tim", 1),
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&pairs[0]).unwrap(),
+            serde_json::json!({ "speakerA": "Clemens", "speakerB": "Tim", "sharedEpisodes": 2 })
+        );
+    }
+
+    #[test]
+    fn test_talk_time_sums_gaps_per_speaker() {
+        let entry = |speaker: Option<&str>, time: &str, text: &str| TranscriptEntry {
+            speaker: speaker.map(str::to_string),
+            time: time.to_string(),
+            text: text.to_string(),
+            lang: None,
+        };
+        let entries = vec![
+            entry(Some("Tim"), "00:00:00", "Hallo und herzlich willkommen"),
+            entry(Some("Clemens"), "00:00:30", "Hi"),
+            entry(None, "00:00:40", "[Musik]"),
+            entry(Some("Tim"), "00:01:00", "Weiter geht's"),
+            entry(Some("Clemens"), "kaputt", "ohne Zeit"),
+            entry(Some("Clemens"), "00:02:00", "Tschüss"),
+        ];
+
+        let with_duration = talk_time(&entries, Some(150.0));
+        let row = |speaker: &str, seconds, utterance_count, word_count| SpeakerTalkTime {
+            speaker: speaker.to_string(),
+            seconds,
+            utterance_count,
+            word_count,
+        };
+        assert_eq!(with_duration, vec![row("Tim", 90.0, 2, 6), row("Clemens", 40.0, 2, 2)]);
+
+        // Without an episode duration the last entry counts but adds no time
+        let without = talk_time(&entries, None);
+        assert_eq!(without[1], row("Clemens", 10.0, 2, 2));
+        assert_eq!(
+            serde_json::to_value(&without[0]).unwrap(),
+            serde_json::json!({ "speaker": "Tim", "seconds": 90.0, "utteranceCount": 2, "wordCount": 6 })
+        );
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::cache::{load_episode_metadata_batch_cached, load_taxonomy_cached};
+use crate::config::AppState as AppStateType;
+
+pub async fn topics_taxonomy(
+    State(st): State<AppStateType>,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    // Get podcast_id from query parameter or use default
+    let podcast_id = params.get("podcast_id").map(|s| s.as_str()).unwrap_or("freakshow");
+    let resolve_titles = params
+        .get("resolveTitles")
+        .is_some_and(|v| v == "true" || v == "1");
+    let filter = TaxonomyFilter {
+        include_outliers: params
+            .get("includeOutliers")
+            .is_none_or(|v| v != "false" && v != "0"),
+        min_topics: params.get("minTopics").and_then(|s| s.parse::<usize>().ok()).unwrap_or(0),
+    };
+
+    match topics_taxonomy_impl(&st, podcast_id, resolve_titles, filter).await {
+        Ok(taxonomy) => (StatusCode::OK, Json(taxonomy)).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to load taxonomy: {:?}", e);
+            (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": format!("Failed to load taxonomy: {}", e) })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Server-side cluster filters of `/api/topics/taxonomy`
+#[derive(Debug, Clone, Copy)]
+struct TaxonomyFilter {
+    /// `includeOutliers=false` drops clusters flagged `isOutlier`
+    include_outliers: bool,
+    /// `minTopics=N` drops clusters with a `topicCount` below N
+    min_topics: usize,
+}
+
+async fn topics_taxonomy_impl(
+    st: &AppStateType,
+    podcast_id: &str,
+    resolve_titles: bool,
+    filter: TaxonomyFilter,
+) -> Result<Value> {
+    let path = taxonomy_path(podcast_id, "topic-taxonomy.json")?;
+    let mut taxonomy = Value::clone(&*load_taxonomy_cached(st, podcast_id, &path).await?);
+    filter_clusters(&mut taxonomy, filter);
+
+    if resolve_titles {
+        // Batch loading is bounded by RAG_METADATA_CONCURRENCY, so large taxonomies don't open every file at once
+        let episode_numbers: Vec<u32> = taxonomy_episode_numbers(&taxonomy).into_iter().collect();
+        let metadata = load_episode_metadata_batch_cached(st, podcast_id, &episode_numbers).await?;
+        let titles: HashMap<u32, String> = metadata
+            .into_iter()
+            .filter_map(|(ep, meta)| meta.title.map(|t| (ep, t)))
+            .collect();
+        resolve_episode_titles(&mut taxonomy, &titles);
+    }
+
+    Ok(taxonomy)
+}
+
+#[derive(Debug, Deserialize)]
+struct DetailedTaxonomy {
+    clusters: Vec<DetailedCluster>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DetailedCluster {
+    id: String,
+    #[serde(default)]
+    topics: Vec<DetailedTopic>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DetailedTopic {
+    /// Missing in taxonomies built before occurrences were recorded
+    #[serde(default)]
+    occurrences: Vec<DetailedOccurrence>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DetailedOccurrence {
+    episode_number: u32,
+    #[serde(default)]
+    duration_sec: u32,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct ClusterEpisode {
+    episode_number: u32,
+    relevance_sec: u64,
+    topic_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ClusterEpisodesResponse {
+    cluster_id: String,
+    episodes: Vec<ClusterEpisode>,
+}
+
+pub async fn topic_cluster_episodes(
+    Path(cluster_id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    // Get podcast_id from query parameter or use default
+    let podcast_id = params.get("podcast_id").map(|s| s.as_str()).unwrap_or("freakshow");
+
+    match topic_cluster_episodes_impl(podcast_id, &cluster_id).await {
+        Ok(Some(episodes)) => (StatusCode::OK, Json(ClusterEpisodesResponse { cluster_id, episodes })).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": format!("Unknown cluster '{}'", cluster_id) })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to load cluster episodes: {:?}", e);
+            (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": format!("Failed to load taxonomy: {}", e) })),
+            )
+                .into_response()
+        }
+    }
+}
+
+async fn topic_cluster_episodes_impl(podcast_id: &str, cluster_id: &str) -> Result<Option<Vec<ClusterEpisode>>> {
+    let path = taxonomy_path(podcast_id, "topic-taxonomy-detailed.json")?;
+    let bytes = tokio::fs::read(&path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let taxonomy: DetailedTaxonomy = serde_json::from_slice(&bytes)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    Ok(taxonomy
+        .clusters
+        .iter()
+        .find(|c| c.id == cluster_id)
+        .map(rank_cluster_episodes))
+}
+
+/// Episodes of a cluster ranked by summed occurrence duration (ties: newest episode first)
+fn rank_cluster_episodes(cluster: &DetailedCluster) -> Vec<ClusterEpisode> {
+    let mut per_episode: HashMap<u32, ClusterEpisode> = HashMap::new();
+    for occ in cluster.topics.iter().flat_map(|t| &t.occurrences) {
+        let entry = per_episode.entry(occ.episode_number).or_insert(ClusterEpisode {
+            episode_number: occ.episode_number,
+            relevance_sec: 0,
+            topic_count: 0,
+        });
+        entry.relevance_sec += u64::from(occ.duration_sec);
+        entry.topic_count += 1;
+    }
+
+    let mut episodes: Vec<ClusterEpisode> = per_episode.into_values().collect();
+    episodes.sort_by(|a, b| {
+        b.relevance_sec
+            .cmp(&a.relevance_sec)
+            .then(b.episode_number.cmp(&a.episode_number))
+    });
+    episodes
+}
+
+fn taxonomy_path(podcast_id: &str, file_name: &str) -> Result<PathBuf> {
+    if podcast_id.is_empty()
+        || !podcast_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(anyhow!("Invalid podcast_id '{}'", podcast_id));
+    }
+    Ok(PathBuf::from(format!("frontend/public/podcasts/{}/{}", podcast_id, file_name)))
+}
+
+fn clusters_mut(taxonomy: &mut Value) -> impl Iterator<Item = &mut Value> {
+    taxonomy
+        .get_mut("clusters")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+}
+
+/// Drop the clusters excluded by `filter`
+fn filter_clusters(taxonomy: &mut Value, filter: TaxonomyFilter) {
+    let Some(clusters) = taxonomy.get_mut("clusters").and_then(Value::as_array_mut) else {
+        return;
+    };
+    clusters.retain(|c| {
+        let is_outlier = c.get("isOutlier").and_then(Value::as_bool).unwrap_or(false);
+        let topic_count = c.get("topicCount").and_then(Value::as_u64).unwrap_or(0);
+        (filter.include_outliers || !is_outlier) && topic_count >= filter.min_topics as u64
+    });
+}
+
+/// Distinct episode numbers referenced by any cluster
+fn taxonomy_episode_numbers(taxonomy: &Value) -> BTreeSet<u32> {
+    taxonomy
+        .get("clusters")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|c| c.get("episodes").and_then(Value::as_array))
+        .flatten()
+        .filter_map(|ep| ep.as_u64().and_then(|n| u32::try_from(n).ok()))
+        .collect()
+}
+
+/// Replace each cluster's `episodes` numbers with `{ number, title }` objects (title null if unknown)
+fn resolve_episode_titles(taxonomy: &mut Value, titles: &HashMap<u32, String>) {
+    for cluster in clusters_mut(taxonomy) {
+        let Some(episodes) = cluster.get_mut("episodes").and_then(Value::as_array_mut) else {
+            continue;
+        };
+        for ep in episodes.iter_mut() {
+            let Some(number) = ep.as_u64() else {
+                continue;
+            };
+            let title = u32::try_from(number).ok().and_then(|n| titles.get(&n));
+            *ep = serde_json::json!({ "number": number, "title": title });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_episode_titles() {
+        let mut taxonomy = serde_json::json!({
+            "clusters": [
+                { "id": "apple", "episodes": [281, 999] },
+                { "id": "podcasting", "episodes": [281] }
+            ]
+        });
+        assert_eq!(
+            taxonomy_episode_numbers(&taxonomy).into_iter().collect::<Vec<_>>(),
+            vec![281, 999]
+        );
+
+        let titles: HashMap<u32, String> = [(281, "FS281 Universal Control".to_string())].into_iter().collect();
+        resolve_episode_titles(&mut taxonomy, &titles);
+
+        let apple = &taxonomy["clusters"][0]["episodes"];
+        assert_eq!(apple[0]["number"], 281);
+        assert_eq!(apple[0]["title"], "FS281 Universal Control");
+        // Unknown episode keeps its number without a title
+        assert_eq!(apple[1]["number"], 999);
+        assert!(apple[1]["title"].is_null());
+        assert_eq!(taxonomy["clusters"][1]["episodes"][0]["title"], "FS281 Universal Control");
+    }
+
+    #[test]
+    fn test_filter_clusters_by_outlier_and_topic_count() {
+        let taxonomy = serde_json::json!({
+            "clusters": [
+                { "id": "apple", "isOutlier": false, "topicCount": 12 },
+                { "id": "misc", "isOutlier": true, "topicCount": 30 },
+                { "id": "tiny", "isOutlier": false, "topicCount": 2 }
+            ]
+        });
+        let ids = |filter| {
+            let mut t = taxonomy.clone();
+            filter_clusters(&mut t, filter);
+            t["clusters"].as_array().unwrap().iter().map(|c| c["id"].as_str().unwrap().to_string()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids(TaxonomyFilter { include_outliers: true, min_topics: 0 }), vec!["apple", "misc", "tiny"]);
+        assert_eq!(ids(TaxonomyFilter { include_outliers: false, min_topics: 0 }), vec!["apple", "tiny"]);
+        assert_eq!(ids(TaxonomyFilter { include_outliers: false, min_topics: 5 }), vec!["apple"]);
+    }
+
+    #[test]
+    fn test_rank_cluster_episodes_by_relevance() {
+        let cluster: DetailedCluster = serde_json::from_value(serde_json::json!({
+            "id": "apple",
+            "topics": [
+                { "occurrences": [
+                    { "episodeNumber": 10, "durationSec": 300, "positionSec": 0 },
+                    { "episodeNumber": 20, "durationSec": 120, "positionSec": 60 }
+                ] },
+                { "occurrences": [
+                    { "episodeNumber": 20, "durationSec": 400, "positionSec": 900 },
+                    { "episodeNumber": 30, "durationSec": 300, "positionSec": 10 }
+                ] },
+                { "topic": "ohne Occurrences" }
+            ]
+        }))
+        .unwrap();
+
+        let ranked = rank_cluster_episodes(&cluster);
+        let order: Vec<(u32, u64)> = ranked.iter().map(|e| (e.episode_number, e.relevance_sec)).collect();
+        // 20 has 120+400; 30 and 10 tie at 300 and the newer episode comes first
+        assert_eq!(order, vec![(20, 520), (30, 300), (10, 300)]);
+        assert_eq!(ranked[0].topic_count, 2);
+    }
+}
@@ -5,8 +5,8 @@ mod tests {
     #[test]
     fn test_cosine_distance_identical_vectors() {
         // Cosine distance between identical vectors should be 0
-        let v1 = vec![1.0, 0.0, 0.0];
-        let v2 = vec![1.0, 0.0, 0.0];
+        let v1 = [1.0, 0.0, 0.0];
+        let v2 = [1.0, 0.0, 0.0];
         
         // Calculate cosine similarity manually
         let dot_product: f64 = v1.iter().zip(&v2).map(|(a, b)| a * b).sum();
@@ -24,8 +24,8 @@ mod tests {
     #[test]
     fn test_cosine_distance_orthogonal_vectors() {
         // Cosine distance between orthogonal vectors should be 1
-        let v1 = vec![1.0, 0.0, 0.0];
-        let v2 = vec![0.0, 1.0, 0.0];
+        let v1 = [1.0, 0.0, 0.0];
+        let v2 = [0.0, 1.0, 0.0];
         
         let dot_product: f64 = v1.iter().zip(&v2).map(|(a, b)| a * b).sum();
         let magnitude1: f64 = v1.iter().map(|x| x * x).sum::<f64>().sqrt();
@@ -42,8 +42,8 @@ mod tests {
     #[test]
     fn test_weighted_average() {
         // Test weighted average calculation
-        let values = vec![10.0, 20.0, 30.0];
-        let weights = vec![1.0, 2.0, 1.0];
+        let values = [10.0, 20.0, 30.0];
+        let weights = [1.0, 2.0, 1.0];
         
         let weighted_sum: f64 = values
             .iter()
@@ -59,7 +59,7 @@ mod tests {
     #[test]
     fn test_vector_normalization() {
         // Test that normalization produces unit vector
-        let v = vec![3.0, 4.0];
+        let v = [3.0, 4.0];
         let magnitude: f64 = v.iter().map(|x| x * x).sum::<f64>().sqrt();
         let normalized: Vec<f64> = v.iter().map(|x| x / magnitude).collect();
         
@@ -74,8 +74,8 @@ mod tests {
     #[test]
     fn test_vector_operations() {
         // Test basic vector operations
-        let v1 = vec![1.0, 2.0, 3.0];
-        let v2 = vec![4.0, 5.0, 6.0];
+        let v1 = [1.0, 2.0, 3.0];
+        let v2 = [4.0, 5.0, 6.0];
         
         // Element-wise addition
         let sum: Vec<f64> = v1.iter().zip(&v2).map(|(a, b)| a + b).collect();
@@ -89,8 +89,8 @@ mod tests {
     #[test]
     fn test_euclidean_distance() {
         // Test Euclidean distance calculation
-        let v1 = vec![0.0_f64, 0.0_f64];
-        let v2 = vec![3.0_f64, 4.0_f64];
+        let v1 = [0.0_f64, 0.0_f64];
+        let v2 = [3.0_f64, 4.0_f64];
         
         let squared_diff: f64 = v1.iter()
             .zip(&v2)
@@ -104,7 +104,7 @@ mod tests {
     #[test]
     fn test_vector_scaling() {
         // Test vector scaling
-        let v = vec![1.0, 2.0, 3.0];
+        let v = [1.0, 2.0, 3.0];
         let scale = 2.5;
         
         let scaled: Vec<f64> = v.iter().map(|x| x * scale).collect();
@@ -115,8 +115,8 @@ mod tests {
     #[test]
     fn test_vector_subtraction() {
         // Test vector subtraction
-        let v1 = vec![5.0, 7.0, 9.0];
-        let v2 = vec![2.0, 3.0, 4.0];
+        let v1 = [5.0, 7.0, 9.0];
+        let v2 = [2.0, 3.0, 4.0];
         
         let diff: Vec<f64> = v1.iter().zip(&v2).map(|(a, b)| a - b).collect();
         
@@ -126,8 +126,8 @@ mod tests {
     #[test]
     fn test_manhattan_distance() {
         // Test Manhattan distance (L1 norm)
-        let v1 = vec![1.0_f64, 2.0_f64, 3.0_f64];
-        let v2 = vec![4.0_f64, 6.0_f64, 8.0_f64];
+        let v1 = [1.0_f64, 2.0_f64, 3.0_f64];
+        let v2 = [4.0_f64, 6.0_f64, 8.0_f64];
         
         let manhattan: f64 = v1.iter()
             .zip(&v2)
@@ -140,7 +140,7 @@ mod tests {
     #[test]
     fn test_zero_vector_handling() {
         // Test handling of zero vectors
-        let zero = vec![0.0, 0.0, 0.0];
+        let zero = [0.0, 0.0, 0.0];
         let magnitude: f64 = zero.iter().map(|x| x * x).sum::<f64>().sqrt();
         
         assert_eq!(magnitude, 0.0, "Zero vector should have magnitude 0");
@@ -149,7 +149,7 @@ mod tests {
     #[test]
     fn test_vector_min_max() {
         // Test finding min and max in vector
-        let v = vec![3.5, 1.2, 7.8, 2.1, 9.3, 4.6];
+        let v = [3.5, 1.2, 7.8, 2.1, 9.3, 4.6];
         
         let min = v.iter().cloned().fold(f64::INFINITY, f64::min);
         let max = v.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
@@ -161,7 +161,7 @@ mod tests {
     #[test]
     fn test_vector_mean() {
         // Test mean calculation
-        let v = vec![10.0, 20.0, 30.0, 40.0, 50.0];
+        let v = [10.0, 20.0, 30.0, 40.0, 50.0];
         let mean: f64 = v.iter().sum::<f64>() / v.len() as f64;
         
         assert_eq!(mean, 30.0);
@@ -170,7 +170,7 @@ mod tests {
     #[test]
     fn test_vector_variance() {
         // Test variance calculation
-        let v = vec![2.0, 4.0, 6.0, 8.0, 10.0];
+        let v = [2.0, 4.0, 6.0, 8.0, 10.0];
         let mean: f64 = v.iter().sum::<f64>() / v.len() as f64;
         let variance: f64 = v.iter()
             .map(|x| (x - mean).powi(2))
@@ -199,8 +199,8 @@ mod tests {
     #[test]
     fn test_parallel_addition() {
         // Test element-wise parallel operations
-        let v1 = vec![1.0, 2.0, 3.0, 4.0, 5.0];
-        let v2 = vec![5.0, 4.0, 3.0, 2.0, 1.0];
+        let v1 = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let v2 = [5.0, 4.0, 3.0, 2.0, 1.0];
         
         let result: Vec<f64> = v1.iter()
             .zip(&v2)
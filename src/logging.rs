@@ -0,0 +1,80 @@
+//! `LOG_FORMAT=json`: one JSON object per log line for the log aggregator.
+
+use tracing_subscriber::fmt::format::{Format, Json, JsonFields};
+use tracing_subscriber::fmt::Layer;
+
+/// Whether `LOG_FORMAT` asks for JSON lines (anything else keeps the human-readable format)
+pub fn json_requested(log_format: Option<&str>) -> bool {
+    log_format.is_some_and(|f| f.trim().eq_ignore_ascii_case("json"))
+}
+
+/// `{"timestamp", "level", "target", "message", ...fields, "span": {"name", ...}}`; the event's
+/// fields sit at the top level, those of the innermost span (e.g. method and path of a request)
+/// under `span`
+pub fn json_layer<S>() -> Layer<S, JsonFields, Format<Json>>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    tracing_subscriber::fmt::layer()
+        .json()
+        .flatten_event(true)
+        .with_current_span(true)
+        .with_span_list(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_lines_carry_span_and_event_fields() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::registry().with(json_layer().with_writer(move || writer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", method = %"POST", path = "/api/chat", client = tracing::field::Empty);
+            let _guard = span.enter();
+            span.record("client", "10.0.0.1");
+            tracing::info!(status = 200u16, latency_ms = 12u64, "request finished");
+            tracing::warn!(error = %"boom \"quoted\"", "failed");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<Value> = output.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        let first = &lines[0];
+        assert_eq!(first["level"], "INFO");
+        assert_eq!(first["message"], "request finished");
+        assert_eq!(first["span"]["name"], "request");
+        assert_eq!(first["span"]["method"], "POST");
+        assert_eq!(first["span"]["path"], "/api/chat");
+        assert_eq!(first["span"]["client"], "10.0.0.1");
+        assert_eq!(first["status"], 200);
+        assert_eq!(first["latency_ms"], 12);
+        assert!(first["timestamp"].as_str().unwrap().ends_with('Z'));
+        assert_eq!(lines[1]["level"], "WARN");
+        assert_eq!(lines[1]["error"], "boom \"quoted\"");
+
+        assert!(json_requested(Some(" JSON ")));
+        assert!(!json_requested(Some("pretty")));
+        assert!(!json_requested(None));
+    }
+}
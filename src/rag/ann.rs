@@ -0,0 +1,107 @@
+//! Approximate nearest-neighbor search over RAG item embeddings, backed by `hnsw_rs`
+//! (HNSW, cosine distance). The graph keeps its own copy of every embedding, so RAG_ANN
+//! roughly doubles the memory held for vectors.
+
+use std::sync::Arc;
+
+use hnsw_rs::prelude::{DistCosine, Hnsw};
+
+use crate::rag::retrieval::RagItem;
+
+/// Neighbors per node on the upper layers (hnsw_rs keeps twice as many on layer 0)
+const M: usize = 16;
+/// Upper bound on the number of layers; hnsw_rs caps it at 16
+const MAX_LAYERS: usize = 16;
+/// Candidate list size while inserting
+const EF_CONSTRUCTION: usize = 100;
+/// Minimum candidate list size while searching
+const EF_SEARCH: usize = 64;
+
+#[derive(Clone)]
+pub struct HnswIndex {
+    graph: Arc<Hnsw<'static, f32, DistCosine>>,
+}
+
+impl HnswIndex {
+    /// Build the graph over all items with an embedding of dimension `dim` and a non-zero norm
+    pub fn build(items: &[RagItem], norms: &[f32], dim: usize) -> Self {
+        let points: Vec<(&[f32], usize)> = items
+            .iter()
+            .zip(norms)
+            .enumerate()
+            .filter_map(|(i, (item, &norm))| {
+                let v = item.embedding.as_deref().filter(|v| v.len() == dim && norm > 0.0)?;
+                Some((v, i))
+            })
+            .collect();
+        let mut graph = Hnsw::new(M, points.len(), MAX_LAYERS, EF_CONSTRUCTION, DistCosine);
+        graph.parallel_insert_slice(&points);
+        graph.set_searching_mode(true);
+        Self { graph: Arc::new(graph) }
+    }
+
+    /// Up to `k` items most similar to `q` (with norm `qn`) as (item index, cosine similarity),
+    /// best first
+    pub fn search(&self, q: &[f32], qn: f32, k: usize) -> Vec<(usize, f32)> {
+        if k == 0 || qn <= 0.0 || self.graph.get_nb_point() == 0 {
+            return Vec::new();
+        }
+        let mut found: Vec<(usize, f32)> = self
+            .graph
+            .search(q, k, k.max(EF_SEARCH))
+            .into_iter()
+            .map(|n| (n.d_id, 1.0 - n.distance))
+            .filter(|(_, s)| s.is_finite())
+            .collect();
+        found.sort_by(|a, b| b.1.total_cmp(&a.1));
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::utils::{dot, l2_norm};
+
+    #[test]
+    fn test_hnsw_recall_matches_exact_search() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut items: Vec<RagItem> = (0..2000)
+            .map(|i| RagItem {
+                embedding: Some((0..32).map(|_| rng.gen_range(-1.0f32..1.0)).collect()),
+                ..RagItem::test_item(i, 0.0)
+            })
+            .collect();
+        // Items without a usable embedding are never returned
+        items[3].embedding = None;
+        items[4].embedding = Some(vec![0.0; 32]);
+        let norms: Vec<f32> = items.iter().map(|it| it.embedding.as_deref().map(l2_norm).unwrap_or(0.0)).collect();
+        let index = HnswIndex::build(&items, &norms, 32);
+
+        let mut matched = 0;
+        for _ in 0..20 {
+            let q: Vec<f32> = (0..32).map(|_| rng.gen_range(-1.0f32..1.0)).collect();
+            let qn = l2_norm(&q);
+            let mut exact: Vec<(usize, f32)> = items
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| norms[*i] > 0.0)
+                .map(|(i, it)| (i, dot(&q, it.embedding.as_ref().unwrap()) / (qn * norms[i])))
+                .collect();
+            exact.sort_by(|a, b| b.1.total_cmp(&a.1));
+            let exact: HashSet<usize> = exact.iter().take(10).map(|&(i, _)| i).collect();
+
+            let approx = index.search(&q, qn, 10);
+            assert_eq!(approx.len(), 10);
+            assert!(approx.windows(2).all(|w| w[0].1 >= w[1].1));
+            assert!(approx.iter().all(|&(i, _)| i != 3 && i != 4));
+            matched += approx.iter().filter(|(i, _)| exact.contains(i)).count();
+        }
+        // Recall@10 over 20 queries
+        assert!(matched >= 180, "recall too low: {matched}/200");
+    }
+}
@@ -1,33 +1,175 @@
 use anyhow::{anyhow, Context, Result};
+use std::sync::Arc;
+
+use futures::{stream, Stream};
 use serde::{Deserialize, Serialize};
 
-use crate::config::AppState;
+use crate::config::{AnswerTemperatures, AppState, DimMismatchPolicy, LlmProvider};
+use crate::utils::normalize_for_match;
 
 #[derive(Debug, Deserialize)]
 struct EmbeddingsResponse {
     data: Vec<EmbeddingDatum>,
 }
 
+/// Cohere v2 (`embeddings.float`) and v1/Ollama (`embeddings` as plain list) responses
+#[derive(Debug, Deserialize)]
+struct EmbeddingListResponse {
+    embeddings: EmbeddingList,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum EmbeddingList {
+    Plain(Vec<Vec<f32>>),
+    ByType { float: Vec<Vec<f32>> },
+}
+
 #[derive(Debug, Deserialize)]
 struct EmbeddingDatum {
     embedding: Vec<f32>,
+    /// Position of the input this vector belongs to (OpenAI sends it; some compatible APIs don't)
+    #[serde(default)]
+    index: Option<usize>,
+}
+
+/// Bring a query embedding to the index dimension according to `policy`
+fn fit_embedding_dim(mut v: Vec<f32>, expected_dim: usize, policy: DimMismatchPolicy) -> Result<Vec<f32>> {
+    if v.len() == expected_dim {
+        return Ok(v);
+    }
+    tracing::warn!(
+        "Embedding dimension mismatch: API returned {} dims, index expects {} (policy: {:?})",
+        v.len(),
+        expected_dim,
+        policy
+    );
+    match policy {
+        DimMismatchPolicy::Error => Err(anyhow!(
+            "Embedding dimension mismatch: API returned {} dims, index expects {}. \
+             Check EMBEDDING_MODEL or set RAG_EMBEDDING_DIM_MISMATCH=truncate",
+            v.len(),
+            expected_dim
+        )),
+        DimMismatchPolicy::Truncate => {
+            v.resize(expected_dim, 0.0);
+            Ok(v)
+        }
+    }
+}
+
+/// Embed `query`, reusing cached vectors unless `use_cache` is false or the cache is disabled
+/// globally. The cache key includes the embedding model, so switching models never serves stale vectors.
+/// Queries that only differ in case, punctuation or whitespace share one entry.
+pub async fn embed_query(
+    st: &AppState,
+    query: &str,
+    expected_dim: Option<usize>,
+    use_cache: bool,
+) -> Result<Vec<f32>> {
+    embed_query_with_model(st, &st.cfg.embedding_model, query, expected_dim, use_cache).await
+}
+
+/// `embed_query` with another model of the configured provider, for indices built with that model
+pub async fn embed_query_with_model(
+    st: &AppState,
+    model: &str,
+    query: &str,
+    expected_dim: Option<usize>,
+    use_cache: bool,
+) -> Result<Vec<f32>> {
+    embed_queries(st, model, &[query], expected_dim, use_cache)
+        .await?
+        .pop()
+        .ok_or_else(|| anyhow!("Embedding API returned no vectors"))
+}
+
+/// Like `embed_query_with_model` for several queries: all cache misses are embedded in a single
+/// API call. Vectors are returned in input order.
+async fn embed_queries(
+    st: &AppState,
+    model: &str,
+    queries: &[&str],
+    expected_dim: Option<usize>,
+    use_cache: bool,
+) -> Result<Vec<Vec<f32>>> {
+    let use_cache = use_cache && st.cfg.embed_cache_enabled;
+    let mut vectors: Vec<Option<Vec<f32>>> = Vec::with_capacity(queries.len());
+    let mut missing: Vec<usize> = Vec::new();
+    for (i, query) in queries.iter().enumerate() {
+        let cache_key = (model.to_string(), query_cache_key(query));
+        let cached = if use_cache {
+            st.query_embedding_cache.get(&cache_key).await
+        } else {
+            None
+        };
+        if cached.is_none() {
+            missing.push(i);
+        }
+        vectors.push(cached.map(|v| v.as_ref().clone()));
+    }
+
+    if !missing.is_empty() {
+        let texts: Vec<&str> = missing.iter().map(|&i| queries[i]).collect();
+        let fetched = embed_texts_with_model(st, model, &texts).await?;
+        for (i, v) in missing.into_iter().zip(fetched) {
+            // A bypassing request still refreshes the cache for later requests
+            if st.cfg.embed_cache_enabled {
+                let cache_key = (model.to_string(), query_cache_key(queries[i]));
+                st.query_embedding_cache.insert(cache_key, Arc::new(v.clone())).await;
+            }
+            vectors[i] = Some(v);
+        }
+    }
+
+    vectors
+        .into_iter()
+        .map(|v| {
+            let v = v.ok_or_else(|| anyhow!("Embedding API returned no vectors"))?;
+            match expected_dim {
+                Some(dim) => fit_embedding_dim(v, dim, st.cfg.embedding_dim_mismatch),
+                None => Ok(v),
+            }
+        })
+        .collect()
 }
 
-pub async fn embed_query(st: &AppState, query: &str) -> Result<Vec<f32>> {
-    #[derive(Serialize)]
-    struct EmbReq<'a> {
-        model: &'a str,
-        input: Vec<&'a str>,
+/// Query part of the embedding cache key: `normalize_for_match`, or the raw query if that leaves nothing
+fn query_cache_key(query: &str) -> String {
+    let normalized = normalize_for_match(query);
+    if normalized.is_empty() {
+        query.to_string()
+    } else {
+        normalized
     }
-    let url = format!("{}/embeddings", st.cfg.llm_base_url);
+}
+
+/// Embed several texts in one API call; vectors are returned in input order
+pub async fn embed_texts(st: &AppState, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+    embed_texts_with_model(st, &st.cfg.embedding_model, texts).await
+}
+
+async fn embed_texts_with_model(st: &AppState, model: &str, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+    let provider = st.cfg.embedding_provider;
+    let (path, body) = match provider {
+        LlmProvider::OpenAi => ("embeddings", serde_json::json!({ "model": model, "input": texts })),
+        LlmProvider::Cohere => (
+            "embed",
+            serde_json::json!({
+                "model": model,
+                "texts": texts,
+                "input_type": "search_query",
+                "embedding_types": ["float"],
+            }),
+        ),
+        LlmProvider::Ollama => ("embed", serde_json::json!({ "model": model, "input": texts })),
+    };
+    let url = format!("{}/{}", st.cfg.embedding_base_url, path);
     let resp = st
         .http
         .post(url)
-        .bearer_auth(&st.cfg.llm_api_key)
-        .json(&EmbReq {
-            model: &st.cfg.embedding_model,
-            input: vec![query],
-        })
+        .bearer_auth(&st.cfg.embedding_api_key)
+        .json(&body)
         .send()
         .await
         .context("Embedding request failed")?;
@@ -37,51 +179,132 @@ pub async fn embed_query(st: &AppState, query: &str) -> Result<Vec<f32>> {
         let body = resp.text().await.unwrap_or_default();
         return Err(anyhow!("Embedding API error: {} - {}", status, body));
     }
-    let data: EmbeddingsResponse = resp.json().await.context("Invalid embeddings JSON")?;
-    let v = data
-        .data
-        .into_iter()
-        .next()
-        .ok_or_else(|| anyhow!("Embedding API returned no vectors"))?
-        .embedding;
-    Ok(v)
+    let bytes = resp.bytes().await.context("Embedding response interrupted")?;
+    parse_embeddings(provider, &bytes, texts.len())
 }
 
-pub async fn llm_answer(
-    st: &AppState, 
-    query: &str, 
-    context: &str, 
-    speaker_profile: Option<&str>,
-    speaker2_profile: Option<&str>,
-    speaker_name: Option<&str>,
-    speaker2_name: Option<&str>,
-) -> Result<String> {
-    #[derive(Serialize)]
-    struct ChatReq<'a> {
-        model: &'a str,
-        messages: Vec<ChatMsg<'a>>,
-        temperature: f32,
+/// Vectors in input order from a provider's embeddings response
+fn parse_embeddings(provider: LlmProvider, body: &[u8], inputs: usize) -> Result<Vec<Vec<f32>>> {
+    if provider == LlmProvider::OpenAi {
+        let data: EmbeddingsResponse = serde_json::from_slice(body).context("Invalid embeddings JSON")?;
+        return order_by_index(data.data, inputs);
     }
-    #[derive(Serialize)]
-    struct ChatMsg<'a> {
-        role: &'a str,
-        content: &'a str,
+    // Cohere and Ollama return one vector per input, in input order
+    let data: EmbeddingListResponse = serde_json::from_slice(body).context("Invalid embeddings JSON")?;
+    let (EmbeddingList::Plain(vectors) | EmbeddingList::ByType { float: vectors }) = data.embeddings;
+    if vectors.len() != inputs {
+        return Err(anyhow!(
+            "Embedding API returned {} vectors for {} inputs",
+            vectors.len(),
+            inputs
+        ));
     }
+    Ok(vectors)
+}
 
-    #[derive(Deserialize)]
-    struct ChatResp {
-        choices: Vec<ChatChoice>,
+/// Put the vectors into input order by their `index`, or keep the response order if the API
+/// sent no indices
+fn order_by_index(data: Vec<EmbeddingDatum>, inputs: usize) -> Result<Vec<Vec<f32>>> {
+    if data.len() != inputs {
+        return Err(anyhow!(
+            "Embedding API returned {} vectors for {} inputs",
+            data.len(),
+            inputs
+        ));
+    }
+    if data.iter().any(|d| d.index.is_none()) {
+        return Ok(data.into_iter().map(|d| d.embedding).collect());
     }
-    #[derive(Deserialize)]
-    struct ChatChoice {
-        message: ChatChoiceMsg,
+    let mut ordered: Vec<Option<Vec<f32>>> = vec![None; inputs];
+    for d in data {
+        let index = d.index.unwrap_or_default();
+        let slot = ordered
+            .get_mut(index)
+            .ok_or_else(|| anyhow!("Embedding API returned index {} for {} inputs", index, inputs))?;
+        if slot.replace(d.embedding).is_some() {
+            return Err(anyhow!("Embedding API returned index {} twice", index));
+        }
     }
-    #[derive(Deserialize)]
-    struct ChatChoiceMsg {
-        content: String,
+    // Every slot is filled: `inputs` distinct in-range indices
+    Ok(ordered.into_iter().flatten().collect())
+}
+
+/// Who said a previous turn of the conversation; clients can't inject system messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    User,
+    Assistant,
+}
+
+impl ChatRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChatRole::User => "user",
+            ChatRole::Assistant => "assistant",
+        }
     }
+}
+
+/// One earlier message of the conversation, sent between the system prompt and the current question
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatTurn {
+    pub role: ChatRole,
+    pub content: String,
+}
+
+/// System and user message plus temperature for one answer, as sent by `llm_answer`
+#[derive(Debug, Clone, Serialize)]
+pub struct AnswerPrompt {
+    pub system: String,
+    /// Earlier turns, oldest first; `build_answer_prompt` leaves this empty
+    pub history: Vec<ChatTurn>,
+    pub user: String,
+    pub temperature: f32,
+    /// Answer length cap; None leaves it to the API
+    pub max_tokens: Option<u32>,
+}
 
-    let (system, user_prompt) = if let (Some(profile1), Some(profile2), Some(name1), Some(name2)) = 
+pub async fn llm_answer(st: &AppState, prompt: &AnswerPrompt) -> Result<String> {
+    chat_completion(st, &prompt.system, &prompt.history, &prompt.user, prompt.temperature, prompt.max_tokens).await
+}
+
+/// Answer languages by request code; German is the default
+const ANSWER_LANGUAGES: &[(&str, &str)] = &[
+    ("de", "German"),
+    ("en", "English"),
+    ("fr", "French"),
+    ("es", "Spanish"),
+    ("it", "Italian"),
+    ("nl", "Dutch"),
+];
+
+pub const DEFAULT_ANSWER_LANGUAGE: &str = "German";
+
+/// Prompt name of the answer language for a code like "en" (case-insensitive); None if unsupported
+pub fn answer_language(code: &str) -> Option<&'static str> {
+    let code = code.trim();
+    ANSWER_LANGUAGES
+        .iter()
+        .find(|(c, _)| c.eq_ignore_ascii_case(code))
+        .map(|&(_, name)| name)
+}
+
+/// Prompt for the answer mode picked by the given speakers: discussion (two), persona (one) or neutral.
+/// `language` is the answer language's English name, e.g. "German".
+#[allow(clippy::too_many_arguments)]
+pub fn build_answer_prompt(
+    temperatures: AnswerTemperatures,
+    language: &str,
+    query: &str,
+    context: &str,
+    speaker_profile: Option<&str>,
+    speaker2_profile: Option<&str>,
+    speaker_name: Option<&str>,
+    speaker2_name: Option<&str>,
+) -> AnswerPrompt {
+    let language_clause = format!("Answer in {language} unless the user asks otherwise");
+    let (system, user_prompt, temperature) = if let (Some(profile1), Some(profile2), Some(name1), Some(name2)) = 
         (speaker_profile, speaker2_profile, speaker_name, speaker2_name) {
         // Discussion/debate mode with two speakers
         let system = format!(
@@ -99,8 +322,8 @@ pub async fn llm_answer(
             - Include citations inline like: (Episode 281, 12:38-17:19)\n\
             - If sources don't contain enough information, have the speakers acknowledge this in character\n\
             - Make it feel like a real conversation with interruptions, agreements, disagreements, humor, etc.\n\
-            - Answer in German unless the user asks otherwise",
-            name1, profile1, name2, profile2, name1, name2
+            - {}",
+            name1, profile1, name2, profile2, name1, name2, language_clause
         );
         
         let user_prompt = format!(
@@ -111,7 +334,7 @@ pub async fn llm_answer(
             query, context, name1, name2
         );
         
-        (system, user_prompt)
+        (system, user_prompt, temperatures.discussion)
     } else if let Some(profile) = speaker_profile {
         // Single speaker persona mode
         let system = format!(
@@ -125,8 +348,8 @@ pub async fn llm_answer(
             - Match the humor style and attitude described\n\
             - If the sources don't contain enough information, say so in character\n\
             - Include citations inline like: (Episode 281, 12:38-17:19)\n\
-            - Answer in German unless the user asks otherwise",
-            profile
+            - {}",
+            profile, language_clause
         );
         
         let user_prompt = format!(
@@ -136,36 +359,160 @@ pub async fn llm_answer(
             query, context
         );
         
-        (system, user_prompt)
+        (system, user_prompt, temperatures.persona)
     } else {
         // Neutral mode (original behavior)
-        let system = "You are a helpful RAG assistant. Answer the user's question using ONLY the provided SOURCES (transcript excerpts). If the sources do not contain enough information, say so explicitly. When you make a factual claim, cite it inline like: (Episode 281, 12:38-17:19). Keep the answer concise and in German unless the user asks otherwise.".to_string();
+        let system = format!("You are a helpful RAG assistant. Answer the user's question using ONLY the provided SOURCES (transcript excerpts). If the sources do not contain enough information, say so explicitly. When you make a factual claim, cite it inline like: (Episode 281, 12:38-17:19). Keep the answer concise. {language_clause}.");
         
         let user_prompt = format!(
             "QUESTION:\n{query}\n\nSOURCES:\n{context}\n\nINSTRUCTIONS:\n- Use the sources only.\n- Prefer quoting short phrases when helpful.\n- Include citations with episode number and time window.\n"
         );
         
-        (system, user_prompt)
+        (system, user_prompt, temperatures.neutral)
     };
 
-    let url = format!("{}/chat/completions", st.cfg.llm_base_url);
+    AnswerPrompt {
+        system,
+        history: Vec::new(),
+        user: user_prompt,
+        temperature,
+        max_tokens: None,
+    }
+}
+
+/// Number the sentences of `answer` and ask the LLM which are not backed by `context`.
+/// Returns the unsupported sentences in answer order.
+pub async fn llm_verify_answer(st: &AppState, answer: &str, context: &str) -> Result<Vec<String>> {
+    let sentences = split_sentences(answer);
+    if sentences.is_empty() {
+        return Ok(Vec::new());
+    }
+    let numbered: String = sentences
+        .iter()
+        .enumerate()
+        .map(|(i, s)| format!("{}. {}\n", i + 1, s))
+        .collect();
+
+    let system = "You check answers against SOURCES (transcript excerpts). A sentence is supported \
+        if the sources state or directly imply it; citations, greetings and statements that the \
+        sources lack information count as supported. Reply ONLY with a JSON array of the numbers \
+        of unsupported sentences, e.g. [2, 5], or [] if all are supported.";
+    let user_prompt = format!("SOURCES:\n{context}\n\nANSWER SENTENCES:\n{numbered}");
+
+    let reply = chat_completion(st, system, &[], &user_prompt, 0.0, None).await?;
+    let numbers = parse_sentence_numbers(&reply)
+        .ok_or_else(|| anyhow!("Unparseable verification reply: {}", reply))?;
+    Ok(sentences
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| numbers.contains(&(i + 1)))
+        .map(|(_, s)| s)
+        .collect())
+}
+
+/// Split an answer into sentences at `.`/`!`/`?` followed by whitespace and at line breaks.
+/// Dots inside citations like "12:38" or numbers like "3.5" don't split.
+fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            sentences.push(std::mem::take(&mut current));
+            continue;
+        }
+        current.push(c);
+        if matches!(c, '.' | '!' | '?') && chars.peek().is_none_or(|n| n.is_whitespace()) {
+            sentences.push(std::mem::take(&mut current));
+        }
+    }
+    sentences.push(current);
+    sentences
+        .into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| s.chars().any(|c| c.is_alphanumeric()))
+        .collect()
+}
+
+/// The first JSON array of numbers in `reply` (models sometimes wrap it in prose or code fences)
+fn parse_sentence_numbers(reply: &str) -> Option<Vec<usize>> {
+    let start = reply.find('[')?;
+    let end = start + reply[start..].find(']')?;
+    serde_json::from_str(&reply[start..=end]).ok()
+}
+
+#[derive(Serialize)]
+struct ChatReq<'a> {
+    model: &'a str,
+    messages: Vec<ChatMsg<'a>>,
+    /// Not for Ollama, which takes it in `options`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<OllamaOptions>,
+    /// Ask for token deltas instead of one completion; Ollama streams unless told otherwise
+    stream: bool,
+}
+
+#[derive(Serialize)]
+struct OllamaOptions {
+    temperature: f32,
+    /// Ollama's name for `max_tokens`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct ChatMsg<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+/// POST a system + history + user chat request; non-success statuses become errors
+async fn send_chat_request(
+    st: &AppState,
+    system: &str,
+    history: &[ChatTurn],
+    user_prompt: &str,
+    temperature: f32,
+    max_tokens: Option<u32>,
+    stream: bool,
+) -> Result<reqwest::Response> {
+    let provider = st.cfg.llm_provider;
+    let path = match provider {
+        LlmProvider::OpenAi => "chat/completions",
+        LlmProvider::Cohere | LlmProvider::Ollama => "chat",
+    };
+    let (temperature, max_tokens, options) = match provider {
+        LlmProvider::Ollama => (None, None, Some(OllamaOptions { temperature, num_predict: max_tokens })),
+        _ => (Some(temperature), max_tokens, None),
+    };
+    let url = format!("{}/{}", st.cfg.llm_base_url, path);
     let resp = st
         .http
         .post(url)
         .bearer_auth(&st.cfg.llm_api_key)
         .json(&ChatReq {
             model: &st.cfg.llm_model,
-            messages: vec![
-                ChatMsg {
-                    role: "system",
-                    content: &system,
-                },
-                ChatMsg {
-                    role: "user",
-                    content: &user_prompt,
-                },
-            ],
-            temperature: 0.2,
+            messages: std::iter::once(ChatMsg {
+                role: "system",
+                content: system,
+            })
+            .chain(history.iter().map(|t| ChatMsg {
+                role: t.role.as_str(),
+                content: &t.content,
+            }))
+            .chain(std::iter::once(ChatMsg {
+                role: "user",
+                content: user_prompt,
+            }))
+            .collect(),
+            temperature,
+            max_tokens,
+            options,
+            stream,
         })
         .send()
         .await
@@ -176,17 +523,464 @@ pub async fn llm_answer(
         let body = resp.text().await.unwrap_or_default();
         return Err(anyhow!("Chat API error: {} - {}", status, body));
     }
+    Ok(resp)
+}
+
+/// Like `llm_answer`, but streams the answer as content deltas from a `stream: true` completion
+pub async fn llm_answer_stream(st: &AppState, prompt: &AnswerPrompt) -> Result<impl Stream<Item = Result<String>>> {
+    let resp = send_chat_request(
+        st,
+        &prompt.system,
+        &prompt.history,
+        &prompt.user,
+        prompt.temperature,
+        prompt.max_tokens,
+        true,
+    )
+    .await?;
+
+    let parser = StreamDeltaParser::new(st.cfg.llm_provider);
+    Ok(stream::try_unfold((resp, parser), |(mut resp, mut parser)| async move {
+        loop {
+            if parser.done {
+                return Ok(None);
+            }
+            let Some(chunk) = resp.chunk().await.context("Chat stream interrupted")? else {
+                return Ok(None);
+            };
+            let deltas = parser.push(&chunk)?;
+            if !deltas.is_empty() {
+                return Ok(Some((deltas.concat(), (resp, parser))));
+            }
+        }
+    }))
+}
+
+/// Incremental parser for streamed chat deltas: `data: {...}` SSE lines (OpenAI, ending at
+/// `data: [DONE]`; Cohere, ending at `message-end`) or NDJSON lines (Ollama, ending at `"done": true`)
+struct StreamDeltaParser {
+    provider: LlmProvider,
+    buf: Vec<u8>,
+    done: bool,
+}
+
+impl StreamDeltaParser {
+    fn new(provider: LlmProvider) -> Self {
+        Self {
+            provider,
+            buf: Vec::new(),
+            done: false,
+        }
+    }
 
-    let data: ChatResp = resp.json().await.context("Invalid chat JSON")?;
-    let content = data
-        .choices
-        .into_iter()
-        .next()
-        .ok_or_else(|| anyhow!("Chat API returned no choices"))?
-        .message
-        .content;
+    /// Feed raw bytes; returns the content deltas of all lines completed by this chunk
+    fn push(&mut self, chunk: &[u8]) -> Result<Vec<String>> {
+        self.buf.extend_from_slice(chunk);
+        let mut deltas = Vec::new();
+        // Lines end at '\n', so a complete line is always valid UTF-8 even if a chunk split a character
+        while let Some(pos) = self.buf.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buf.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim();
+            let data = match self.provider {
+                LlmProvider::Ollama => line,
+                // Other SSE fields (`event:`, comments) carry nothing we need
+                _ => match line.strip_prefix("data:") {
+                    Some(data) => data.trim(),
+                    None => continue,
+                },
+            };
+            if data.is_empty() {
+                continue;
+            }
+            if self.provider == LlmProvider::OpenAi && data == "[DONE]" {
+                self.done = true;
+                break;
+            }
+            let parsed: serde_json::Value = serde_json::from_str(data).context("Invalid chat stream chunk")?;
+            if let Some(error) = parsed.get("error").filter(|e| !e.is_null()) {
+                return Err(anyhow!("Chat stream error: {}", error));
+            }
+            match self.provider {
+                LlmProvider::OpenAi => deltas.extend(
+                    parsed["choices"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|c| c["delta"]["content"].as_str())
+                        .map(str::to_string),
+                ),
+                LlmProvider::Cohere => match parsed["type"].as_str() {
+                    Some("content-delta") => {
+                        deltas.extend(parsed.pointer("/delta/message/content/text").and_then(|t| t.as_str()).map(str::to_string))
+                    }
+                    Some("message-end") => self.done = true,
+                    _ => {}
+                },
+                LlmProvider::Ollama => {
+                    deltas.extend(parsed.pointer("/message/content").and_then(|t| t.as_str()).map(str::to_string));
+                    self.done = parsed["done"].as_bool().unwrap_or(false);
+                }
+            }
+            if self.done {
+                break;
+            }
+        }
+        deltas.retain(|d| !d.is_empty());
+        Ok(deltas)
+    }
+}
+
+/// Answer text of a non-streaming chat response
+fn completion_text(provider: LlmProvider, resp: &serde_json::Value) -> Option<String> {
+    match provider {
+        LlmProvider::OpenAi => resp.pointer("/choices/0/message/content")?.as_str().map(str::to_string),
+        // Cohere v2: a list of content blocks
+        LlmProvider::Cohere => {
+            let blocks = resp.pointer("/message/content")?.as_array()?;
+            Some(blocks.iter().filter_map(|b| b["text"].as_str()).collect())
+        }
+        LlmProvider::Ollama => resp.pointer("/message/content")?.as_str().map(str::to_string),
+    }
+}
+
+/// One non-streaming chat completion with a system and a user message
+pub(crate) async fn chat_completion(
+    st: &AppState,
+    system: &str,
+    history: &[ChatTurn],
+    user_prompt: &str,
+    temperature: f32,
+    max_tokens: Option<u32>,
+) -> Result<String> {
+    let resp = send_chat_request(st, system, history, user_prompt, temperature, max_tokens, false).await?;
+    let data: serde_json::Value = resp.json().await.context("Invalid chat JSON")?;
+    let content = completion_text(st.cfg.llm_provider, &data)
+        .ok_or_else(|| anyhow!("Chat API returned no answer: {}", data))?;
     Ok(content.trim().to_string())
 }
 
 
 
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::test_support::spawn_app;
+    use axum::{http::StatusCode, response::IntoResponse, routing::post, Json, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Serve a fake OpenAI-compatible embeddings endpoint that always returns `embedding`
+    pub(crate) async fn mock_embeddings_server(embedding: Vec<f32>) -> String {
+        mock_counting_embeddings_server(embedding, Arc::new(AtomicUsize::new(0))).await
+    }
+
+    /// Like `mock_embeddings_server`, counting the requests it receives in `calls`
+    pub(crate) async fn mock_counting_embeddings_server(embedding: Vec<f32>, calls: Arc<AtomicUsize>) -> String {
+        mock_embeddings_api(move |_, inputs| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Some(vec![embedding.clone(); inputs.len()])
+        })
+        .await
+    }
+
+    /// Serve a fake OpenAI-compatible embeddings endpoint: `embed(model, inputs)` returns one
+    /// vector per input (a single string input counts as one), or None for a 400 response.
+    /// The vectors are sent with their `index` in reverse order, as the API does not promise any.
+    pub(crate) async fn mock_embeddings_api<F>(embed: F) -> String
+    where
+        F: Fn(&str, &[String]) -> Option<Vec<Vec<f32>>> + Clone + Send + Sync + 'static,
+    {
+        let app = Router::new().route(
+            "/embeddings",
+            post(move |Json(body): Json<serde_json::Value>| {
+                let inputs: Vec<String> = match &body["input"] {
+                    serde_json::Value::String(s) => vec![s.clone()],
+                    other => serde_json::from_value(other.clone()).unwrap(),
+                };
+                let vectors = embed(body["model"].as_str().unwrap_or_default(), &inputs);
+                async move {
+                    let Some(vectors) = vectors else {
+                        return StatusCode::BAD_REQUEST.into_response();
+                    };
+                    let data: Vec<_> = vectors
+                        .into_iter()
+                        .enumerate()
+                        .rev()
+                        .map(|(i, v)| serde_json::json!({ "index": i, "embedding": v }))
+                        .collect();
+                    Json(serde_json::json!({ "data": data })).into_response()
+                }
+            }),
+        );
+        format!("http://{}", spawn_app(app).await)
+    }
+
+    #[tokio::test]
+    async fn test_embed_query_pads_short_vector_when_configured() {
+        let mut cfg = AppConfig::for_tests();
+        cfg.embedding_base_url = mock_embeddings_server(vec![0.5, 0.25]).await;
+        cfg.embedding_dim_mismatch = DimMismatchPolicy::Truncate;
+        let st = AppState::for_tests(cfg);
+
+        let v = embed_query(&st, "hallo", Some(4), true).await.unwrap();
+        assert_eq!(v, vec![0.5, 0.25, 0.0, 0.0]);
+
+        let v = embed_query(&st, "hallo", Some(1), true).await.unwrap();
+        assert_eq!(v, vec![0.5]);
+    }
+
+    #[tokio::test]
+    async fn test_embed_query_errors_on_mismatch_by_default() {
+        let mut cfg = AppConfig::for_tests();
+        cfg.embedding_base_url = mock_embeddings_server(vec![0.5, 0.25]).await;
+        let st = AppState::for_tests(cfg);
+
+        let err = embed_query(&st, "hallo", Some(4), true).await.unwrap_err();
+        assert!(err.to_string().contains("dimension mismatch"));
+    }
+
+    #[tokio::test]
+    async fn test_no_embed_cache_forces_fresh_call() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut cfg = AppConfig::for_tests();
+        cfg.embedding_base_url = mock_counting_embeddings_server(vec![0.5, 0.25], calls.clone()).await;
+        let st = AppState::for_tests(cfg);
+
+        embed_query(&st, "hallo", None, true).await.unwrap();
+        embed_query(&st, "hallo", None, true).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Per-request bypass hits the API despite the warm cache
+        embed_query(&st, "hallo", None, false).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // A different embedding model never reuses the cached vector
+        let mut other_model = st.clone();
+        other_model.cfg.embedding_model = "other-embedding".to_string();
+        embed_query(&other_model, "hallo", None, true).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // Whitespace, case and punctuation differences share the cached vector
+        embed_query(&st, "  Hallo? ", None, true).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // Global kill switch
+        let mut disabled = st.clone();
+        disabled.cfg.embed_cache_enabled = false;
+        embed_query(&disabled, "hallo", None, true).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_embed_queries_batches_misses_and_maps_by_index() {
+        let inputs_seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = inputs_seen.clone();
+        // Each vector encodes the length of its input
+        let mut cfg = AppConfig::for_tests();
+        cfg.embedding_base_url = mock_embeddings_api(move |_, inputs| {
+            seen.lock().unwrap().push(inputs.to_vec());
+            Some(inputs.iter().map(|t| vec![t.len() as f32, 1.0]).collect())
+        })
+        .await;
+        let st = AppState::for_tests(cfg);
+
+        let v = embed_queries(&st, &st.cfg.embedding_model, &["a", "bbb", "cc"], None, true).await.unwrap();
+        assert_eq!(v, vec![vec![1.0, 1.0], vec![3.0, 1.0], vec![2.0, 1.0]]);
+
+        // Only the uncached query goes out, still in one request
+        let v = embed_queries(&st, &st.cfg.embedding_model, &["cc", "dddd", "a"], None, true).await.unwrap();
+        assert_eq!(v, vec![vec![2.0, 1.0], vec![4.0, 1.0], vec![1.0, 1.0]]);
+        assert_eq!(
+            *inputs_seen.lock().unwrap(),
+            vec![vec!["a", "bbb", "cc"], vec!["dddd"]]
+        );
+
+        assert!(embed_queries(&st, &st.cfg.embedding_model, &[], None, true).await.unwrap().is_empty());
+        assert_eq!(inputs_seen.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_order_by_index_rejects_bad_indices() {
+        let datum = |index, x| EmbeddingDatum { embedding: vec![x], index };
+        assert_eq!(
+            order_by_index(vec![datum(Some(1), 1.0), datum(Some(0), 0.0)], 2).unwrap(),
+            vec![vec![0.0], vec![1.0]]
+        );
+        // Without indices the response order is kept
+        assert_eq!(
+            order_by_index(vec![datum(None, 1.0), datum(None, 0.0)], 2).unwrap(),
+            vec![vec![1.0], vec![0.0]]
+        );
+        assert!(order_by_index(vec![datum(Some(0), 0.0), datum(Some(0), 1.0)], 2).is_err());
+        assert!(order_by_index(vec![datum(Some(0), 0.0), datum(Some(2), 1.0)], 2).is_err());
+        assert!(order_by_index(vec![datum(Some(0), 0.0)], 2).is_err());
+    }
+
+    /// Serve a fake chat completions endpoint answering `content`; every request body is
+    /// appended to `requests`
+    pub(crate) async fn mock_chat_server(requests: Arc<std::sync::Mutex<Vec<serde_json::Value>>>, content: &'static str) -> String {
+        let app = Router::new().route(
+            "/chat/completions",
+            post(move |Json(body): Json<serde_json::Value>| {
+                requests.lock().unwrap().push(body);
+                async move { Json(serde_json::json!({ "choices": [{ "message": { "content": content } }] })) }
+            }),
+        );
+        format!("http://{}", spawn_app(app).await)
+    }
+
+    /// Like `mock_chat_server`, but answers as an OpenAI server-sent event stream of `deltas`
+    pub(crate) async fn mock_chat_stream_server(
+        requests: Arc<std::sync::Mutex<Vec<serde_json::Value>>>,
+        deltas: &'static [&'static str],
+    ) -> String {
+        let app = Router::new().route(
+            "/chat/completions",
+            post(move |Json(body): Json<serde_json::Value>| {
+                requests.lock().unwrap().push(body);
+                let frames = deltas
+                    .iter()
+                    .map(|d| format!("data: {}\n\n", serde_json::json!({ "choices": [{ "delta": { "content": d } }] })))
+                    .collect::<String>();
+                async move {
+                    format!("data: {{\"choices\":[{{\"delta\":{{\"role\":\"assistant\"}}}}]}}\n\n{frames}data: [DONE]\n\n")
+                }
+            }),
+        );
+        format!("http://{}", spawn_app(app).await)
+    }
+
+    #[tokio::test]
+    async fn test_llm_answer_uses_temperature_per_mode() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut cfg = AppConfig::for_tests();
+        cfg.llm_base_url = mock_chat_server(seen.clone(), "ok").await;
+        cfg.answer_temperatures = AnswerTemperatures {
+            neutral: 0.1,
+            persona: 0.4,
+            discussion: 0.9,
+        };
+        let st = AppState::for_tests(cfg);
+
+        let temps = st.cfg.answer_temperatures;
+        for prompt in [
+            build_answer_prompt(temps, DEFAULT_ANSWER_LANGUAGE, "Frage", "SOURCE", None, None, None, None),
+            build_answer_prompt(temps, DEFAULT_ANSWER_LANGUAGE, "Frage", "SOURCE", Some("Profil"), None, Some("Tim"), None),
+            build_answer_prompt(temps, DEFAULT_ANSWER_LANGUAGE, "Frage", "SOURCE", Some("Profil"), Some("Profil 2"), Some("Tim"), Some("Clemens")),
+        ] {
+            llm_answer(&st, &prompt).await.unwrap();
+        }
+
+        let seen: Vec<f32> = seen.lock().unwrap().iter().map(|b| b["temperature"].as_f64().unwrap() as f32).collect();
+        assert_eq!(seen, vec![0.1, 0.4, 0.9]);
+    }
+
+    #[tokio::test]
+    async fn test_llm_answer_sends_history_between_system_and_question() {
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut cfg = AppConfig::for_tests();
+        cfg.llm_base_url = mock_chat_server(requests.clone(), "ok").await;
+        let st = AppState::for_tests(cfg);
+
+        let mut prompt = build_answer_prompt(st.cfg.answer_temperatures, DEFAULT_ANSWER_LANGUAGE, "Und Clemens?", "SOURCE", None, None, None, None);
+        prompt.history = vec![
+            ChatTurn { role: ChatRole::User, content: "Was nutzt Tim?".to_string() },
+            ChatTurn { role: ChatRole::Assistant, content: "Universal Control.".to_string() },
+        ];
+        llm_answer(&st, &prompt).await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        let roles: Vec<String> = requests[0]["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| format!("{}: {}", m["role"].as_str().unwrap(), m["content"].as_str().unwrap()))
+            .collect();
+        assert_eq!(roles.len(), 4);
+        assert!(roles[0].starts_with("system: "));
+        assert_eq!(roles[1], "user: Was nutzt Tim?");
+        assert_eq!(roles[2], "assistant: Universal Control.");
+        assert!(roles[3].starts_with("user: ") && roles[3].contains("Und Clemens?"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_answer_flags_fabricated_sentence() {
+        let mut cfg = AppConfig::for_tests();
+        cfg.llm_base_url = mock_chat_server(Arc::default(), "```json\n[2]\n```").await;
+        let st = AppState::for_tests(cfg);
+
+        let answer = "Tim nutzt Universal Control (Episode 281, 12:38-17:19). \
+            Apple hat das Feature 2010 erfunden. Es funktioniert über WLAN.";
+        let unsupported = llm_verify_answer(&st, answer, "SOURCE 1 ...").await.unwrap();
+        assert_eq!(unsupported, vec!["Apple hat das Feature 2010 erfunden.".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_llm_answer_stream_yields_deltas() {
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut cfg = AppConfig::for_tests();
+        cfg.llm_base_url = mock_chat_stream_server(requests.clone(), &["Hallo", " Wel", "t (Episode 281)"]).await;
+        let st = AppState::for_tests(cfg);
+
+        let prompt = build_answer_prompt(st.cfg.answer_temperatures, DEFAULT_ANSWER_LANGUAGE, "frage", "SOURCE: ...", None, None, None, None);
+        let tokens = llm_answer_stream(&st, &prompt).await.unwrap();
+        let deltas: Vec<String> = futures::TryStreamExt::try_collect(tokens).await.unwrap();
+        assert_eq!(deltas.concat(), "Hallo Welt (Episode 281)");
+        assert_eq!(requests.lock().unwrap()[0]["stream"], true);
+    }
+
+    #[test]
+    fn test_sse_parser_handles_split_chunks() {
+        let line = format!("data: {}\n", serde_json::json!({ "choices": [{ "delta": { "content": "Grüße" } }] }));
+        let bytes = line.as_bytes();
+        // Split inside the two-byte "ü"
+        let split = line.find('ü').unwrap() + 1;
+
+        let mut parser = StreamDeltaParser::new(LlmProvider::OpenAi);
+        assert!(parser.push(&bytes[..split]).unwrap().is_empty());
+        assert_eq!(parser.push(&bytes[split..]).unwrap(), vec!["Grüße".to_string()]);
+        assert!(parser.push(b"data: [DONE]\n").unwrap().is_empty());
+        assert!(parser.done);
+    }
+
+    #[test]
+    fn test_parse_embeddings_per_provider() {
+        let cohere = br#"{"id":"x","embeddings":{"float":[[0.5,0.25],[1.0,0.0]]},"texts":["a","b"]}"#;
+        assert_eq!(
+            parse_embeddings(LlmProvider::Cohere, cohere, 2).unwrap(),
+            vec![vec![0.5, 0.25], vec![1.0, 0.0]]
+        );
+        let ollama = br#"{"model":"nomic-embed-text","embeddings":[[0.5,0.25]]}"#;
+        assert_eq!(parse_embeddings(LlmProvider::Ollama, ollama, 1).unwrap(), vec![vec![0.5, 0.25]]);
+        assert!(parse_embeddings(LlmProvider::Ollama, ollama, 2).is_err());
+        // An OpenAI-shaped body is not mistaken for another provider's
+        let openai = br#"{"data":[{"embedding":[0.5,0.25],"index":0}]}"#;
+        assert!(parse_embeddings(LlmProvider::Ollama, openai, 1).is_err());
+        assert_eq!(parse_embeddings(LlmProvider::OpenAi, openai, 1).unwrap(), vec![vec![0.5, 0.25]]);
+    }
+
+    #[test]
+    fn test_stream_parser_handles_ollama_and_cohere() {
+        let mut ollama = StreamDeltaParser::new(LlmProvider::Ollama);
+        let lines = "{\"message\":{\"role\":\"assistant\",\"content\":\"Hal\"},\"done\":false}\n\
+            {\"message\":{\"role\":\"assistant\",\"content\":\"lo\"},\"done\":false}\n\
+            {\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true}\n";
+        assert_eq!(ollama.push(lines.as_bytes()).unwrap(), vec!["Hal".to_string(), "lo".to_string()]);
+        assert!(ollama.done);
+
+        let mut cohere = StreamDeltaParser::new(LlmProvider::Cohere);
+        let frames = "event: message-start\ndata: {\"type\":\"message-start\"}\n\n\
+            event: content-delta\ndata: {\"type\":\"content-delta\",\"delta\":{\"message\":{\"content\":{\"text\":\"Hallo\"}}}}\n\n\
+            event: message-end\ndata: {\"type\":\"message-end\"}\n\n";
+        assert_eq!(cohere.push(frames.as_bytes()).unwrap(), vec!["Hallo".to_string()]);
+        assert!(cohere.done);
+
+        let mut failing = StreamDeltaParser::new(LlmProvider::Ollama);
+        assert!(failing.push(b"{\"error\":\"model not found\"}\n").is_err());
+
+        let reply = serde_json::json!({ "message": { "role": "assistant", "content": [{ "type": "text", "text": "Antwort" }] } });
+        assert_eq!(completion_text(LlmProvider::Cohere, &reply).as_deref(), Some("Antwort"));
+    }
+}
@@ -1,3 +1,4 @@
+pub mod ann;
 pub mod retrieval;
 pub mod embeddings;
 
@@ -1,24 +1,24 @@
-use std::{cmp::Ordering, path::PathBuf};
+use std::{cmp::Ordering, collections::{HashMap, HashSet}, path::{Path, PathBuf}};
 
 use anyhow::{anyhow, Context, Result};
 use rayon::prelude::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::config::AppState;
-use crate::rag::embeddings::embed_query;
-use crate::utils::{dot, l2_norm, normalize_for_match};
+use crate::rag::ann::HnswIndex;
+use crate::rag::embeddings::{chat_completion, embed_query_with_model};
+use crate::utils::{dot, l2_norm, normalize_for_match, read_sidecar, tokenize, write_sidecar, SourceStamp};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RagDb {
     #[allow(dead_code)]
     pub schema_version: Option<u32>,
-    #[allow(dead_code)]
     pub embedding_model: Option<String>,
     pub items: Vec<RagItem>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct RagItem {
     #[allow(dead_code)]
@@ -36,7 +36,36 @@ pub struct RagItem {
     pub embedding: Option<Vec<f32>>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[cfg(test)]
+impl RagItem {
+    /// Minimal item for unit tests: a 60 second segment without topic or embedding
+    pub fn test_item(episode_number: u32, start_sec: f64) -> Self {
+        Self {
+            id: 0,
+            episode_number,
+            episode_title: None,
+            topic: None,
+            subject: None,
+            start_sec,
+            end_sec: start_sec + 60.0,
+            start_hms: None,
+            end_hms: None,
+            summary: None,
+            text: None,
+            embedding: None,
+        }
+    }
+}
+
+#[cfg(test)]
+impl RagIndex {
+    /// Index over `items` for unit tests, built like a loaded database
+    pub fn test_index(items: Vec<RagItem>) -> Self {
+        Self::from_db(RagDb { schema_version: None, embedding_model: None, items }, false)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct RagSubject {
     pub coarse: Option<String>,
@@ -48,36 +77,116 @@ pub struct RagIndex {
     pub items: Vec<RagItem>,
     // Precomputed norms for cosine similarity; 0.0 if missing.
     pub norms: Vec<f32>,
+    // Embeddings were scaled to unit length at load (`with_normalized_embeddings`); norms are all 1.0.
+    pub normalized: bool,
     // True when *all* items have embeddings.
     pub has_embeddings: bool,
+    // Dimension of the stored embeddings (taken from the first item that has one).
+    pub embedding_dim: Option<usize>,
+    // Model the index was built with, if the database records it.
+    pub embedding_model: Option<String>,
+    // Approximate nearest-neighbor graph; only built with RAG_ANN (see `with_ann`).
+    pub ann: Option<HnswIndex>,
+    // Term statistics for BM25 scoring in hybrid retrieval.
+    pub bm25: Bm25Index,
 }
 
 impl RagIndex {
-    pub fn load(path: &PathBuf) -> Result<Self> {
-        let bytes =
-            std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
-        Self::load_from_bytes(&bytes)
-            .with_context(|| format!("Failed to parse JSON {}", path.display()))
-    }
-
-    pub fn load_from_bytes(bytes: &[u8]) -> Result<Self> {
-        use serde_json::Deserializer;
-        use std::io::{BufReader, Cursor};
-
-        // Use streaming deserializer with a reader for better memory efficiency
-        // This allows the deserializer to read incrementally instead of loading everything
-        let reader = BufReader::new(Cursor::new(bytes));
-        let mut deserializer = Deserializer::from_reader(reader);
-        
-        // Deserialize the outer structure
-        // The Deserializer will read incrementally from the reader
-        let db: RagDb = serde::Deserialize::deserialize(&mut deserializer)
-            .with_context(|| "Failed to parse JSON")?;
+    /// Load a RAG database file, or every index listed by an `indices.json` manifest
+    pub fn load(path: &Path, dedup: bool) -> Result<Self> {
+        if path.ends_with("indices.json") {
+            Self::load_union(&IndexManifest::load(path)?, dedup)
+        } else {
+            Self::load_or_build_binary(path, dedup)
+        }
+    }
+
+    /// Load from a file path through a binary sidecar (`<name>.bin`, bincode) that skips parsing
+    /// the embedding arrays as JSON. The sidecar is (re)written when missing, stale or of an
+    /// older format; failing to write it only costs the speedup.
+    /// With `dedup`, duplicate segments are dropped (see `dedup_items`).
+    pub fn load_or_build_binary(path: &Path, dedup: bool) -> Result<Self> {
+        let bin_path = path.with_extension("bin");
+        let source = SourceStamp::of(path)?;
+        match read_sidecar(&bin_path, BINARY_DB_MAGIC, BINARY_DB_VERSION, source) {
+            Ok(Some(db)) => return Ok(Self::from_db(db, dedup)),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Ignoring unreadable {}: {:#}", bin_path.display(), e),
+        }
+
+        let db = read_db(path)?;
+        if let Err(e) = write_sidecar(&bin_path, BINARY_DB_MAGIC, BINARY_DB_VERSION, source, &db) {
+            tracing::warn!("Could not write {}: {:#}", bin_path.display(), e);
+        }
+        Ok(Self::from_db(db, dedup))
+    }
+
+    /// Load several index files (see `IndexManifest`) and search them as one.
+    /// Items sharing `(episode, start_sec)` are kept once; earlier paths win.
+    pub fn load_union(paths: &[PathBuf], dedup: bool) -> Result<Self> {
+        let mut items: Vec<RagItem> = Vec::new();
+        let mut dim: Option<usize> = None;
+        let mut model: Option<String> = None;
+        for path in paths {
+            let db = read_db(path)?;
+            match (&model, db.embedding_model) {
+                (Some(expected), Some(m)) if *expected != m => {
+                    tracing::warn!(
+                        "Embedding model of {} ({}) differs from previous indices ({})",
+                        path.display(),
+                        m,
+                        expected
+                    );
+                }
+                (None, m) => model = m,
+                _ => {}
+            }
+            if let Some(d) = db.items.iter().find_map(|it| it.embedding.as_ref().map(|v| v.len())) {
+                match dim {
+                    Some(expected) if expected != d => {
+                        return Err(anyhow!(
+                            "Embedding dimension of {} ({}) differs from previous indices ({})",
+                            path.display(),
+                            d,
+                            expected
+                        ));
+                    }
+                    _ => dim = Some(d),
+                }
+            }
+            items.extend(db.items);
+        }
+
+        let mut seen: HashSet<(u32, u64)> = HashSet::new();
+        let before = items.len();
+        items.retain(|it| seen.insert((it.episode_number, it.start_sec.to_bits())));
+        if before > items.len() {
+            tracing::info!("Merged {} indices, dropped {} overlapping items", paths.len(), before - items.len());
+        }
+
+        Ok(Self::from_db(
+            RagDb {
+                schema_version: None,
+                embedding_model: model,
+                items,
+            },
+            dedup,
+        ))
+    }
+
+    fn from_db(db: RagDb, dedup: bool) -> Self {
+        let mut items = db.items;
+        if dedup {
+            let removed = dedup_items(&mut items);
+            if removed > 0 {
+                tracing::info!("Dropped {} duplicate RAG items", removed);
+            }
+        }
 
         // Calculate norms while processing (reduces memory pressure)
-        let mut norms = Vec::with_capacity(db.items.len());
+        let mut norms = Vec::with_capacity(items.len());
         let mut has_embeddings = true;
-        for it in &db.items {
+        for it in &items {
             if let Some(v) = &it.embedding {
                 norms.push(l2_norm(v));
             } else {
@@ -86,93 +195,420 @@ impl RagIndex {
             }
         }
 
-        Ok(Self {
-            items: db.items,
+        let embedding_dim = items.iter().find_map(|it| it.embedding.as_ref().map(|v| v.len()));
+        let bm25 = Bm25Index::build(&items);
+
+        Self {
+            items,
             norms,
             has_embeddings,
-        })
+            embedding_dim,
+            embedding_model: db.embedding_model,
+            normalized: false,
+            ann: None,
+            bm25,
+        }
     }
-    
-    /// Load from a file path using streaming deserialization
-    /// This is more memory-efficient for large files as it reads incrementally
-    pub fn load_from_path(path: &PathBuf) -> Result<Self> {
-        use serde_json::Deserializer;
-        use std::fs::File;
-        use std::io::BufReader;
-
-        // Open file and create a buffered reader for streaming
-        let file = File::open(path)
-            .with_context(|| format!("Failed to open {}", path.display()))?;
-        let reader = BufReader::new(file);
-        let mut deserializer = Deserializer::from_reader(reader);
-        
-        // Deserialize incrementally - the reader will fetch data as needed
-        let db: RagDb = serde::Deserialize::deserialize(&mut deserializer)
-            .with_context(|| format!("Failed to parse JSON {}", path.display()))?;
 
-        // Calculate norms while processing
-        let mut norms = Vec::with_capacity(db.items.len());
-        let mut has_embeddings = true;
-        for it in &db.items {
-            if let Some(v) = &it.embedding {
-                norms.push(l2_norm(v));
-            } else {
-                norms.push(0.0);
-                has_embeddings = false;
+    /// Scale every embedding to unit length so cosine scoring is a plain dot product (RAG_NORMALIZE_ON_LOAD).
+    /// Items without a usable embedding keep norm 0.0 and stay out of vector search.
+    pub fn with_normalized_embeddings(mut self) -> Self {
+        for (item, norm) in self.items.iter_mut().zip(self.norms.iter_mut()) {
+            let Some(v) = item.embedding.as_mut().filter(|_| *norm > 0.0) else {
+                continue;
+            };
+            let inv = 1.0 / *norm;
+            v.iter_mut().for_each(|x| *x *= inv);
+            *norm = 1.0;
+        }
+        self.normalized = true;
+        self
+    }
+
+    /// Build the HNSW graph so searches skip the exact scan. Indices without embeddings
+    /// are returned unchanged.
+    pub fn with_ann(mut self) -> Self {
+        let Some(dim) = self.embedding_dim.filter(|_| self.has_embeddings) else {
+            return self;
+        };
+        let started = std::time::Instant::now();
+        self.ann = Some(HnswIndex::build(&self.items, &self.norms, dim));
+        tracing::info!("Built ANN index over {} items in {:?}", self.items.len(), started.elapsed());
+        self
+    }
+
+    /// Approximate top-`k` (item index, cosine similarity) if the index has an ANN graph
+    pub fn ann_search(&self, q: &[f32], qn: f32, k: usize) -> Option<Vec<(usize, f32)>> {
+        let ann = self.ann.as_ref()?;
+        Some(ann.search(q, qn, k))
+    }
+}
+
+/// Text used for keyword matching: the transcript text, else the summary
+fn lexical_text(item: &RagItem) -> &str {
+    item.text.as_deref().or(item.summary.as_deref()).unwrap_or_default()
+}
+
+/// BM25 term saturation
+const BM25_K1: f32 = 1.2;
+/// BM25 document length normalization
+const BM25_B: f32 = 0.75;
+
+/// Inverted index over `lexical_text` for BM25 scoring
+#[derive(Debug, Clone, Default)]
+pub struct Bm25Index {
+    // term -> (item index, term frequency)
+    postings: HashMap<String, Vec<(u32, u32)>>,
+    // Token count per item
+    doc_lens: Vec<u32>,
+    avg_len: f32,
+}
+
+impl Bm25Index {
+    pub fn build(items: &[RagItem]) -> Self {
+        let mut postings: HashMap<String, Vec<(u32, u32)>> = HashMap::new();
+        let mut doc_lens = Vec::with_capacity(items.len());
+        for (i, it) in items.iter().enumerate() {
+            let tokens = tokenize(lexical_text(it));
+            doc_lens.push(tokens.len() as u32);
+            let mut tf: HashMap<String, u32> = HashMap::new();
+            for t in tokens {
+                *tf.entry(t).or_default() += 1;
+            }
+            for (t, n) in tf {
+                postings.entry(t).or_default().push((i as u32, n));
             }
         }
+        let total: u64 = doc_lens.iter().map(|&l| l as u64).sum();
+        let avg_len = if doc_lens.is_empty() { 0.0 } else { total as f32 / doc_lens.len() as f32 };
+        Self { postings, doc_lens, avg_len }
+    }
 
-        Ok(Self {
-            items: db.items,
-            norms,
-            has_embeddings,
-        })
+    /// BM25 score of every item containing at least one query term
+    pub fn scores(&self, query: &str) -> HashMap<usize, f32> {
+        let n = self.doc_lens.len() as f32;
+        let terms: HashSet<String> = tokenize(query).into_iter().collect();
+        let mut scores: HashMap<usize, f32> = HashMap::new();
+        for term in &terms {
+            let Some(docs) = self.postings.get(term) else {
+                continue;
+            };
+            let df = docs.len() as f32;
+            let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+            for &(doc, tf) in docs {
+                let tf = tf as f32;
+                let len_norm = 1.0 - BM25_B + BM25_B * self.doc_lens[doc as usize] as f32 / self.avg_len.max(1.0);
+                *scores.entry(doc as usize).or_default() += idf * tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * len_norm);
+            }
+        }
+        scores
     }
 }
 
+/// Streaming read of a RAG database file (plain or gzip-compressed)
+fn read_db(path: &Path) -> Result<RagDb> {
+    use serde_json::Deserializer;
+
+    let reader = crate::gzip::open_maybe_gzip(path)?;
+    let mut deserializer = Deserializer::from_reader(reader);
+
+    // Deserialize incrementally - the reader will fetch data as needed
+    serde::Deserialize::deserialize(&mut deserializer)
+        .with_context(|| format!("Failed to parse JSON {}", path.display()))
+}
+
+/// Layout of the binary sidecar; bump when the format or `RagItem` changes
+const BINARY_DB_VERSION: u8 = 2;
+const BINARY_DB_MAGIC: [u8; 4] = *b"RAGB";
+
+/// `db/<podcast>/indices.json`: index files searched together for one podcast.
+/// Relative paths are resolved against the manifest's directory.
+#[derive(Debug, Deserialize)]
+pub struct IndexManifest {
+    pub indices: Vec<PathBuf>,
+}
+
+impl IndexManifest {
+    pub fn load(path: &Path) -> Result<Vec<PathBuf>> {
+        let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let manifest: IndexManifest = serde_json::from_slice(&bytes)
+            .with_context(|| format!("Failed to parse JSON {}", path.display()))?;
+        if manifest.indices.is_empty() {
+            return Err(anyhow!("{} lists no indices", path.display()));
+        }
+        let base = path.parent().unwrap_or_else(|| Path::new("."));
+        Ok(manifest.indices.into_iter().map(|p| base.join(p)).collect())
+    }
+}
+
+/// `db/<podcast>/episode-title-embeddings.json`: one embedding per episode title, tagged with
+/// the embedding model so switching models re-embeds every title.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TitleEmbeddings {
+    pub embedding_model: String,
+    pub titles: Vec<TitleEmbedding>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TitleEmbedding {
+    pub episode_number: u32,
+    pub title: String,
+    pub embedding: Vec<f32>,
+}
+
+impl TitleEmbeddings {
+    /// Distinct (episode, title) pairs of an index, ordered by episode
+    pub fn episode_titles(items: &[RagItem]) -> Vec<(u32, String)> {
+        let mut seen: HashSet<u32> = HashSet::new();
+        let mut titles: Vec<(u32, String)> = items
+            .iter()
+            .filter_map(|it| {
+                let title = it.episode_title.as_deref()?.trim();
+                (!title.is_empty() && seen.insert(it.episode_number))
+                    .then(|| (it.episode_number, title.to_string()))
+            })
+            .collect();
+        titles.sort_by_key(|(ep, _)| *ep);
+        titles
+    }
+
+    /// Keep only entries for `model` whose title is still in `wanted`; returns the titles
+    /// that still need an embedding.
+    pub fn retain_current(&mut self, model: &str, wanted: &[(u32, String)]) -> Vec<(u32, String)> {
+        if self.embedding_model != model {
+            self.embedding_model = model.to_string();
+            self.titles.clear();
+        }
+        let wanted_set: HashSet<(u32, &str)> = wanted.iter().map(|(ep, t)| (*ep, t.as_str())).collect();
+        self.titles.retain(|t| wanted_set.contains(&(t.episode_number, t.title.as_str())));
+        let have: HashSet<u32> = self.titles.iter().map(|t| t.episode_number).collect();
+        wanted.iter().filter(|(ep, _)| !have.contains(ep)).cloned().collect()
+    }
+
+    /// Cosine similarity of each episode title to the query; titles embedded with a
+    /// different dimension are skipped.
+    pub fn scores(&self, q: &[f32], qn: f32) -> HashMap<u32, f32> {
+        self.titles
+            .iter()
+            .filter(|t| t.embedding.len() == q.len())
+            .filter_map(|t| {
+                let tn = l2_norm(&t.embedding);
+                if tn <= 0.0 || qn <= 0.0 {
+                    return None;
+                }
+                let s = dot(q, &t.embedding) / (qn * tn);
+                s.is_finite().then_some((t.episode_number, s))
+            })
+            .collect()
+    }
+}
+
+/// Drop items whose (episode, start, end) window or embedding was already seen, keeping the first.
+/// Returns the number of removed items.
+fn dedup_items(items: &mut Vec<RagItem>) -> usize {
+    let mut seen_windows: HashSet<(u32, u64, u64)> = HashSet::new();
+    let mut seen_embeddings: HashSet<Vec<u32>> = HashSet::new();
+    let before = items.len();
+    items.retain(|it| {
+        let window = (it.episode_number, it.start_sec.to_bits(), it.end_sec.to_bits());
+        let new_window = seen_windows.insert(window);
+        let new_embedding = match &it.embedding {
+            Some(v) => seen_embeddings.insert(v.iter().map(|x| x.to_bits()).collect()),
+            None => true,
+        };
+        new_window && new_embedding
+    });
+    before - items.len()
+}
+
 #[derive(Clone)]
 pub struct Hit {
     pub item: RagItem,
     pub score: f32,
 }
 
-pub async fn retrieve(st: &AppState, rag: &RagIndex, query: &str, top_k: usize) -> Result<Vec<Hit>> {
+/// Greedy Maximal Marginal Relevance over `candidates` (item index, query similarity):
+/// each step picks the candidate maximizing `lambda * sim(query) - (1 - lambda) * max sim(selected)`,
+/// using the stored item embeddings. Returns up to `k` picks with their query similarity.
+fn mmr_select(rag: &RagIndex, candidates: &[(usize, f32)], lambda: f32, k: usize) -> Vec<(usize, f32)> {
+    let cosine = |a: usize, b: usize| -> f32 {
+        let (Some(va), Some(vb)) = (rag.items[a].embedding.as_ref(), rag.items[b].embedding.as_ref()) else {
+            return 0.0;
+        };
+        let n = rag.norms[a] * rag.norms[b];
+        if n <= 0.0 {
+            0.0
+        } else {
+            dot(va, vb) / n
+        }
+    };
+
+    let mut remaining: Vec<(usize, f32)> = candidates.to_vec();
+    // Highest similarity to any selected item, per remaining candidate
+    let mut max_sim: Vec<f32> = vec![f32::NEG_INFINITY; remaining.len()];
+    let mut selected: Vec<(usize, f32)> = Vec::with_capacity(k.min(remaining.len()));
+    while selected.len() < k && !remaining.is_empty() {
+        let (best, _) = remaining
+            .iter()
+            .zip(&max_sim)
+            .map(|(&(_, score), &redundancy)| {
+                let redundancy = if redundancy.is_finite() { redundancy } else { 0.0 };
+                lambda * score - (1.0 - lambda) * redundancy
+            })
+            .enumerate()
+            .fold((0, f32::NEG_INFINITY), |acc, (pos, mmr)| if mmr > acc.1 { (pos, mmr) } else { acc });
+        let pick = remaining.swap_remove(best);
+        max_sim.swap_remove(best);
+        for (cand, sim) in remaining.iter().zip(max_sim.iter_mut()) {
+            *sim = sim.max(cosine(pick.0, cand.0));
+        }
+        selected.push(pick);
+    }
+    selected
+}
+
+/// MMR candidates per requested hit: reranking picks from the best `top_k * MMR_POOL_FACTOR`
+const MMR_POOL_FACTOR: usize = 4;
+
+/// Hybrid candidates per kept hit, taken from both the cosine and the BM25 ranking
+const HYBRID_POOL_FACTOR: usize = 4;
+
+/// Cosine similarity of item `i` to the query, if it has a usable embedding
+pub(crate) fn item_cosine(rag: &RagIndex, i: usize, q: &[f32], qn: f32) -> Option<f32> {
+    let v = rag.items[i].embedding.as_ref()?;
+    let dn = rag.norms[i];
+    if dn <= 0.0 {
+        return None;
+    }
+    // Unit-length items leave only the query norm to divide by
+    let s = if rag.normalized { dot(q, v) / qn } else { dot(q, v) / (qn * dn) };
+    s.is_finite().then_some(s)
+}
+
+/// Rescore the union of the cosine candidates `dense` and the best `pool` BM25 matches as
+/// `alpha * cosine + (1 - alpha) * bm25`, both min-max/max normalized over the candidates.
+/// Best first.
+fn hybrid_rescore(
+    rag: &RagIndex,
+    dense: &[(usize, f32)],
+    lexical: &HashMap<usize, f32>,
+    q: &[f32],
+    qn: f32,
+    alpha: f32,
+    pool: usize,
+) -> Vec<(usize, f32)> {
+    let mut lexical_top: Vec<(usize, f32)> = lexical.iter().map(|(&i, &s)| (i, s)).collect();
+    lexical_top.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal).then(a.0.cmp(&b.0)));
+    lexical_top.truncate(pool);
+
+    let mut candidates: HashMap<usize, f32> = dense.iter().copied().collect();
+    for (i, _) in lexical_top {
+        if let Some(s) = item_cosine(rag, i, q, qn) {
+            candidates.entry(i).or_insert(s);
+        }
+    }
+
+    let min_cos = candidates.values().copied().fold(f32::INFINITY, f32::min);
+    let max_cos = candidates.values().copied().fold(f32::NEG_INFINITY, f32::max);
+    let max_bm25 = candidates
+        .keys()
+        .filter_map(|i| lexical.get(i))
+        .copied()
+        .fold(0.0f32, f32::max);
+    let mut scored: Vec<(usize, f32)> = candidates
+        .into_iter()
+        .map(|(i, cos)| {
+            let cos = if max_cos > min_cos { (cos - min_cos) / (max_cos - min_cos) } else { 1.0 };
+            let bm25 = if max_bm25 > 0.0 { lexical.get(&i).copied().unwrap_or(0.0) / max_bm25 } else { 0.0 };
+            (i, alpha * cos + (1.0 - alpha) * bm25)
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal).then(a.0.cmp(&b.0)));
+    scored
+}
+
+/// `use_embed_cache: false` forces a fresh query embedding (see `embed_query_with_model`).
+/// Indices with an ANN graph (`RAG_ANN`) are searched approximately instead of scanned.
+/// With `hybrid_alpha`, cosine and BM25 scores are blended (see `hybrid_rescore`) and hit
+/// scores are the blended values.
+/// With `mmr_lambda`, the top-K is chosen by Maximal Marginal Relevance (see `mmr_select`).
+/// The keyword fallback for indices without embeddings ignores both.
+pub async fn retrieve(
+    st: &AppState,
+    rag: &RagIndex,
+    query: &str,
+    top_k: usize,
+    use_embed_cache: bool,
+    mmr_lambda: Option<f32>,
+    hybrid_alpha: Option<f32>,
+) -> Result<Vec<Hit>> {
+    let started = std::time::Instant::now();
+    let hits = search(st, rag, query, top_k, use_embed_cache, mmr_lambda, hybrid_alpha).await;
+    st.metrics.observe_retrieve(started.elapsed());
+    hits
+}
+
+async fn search(
+    st: &AppState,
+    rag: &RagIndex,
+    query: &str,
+    top_k: usize,
+    use_embed_cache: bool,
+    mmr_lambda: Option<f32>,
+    hybrid_alpha: Option<f32>,
+) -> Result<Vec<Hit>> {
     if rag.has_embeddings {
-        let q = embed_query(st, query).await?;
+        // Cosine scores only mean something against a query from the index's own model
+        let model = rag.embedding_model.as_deref().unwrap_or(&st.cfg.embedding_model);
+        let q = embed_query_with_model(st, model, query, rag.embedding_dim, use_embed_cache).await?;
         let qn = l2_norm(&q);
         if qn <= 0.0 {
             return Err(anyhow!("Query embedding norm is 0"));
         }
 
-        // Parallel computation of scores
-        let mut scored: Vec<(usize, f32)> = rag.items
-            .par_iter()
-            .enumerate()
-            .filter_map(|(i, it)| {
-                let v = it.embedding.as_ref()?;
-                let dn = rag.norms[i];
-                if dn <= 0.0 {
-                    return None;
-                }
-                let s = dot(&q, v) / (qn * dn);
-                if s.is_finite() {
-                    Some((i, s))
-                } else {
-                    None
-                }
-            })
-            .collect();
+        // Hits to keep: the top-K, or the MMR candidate pool
+        let keep = match mmr_lambda {
+            Some(_) => top_k.saturating_mul(MMR_POOL_FACTOR),
+            None => top_k,
+        };
+
+        // Hybrid mode blends a larger cosine pool with the best lexical matches
+        let pool = match hybrid_alpha {
+            Some(_) => keep.saturating_mul(HYBRID_POOL_FACTOR),
+            None => keep,
+        };
+
+        // Approximate search when the index has an ANN graph, else a parallel exact scan
+        let mut scored: Vec<(usize, f32)> = if let Some(approx) = rag.ann_search(&q, qn, pool) {
+            approx
+        } else {
+            (0..rag.items.len())
+                .into_par_iter()
+                .filter_map(|i| item_cosine(rag, i, &q, qn).map(|s| (i, s)))
+                .collect()
+        };
 
         // Use partial sort for better performance when we only need top-K
-        if scored.len() > top_k {
-            let (top_part, _, _) = scored.select_nth_unstable_by(top_k - 1, |a, b| {
+        if scored.len() > pool {
+            // Everything up to and including index pool - 1 is the top-K
+            scored.select_nth_unstable_by(pool - 1, |a, b| {
                 b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal)
             });
-            top_part.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
-            scored = top_part.to_vec();
+            scored.truncate(pool);
+            scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
         } else {
             scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
         }
+        if let Some(alpha) = hybrid_alpha {
+            let lexical = rag.bm25.scores(query);
+            scored = hybrid_rescore(rag, &scored, &lexical, &q, qn, alpha, pool);
+            scored.truncate(keep);
+        }
+        if let Some(lambda) = mmr_lambda {
+            scored = mmr_select(rag, &scored, lambda, top_k);
+        }
         Ok(scored
             .into_iter()
             .map(|(i, score)| Hit {
@@ -183,22 +619,17 @@ pub async fn retrieve(st: &AppState, rag: &RagIndex, query: &str, top_k: usize)
     } else {
         // Fallback if DB was built with --no-embeddings.
         let q = normalize_for_match(query);
-        let q_tokens: Vec<&str> = q.split_whitespace().collect();
+        let q_tokens = tokenize(query);
 
         let mut scored: Vec<(usize, f32)> = Vec::with_capacity(rag.items.len());
         for (i, it) in rag.items.iter().enumerate() {
-            let hay = normalize_for_match(
-                it.text
-                    .as_deref()
-                    .or(it.summary.as_deref())
-                    .unwrap_or_default(),
-            );
+            let hay = normalize_for_match(lexical_text(it));
             if hay.is_empty() {
                 continue;
             }
             let mut score = 0.0f32;
             for t in &q_tokens {
-                if hay.contains(t) {
+                if hay.contains(t.as_str()) {
                     score += 1.0;
                 }
             }
@@ -223,3 +654,349 @@ pub async fn retrieve(st: &AppState, rag: &RagIndex, query: &str, top_k: usize)
     }
 }
 
+/// Snippet length per candidate in the rerank prompt (chars)
+const RERANK_SNIPPET_CHARS: usize = 600;
+
+/// Order of `items` by LLM-judged relevance (0-10) to `query`, asked in a single call. Ties keep
+/// the given (vector) order; a failed call or unparseable reply returns the given order.
+pub async fn llm_rerank_order(st: &AppState, query: &str, items: &[&RagItem]) -> Vec<usize> {
+    let identity: Vec<usize> = (0..items.len()).collect();
+    if items.len() < 2 {
+        return identity;
+    }
+    let snippets: String = items
+        .iter()
+        .enumerate()
+        .map(|(i, it)| {
+            let text: String = lexical_text(it).chars().take(RERANK_SNIPPET_CHARS).collect();
+            format!("{}. {}\n", i + 1, text.replace('\n', " "))
+        })
+        .collect();
+    let system = "You rate how relevant each numbered transcript snippet is to the user's question, \
+        from 0 (unrelated) to 10 (answers it directly). Reply ONLY with a JSON array containing one \
+        score per snippet in snippet order, e.g. [7, 2, 10].";
+    let user_prompt = format!("QUESTION: {query}\n\nSNIPPETS:\n{snippets}");
+
+    let reply = match chat_completion(st, system, &[], &user_prompt, 0.0, None).await {
+        Ok(reply) => reply,
+        Err(e) => {
+            tracing::warn!("Rerank failed, keeping vector order: {:#}", e);
+            return identity;
+        }
+    };
+    let Some(scores) = parse_rerank_scores(&reply, items.len()) else {
+        tracing::warn!("Unparseable rerank reply, keeping vector order: {}", reply);
+        return identity;
+    };
+    let mut order = identity;
+    // Stable: equal scores stay in vector order
+    order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+    order
+}
+
+/// Exactly `n` finite scores from the first JSON array in `reply`, clamped to 0-10
+fn parse_rerank_scores(reply: &str, n: usize) -> Option<Vec<f32>> {
+    let start = reply.find('[')?;
+    let end = start + reply[start..].find(']')?;
+    let scores: Vec<f32> = serde_json::from_str(&reply[start..=end]).ok()?;
+    (scores.len() == n && scores.iter().all(|s| s.is_finite()))
+        .then(|| scores.into_iter().map(|s| s.clamp(0.0, 10.0)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AppConfig, AppState};
+    use crate::rag::embeddings::tests::{mock_chat_server, mock_embeddings_server};
+
+    fn item(episode_number: u32, start_sec: f64, embedding: Vec<f32>) -> RagItem {
+        RagItem {
+            embedding: Some(embedding),
+            ..RagItem::test_item(episode_number, start_sec)
+        }
+    }
+
+    fn db_with_duplicates() -> RagDb {
+        RagDb {
+            schema_version: None,
+            embedding_model: None,
+            items: vec![
+                item(1, 0.0, vec![1.0, 0.0]),
+                // Same window as the first item
+                item(1, 0.0, vec![0.9, 0.1]),
+                // Same embedding as the first item
+                item(2, 60.0, vec![1.0, 0.0]),
+                item(3, 0.0, vec![0.0, 1.0]),
+            ],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dedup_items_at_load() {
+        assert_eq!(RagIndex::from_db(db_with_duplicates(), false).items.len(), 4);
+
+        let rag = RagIndex::from_db(db_with_duplicates(), true);
+        assert_eq!(rag.items.len(), 2);
+        assert_eq!(rag.norms.len(), 2);
+
+        let mut cfg = AppConfig::for_tests();
+        cfg.embedding_base_url = mock_embeddings_server(vec![1.0, 0.0]).await;
+        let st = AppState::for_tests(cfg);
+
+        let hits = retrieve(&st, &rag, "apple", 4, true, None, None).await.unwrap();
+        let windows: HashSet<(u32, u64)> = hits
+            .iter()
+            .map(|h| (h.item.episode_number, h.item.start_sec.to_bits()))
+            .collect();
+        assert_eq!(windows.len(), hits.len());
+        assert_eq!(hits[0].item.episode_number, 1);
+    }
+
+    fn write_index(name: &str, items: serde_json::Value) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rag-union-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, serde_json::json!({ "items": items }).to_string()).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_binary_sidecar_roundtrip_and_regeneration() {
+        let path = write_index(
+            "rag-embeddings.json",
+            serde_json::json!([
+                { "id": 1, "episodeNumber": 10, "topic": "Apple", "startSec": 0.0, "endSec": 60.0, "embedding": [1.0, 0.5] },
+                { "id": 2, "episodeNumber": 11, "startSec": 0.0, "endSec": 60.0 },
+            ]),
+        );
+        let bin_path = path.with_extension("bin");
+        let _ = std::fs::remove_file(&bin_path);
+
+        let built = RagIndex::load_or_build_binary(&path, false).unwrap();
+        assert!(bin_path.exists());
+        let source = SourceStamp::of(&path).unwrap();
+        let db: RagDb = read_sidecar(&bin_path, BINARY_DB_MAGIC, BINARY_DB_VERSION, source).unwrap().unwrap();
+        assert_eq!(db.items.len(), 2);
+        assert_eq!(db.items[0].embedding, Some(vec![1.0, 0.5]));
+        assert_eq!(db.items[0].topic.as_deref(), Some("Apple"));
+        assert!(db.items[1].embedding.is_none());
+        let loaded = RagIndex::load_or_build_binary(&path, false).unwrap();
+        assert_eq!(loaded.norms, built.norms);
+        assert!(!loaded.has_embeddings);
+
+        // An older format version is ignored and rewritten
+        let mut bytes = std::fs::read(&bin_path).unwrap();
+        bytes[4] = BINARY_DB_VERSION.wrapping_sub(1);
+        std::fs::write(&bin_path, &bytes).unwrap();
+        assert!(read_sidecar::<RagDb>(&bin_path, BINARY_DB_MAGIC, BINARY_DB_VERSION, source).unwrap().is_none());
+        RagIndex::load_or_build_binary(&path, false).unwrap();
+        assert_eq!(std::fs::read(&bin_path).unwrap()[4], BINARY_DB_VERSION);
+
+        // A changed source file wins over the sidecar
+        write_index(
+            "rag-embeddings.json",
+            serde_json::json!([{ "id": 1, "episodeNumber": 12, "startSec": 0.0, "endSec": 60.0, "embedding": [0.0, 1.0] }]),
+        );
+        let reloaded = RagIndex::load_or_build_binary(&path, false).unwrap();
+        assert_eq!(reloaded.items.len(), 1);
+        assert_eq!(reloaded.items[0].episode_number, 12);
+    }
+
+    #[test]
+    fn test_load_gzip_compressed_index() {
+        let json = serde_json::json!({ "items": [
+            { "id": 1, "episodeNumber": 10, "startSec": 0.0, "endSec": 60.0, "embedding": [1.0, 0.0] },
+        ] });
+        let path = write_index("gz-index.json", serde_json::json!([]));
+        let gz = crate::gzip::gz_path(&path);
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, json.to_string().as_bytes()).unwrap();
+        std::fs::write(&gz, encoder.finish().unwrap()).unwrap();
+        let index = RagIndex::load_or_build_binary(&gz, false).unwrap();
+        assert_eq!(index.items.len(), 1);
+        assert_eq!(index.items[0].episode_number, 10);
+    }
+
+    #[tokio::test]
+    async fn test_union_of_indices_returns_hits_from_both() {
+        let legacy = write_index(
+            "legacy.json",
+            serde_json::json!([
+                { "id": 1, "episodeNumber": 10, "startSec": 0.0, "endSec": 60.0, "embedding": [1.0, 0.0] },
+                { "id": 2, "episodeNumber": 11, "startSec": 0.0, "endSec": 60.0, "embedding": [0.0, 1.0] },
+            ]),
+        );
+        write_index(
+            "current.json",
+            serde_json::json!([
+                // Overlaps with the legacy index and must only appear once
+                { "id": 1, "episodeNumber": 10, "startSec": 0.0, "endSec": 60.0, "embedding": [1.0, 0.0] },
+                { "id": 2, "episodeNumber": 300, "startSec": 120.0, "endSec": 180.0, "embedding": [0.9, 0.1] },
+            ]),
+        );
+        let manifest = legacy.parent().unwrap().join("indices.json");
+        std::fs::write(&manifest, r#"{ "indices": ["current.json", "legacy.json"] }"#).unwrap();
+
+        let rag = RagIndex::load(&manifest, false).unwrap();
+        assert_eq!(rag.items.len(), 3);
+
+        let mut cfg = AppConfig::for_tests();
+        cfg.embedding_base_url = mock_embeddings_server(vec![1.0, 0.0]).await;
+        let st = AppState::for_tests(cfg);
+
+        let hits = retrieve(&st, &rag, "apple", 2, true, None, None).await.unwrap();
+        let episodes: Vec<u32> = hits.iter().map(|h| h.item.episode_number).collect();
+        assert_eq!(episodes, vec![10, 300]);
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_surfaces_exact_term_match() {
+        let text = |mut it: RagItem, text: &str| {
+            it.text = Some(text.to_string());
+            it
+        };
+        let rag = RagIndex::from_db(
+            RagDb {
+                schema_version: None,
+                embedding_model: None,
+                items: vec![
+                    text(item(1, 0.0, vec![1.0, 0.0]), "Neue Laptops von Apple"),
+                    text(item(2, 0.0, vec![0.8, 0.6]), "Der M2 Chip im Test, M2 überall"),
+                    text(item(3, 0.0, vec![0.0, 1.0]), "Kaffee und Espresso"),
+                ],
+            },
+            false,
+        );
+        let mut cfg = AppConfig::for_tests();
+        cfg.embedding_base_url = mock_embeddings_server(vec![1.0, 0.0]).await;
+        let st = AppState::for_tests(cfg);
+
+        let plain = retrieve(&st, &rag, "M2 Laptop", 1, true, None, None).await.unwrap();
+        assert_eq!(plain[0].item.episode_number, 1);
+
+        let hybrid = retrieve(&st, &rag, "M2 Laptop", 2, true, None, Some(0.5)).await.unwrap();
+        let episodes: Vec<u32> = hybrid.iter().map(|h| h.item.episode_number).collect();
+        assert_eq!(episodes, vec![2, 1]);
+        assert!(hybrid.iter().all(|h| (0.0..=1.0).contains(&h.score)));
+
+        // alpha = 1 is the cosine ranking again
+        let cosine = retrieve(&st, &rag, "M2 Laptop", 1, true, None, Some(1.0)).await.unwrap();
+        assert_eq!(cosine[0].item.episode_number, 1);
+    }
+
+    #[test]
+    fn test_normalized_embeddings_keep_cosine_scores() {
+        let mut no_embedding = item(3, 0.0, vec![]);
+        no_embedding.embedding = None;
+        no_embedding.text = Some("Kaffee".to_string());
+        let build = || {
+            RagIndex::from_db(
+                RagDb {
+                    schema_version: None,
+                    embedding_model: None,
+                    items: vec![item(1, 0.0, vec![3.0, 4.0]), item(2, 0.0, vec![0.0, 0.0]), no_embedding.clone()],
+                },
+                false,
+            )
+        };
+        let raw = build();
+        let normalized = build().with_normalized_embeddings();
+
+        assert!(normalized.normalized && !raw.normalized);
+        assert_eq!(normalized.norms, vec![1.0, 0.0, 0.0]);
+        assert_eq!(normalized.items[0].embedding.as_deref(), Some(&[0.6, 0.8][..]));
+
+        let q = [2.0, 1.0];
+        let qn = l2_norm(&q);
+        for i in 0..3 {
+            let (a, b) = (item_cosine(&raw, i, &q, qn), item_cosine(&normalized, i, &q, qn));
+            assert_eq!(a.is_some(), b.is_some());
+            if let (Some(a), Some(b)) = (a, b) {
+                assert!((a - b).abs() < 1e-6, "{a} vs {b}");
+            }
+        }
+        // Lexical scoring doesn't look at embeddings
+        assert_eq!(raw.bm25.scores("kaffee"), normalized.bm25.scores("kaffee"));
+    }
+
+    #[test]
+    fn test_parse_rerank_scores_is_defensive() {
+        assert_eq!(parse_rerank_scores("Scores: [7, 2.5, 10]", 3), Some(vec![7.0, 2.5, 10.0]));
+        assert_eq!(parse_rerank_scores("[12, -1]", 2), Some(vec![10.0, 0.0]));
+        // Wrong count, no array, or non-numbers fall back to vector order
+        assert_eq!(parse_rerank_scores("[7, 2]", 3), None);
+        assert_eq!(parse_rerank_scores("sehr relevant", 1), None);
+        assert_eq!(parse_rerank_scores("[\"hoch\", 3]", 2), None);
+    }
+
+    #[tokio::test]
+    async fn test_llm_rerank_reorders_and_falls_back() {
+        let items = [RagItem::test_item(1, 0.0), RagItem::test_item(2, 0.0), RagItem::test_item(3, 0.0)];
+        let refs: Vec<&RagItem> = items.iter().collect();
+
+        let mut cfg = AppConfig::for_tests();
+        cfg.llm_base_url = mock_chat_server(Default::default(), "[3, 9, 3]").await;
+        let st = AppState::for_tests(cfg);
+        assert_eq!(llm_rerank_order(&st, "frage", &refs).await, vec![1, 0, 2]);
+
+        let mut cfg = AppConfig::for_tests();
+        cfg.llm_base_url = mock_chat_server(Default::default(), "keine Ahnung").await;
+        let st = AppState::for_tests(cfg);
+        assert_eq!(llm_rerank_order(&st, "frage", &refs).await, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_bm25_prefers_rarer_terms_and_shorter_docs() {
+        let doc = |text: &str| RagItem {
+            text: Some(text.to_string()),
+            ..RagItem::test_item(1, 0.0)
+        };
+        let bm25 = Bm25Index::build(&[
+            doc("apple apple banane"),
+            doc("apple kiwi"),
+            doc("apple kiwi kiwi kiwi kiwi kiwi kiwi kiwi"),
+            doc(""),
+        ]);
+        let scores = bm25.scores("Apple, Banane!");
+        // "banane" is rare, so its only document wins
+        assert!(scores[&0] > scores[&1]);
+        // Same term frequency, shorter document scores higher
+        assert!(scores[&1] > scores[&2]);
+        assert!(!scores.contains_key(&3));
+        assert!(bm25.scores("unbekannt").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mmr_skips_near_duplicate_windows() {
+        let rag = RagIndex::from_db(
+            RagDb {
+                schema_version: None,
+                embedding_model: None,
+                items: vec![
+                    // Two overlapping windows of the same segment
+                    item(1, 0.0, vec![1.0, 0.05]),
+                    item(1, 30.0, vec![1.0, 0.06]),
+                    item(2, 0.0, vec![0.6, -0.8]),
+                ],
+            },
+            false,
+        );
+        let mut cfg = AppConfig::for_tests();
+        cfg.embedding_base_url = mock_embeddings_server(vec![1.0, 0.0]).await;
+        let st = AppState::for_tests(cfg);
+
+        let plain = retrieve(&st, &rag, "apple", 2, true, None, None).await.unwrap();
+        assert!(plain.iter().all(|h| h.item.episode_number == 1));
+
+        let diverse = retrieve(&st, &rag, "apple", 2, true, Some(0.5), None).await.unwrap();
+        let picked: Vec<(u32, f64)> = diverse.iter().map(|h| (h.item.episode_number, h.item.start_sec)).collect();
+        assert_eq!(picked, vec![(1, 0.0), (2, 0.0)]);
+        // Scores stay the query similarity
+        assert!(diverse[0].score > diverse[1].score);
+
+        // lambda = 1 is plain relevance ranking; the top_k cap always holds
+        let relevance = retrieve(&st, &rag, "apple", 2, true, Some(1.0), None).await.unwrap();
+        assert_eq!(relevance.len(), 2);
+        assert!(relevance.iter().all(|h| h.item.episode_number == 1));
+    }
+}
@@ -1,55 +1,111 @@
 // Main entry point for RAG backend
 mod config;
 mod cache;
+mod gzip;
 mod handlers;
+mod logging;
 mod rag;
+#[cfg(test)]
+mod test_support;
 mod transcript;
 mod utils;
 
 use anyhow::{Context, Result};
 use axum::{
-    http::{header, HeaderName, HeaderValue, Method, StatusCode},
-    response::IntoResponse,
+    http::{header, HeaderName, HeaderValue, Method},
     routing::post,
     Router,
 };
 use moka::future::Cache;
 use reqwest::Client;
+use std::net::SocketAddr;
 use std::time::Duration;
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
 use tracing::info;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use config::{AppConfig, AppState};
-use handlers::{chat, episodes_latest, episodes_search, speakers_list};
-use handlers::analytics::{self, insert_test_data_endpoint, stats, track, track_episode_play};
+use handlers::{chat, chat_prompt, chat_stream, episode_transcript, episode_transcript_vtt, episodes_latest, episodes_search, episodes_similar, health, health_embeddings, health_ready, speakers_cooccurrence, speakers_list, speakers_talk_time, topic_cluster_episodes, topics_taxonomy};
+use handlers::health::warm_then_ready;
+use handlers::metrics::{metrics, track_requests};
+use handlers::analytics::{self, delete_user_data, insert_test_data_endpoint, stats, track, track_episode_play, track_event};
 use cache::load_rag_index_cached;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-async fn health() -> impl IntoResponse {
-    (StatusCode::OK, "ok")
-}
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::from_default_env().add_directive("info".parse()?))
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
-    let (cfg, settings_source) = AppConfig::from_env_and_settings()?;
-    info!("Settings source: {}", settings_source);
-    
+/// All API routes. Handlers apply auth themselves; probe paths are exempt via `cfg.auth_exempt_paths`.
+/// `/metrics` is only served here when `METRICS_BIND_ADDR` does not move it to its own listener.
+fn build_router(app_state: AppState) -> Router {
     let cors = CorsLayer::new()
         .allow_origin(HeaderValue::from_static("*"))
-        .allow_methods([Method::POST, Method::GET, Method::OPTIONS])
+        .allow_methods([Method::POST, Method::GET, Method::DELETE, Method::OPTIONS])
         .allow_headers([
             header::CONTENT_TYPE,
             header::AUTHORIZATION,
             HeaderName::from_static("x-auth-token"),
         ]);
 
+    let router = Router::new()
+        .route("/api/chat", post(chat))
+        .route("/api/chat/stream", post(chat_stream))
+        .route("/api/chat/prompt", post(chat_prompt))
+        .route("/api/episodes/search", post(episodes_search))
+        .route("/api/episodes/latest", post(episodes_latest))
+        .route("/api/episodes/:podcast_id/:episode_number/similar", axum::routing::get(episodes_similar))
+        .route("/api/episodes/:podcast_id/:episode_number/transcript", axum::routing::get(episode_transcript))
+        .route("/api/episodes/:podcast_id/:episode_number/transcript.vtt", axum::routing::get(episode_transcript_vtt))
+        .route("/api/speakers", axum::routing::get(speakers_list))
+        .route("/api/speakers/cooccurrence", axum::routing::get(speakers_cooccurrence))
+        .route("/api/speakers/talk-time/:episode_number", axum::routing::get(speakers_talk_time))
+        .route("/api/topics/taxonomy", axum::routing::get(topics_taxonomy))
+        .route("/api/topics/:cluster_id/episodes", axum::routing::get(topic_cluster_episodes))
+        .route("/api/analytics/track", post(track))
+        .route("/api/analytics/track-episode-play", post(track_episode_play))
+        .route("/api/analytics/track-event", post(track_event))
+        .route("/api/analytics/stats", axum::routing::get(stats))
+        .route("/api/analytics/stats/user/:fingerprint", axum::routing::delete(delete_user_data))
+        .route("/api/analytics/test-data", axum::routing::get(insert_test_data_endpoint))
+        .route("/api/health", axum::routing::get(health))
+        .route("/api/health/ready", axum::routing::get(health_ready))
+        .route("/api/health/embeddings", axum::routing::get(health_embeddings));
+    let router = if app_state.cfg.metrics_bind_addr.is_none() {
+        router.route("/metrics", axum::routing::get(metrics))
+    } else {
+        router
+    };
+
+    router
+        .route_layer(axum::middleware::from_fn_with_state(app_state.clone(), track_requests))
+        .layer(cors)
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(request_span)
+                .on_response(log_response),
+        )
+        .with_state(app_state)
+}
+
+/// `/metrics` alone, for the internal `METRICS_BIND_ADDR` listener
+fn metrics_router(app_state: AppState) -> Router {
+    Router::new()
+        .route("/metrics", axum::routing::get(metrics))
+        .with_state(app_state)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let registry = tracing_subscriber::registry()
+        .with(tracing_subscriber::EnvFilter::from_default_env().add_directive("info".parse()?));
+    if logging::json_requested(std::env::var("LOG_FORMAT").ok().as_deref()) {
+        registry.with(logging::json_layer()).init();
+    } else {
+        registry.with(tracing_subscriber::fmt::layer()).init();
+    }
+
+    let (cfg, settings_source) = AppConfig::from_env_and_settings()?;
+    info!("Settings source: {}", settings_source);
+    
     // Configure HTTP client with connection pooling
     let http = Client::builder()
         .timeout(std::time::Duration::from_secs(30))
@@ -117,6 +173,33 @@ async fn main() -> Result<()> {
         .time_to_idle(Duration::from_secs(1800))
         .build();
 
+    // Topic taxonomy cache: up to 20 podcasts, reloaded when the file's mtime changes
+    let taxonomy_cache = Cache::builder()
+        .max_capacity(20)
+        .build();
+
+    // Query embedding cache: up to 10000 queries, TTL from RAG_QUERY_CACHE_TTL_SECS (default 10 minutes)
+    let query_embedding_cache = Cache::builder()
+        .max_capacity(10_000)
+        .time_to_live(cfg.query_cache_ttl)
+        .build();
+
+    // Episode title embeddings: up to 20 podcasts, no TTL (sidecar is the source of truth)
+    let title_embedding_cache = Cache::builder()
+        .max_capacity(20)
+        .build();
+
+    // Embedding model mismatches found at index load: one entry per podcast
+    let embedding_model_mismatches = Cache::builder()
+        .max_capacity(100)
+        .build();
+
+    // LLM reachability for /api/health: probe at most every 30 seconds
+    let llm_health_cache = Cache::builder()
+        .max_capacity(1)
+        .time_to_live(Duration::from_secs(30))
+        .build();
+
     // Initialize analytics database
     let analytics_db_path = PathBuf::from("analytics.db");
     let geoip_db_path = std::env::var("GEOIP_DB_PATH")
@@ -127,8 +210,47 @@ async fn main() -> Result<()> {
     let analytics_db = Arc::new(
         analytics::AnalyticsDb::new(&analytics_db_path, geoip_db_path.as_ref())
             .context("Failed to initialize analytics database")?
+            .with_location_granularity(analytics::LocationGranularity::from_env()?)
+            .with_play_half_life_days(
+                std::env::var("ANALYTICS_PLAY_HALF_LIFE_DAYS")
+                    .ok()
+                    .and_then(|s| s.parse::<f64>().ok()),
+            )
+            .with_location_min_views(
+                std::env::var("ANALYTICS_LOCATION_MIN_VIEWS")
+                    .ok()
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .unwrap_or(1),
+            )
+            .with_bot_ua_patterns(
+                std::env::var("ANALYTICS_BOT_UA_PATTERNS")
+                    .map(|s| s.split(',').map(|p| p.to_string()).collect())
+                    .unwrap_or_else(|_| analytics::BOT_UA_PATTERNS.iter().map(|p| p.to_string()).collect()),
+            )
+            .with_play_dedup_minutes(
+                std::env::var("PLAY_DEDUP_MINUTES")
+                    .ok()
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .unwrap_or(analytics::DEFAULT_PLAY_DEDUP_MINUTES),
+            )
     );
     
+    // Daily stats snapshots for trend analysis (ANALYTICS_SNAPSHOT_INTERVAL_HOURS=0 disables)
+    let snapshot_hours = std::env::var("ANALYTICS_SNAPSHOT_INTERVAL_HOURS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(24);
+    if snapshot_hours > 0 {
+        let snapshot_dir = std::env::var("ANALYTICS_SNAPSHOT_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from("analytics-snapshots"));
+        tokio::spawn(analytics::run_stats_snapshots(
+            analytics_db.clone(),
+            snapshot_dir,
+            Duration::from_secs(snapshot_hours * 3600),
+        ));
+    }
+    
     if geoip_db_path.is_none() || !geoip_db_path.as_ref().unwrap().exists() {
         info!("GeoIP database not found. Location tracking will be disabled. Set GEOIP_DB_PATH env var or place GeoLite2-City.mmdb in the project root.");
     } else {
@@ -147,35 +269,107 @@ async fn main() -> Result<()> {
         speaker_meta_cache,
         episode_topics_map_cache,
         episode_files_cache,
+        taxonomy_cache,
+        query_embedding_cache,
+        title_embedding_cache,
+        embedding_model_mismatches,
+        llm_health_cache,
         analytics_db,
+        chat_rate_limiter: Arc::new(handlers::rate_limit::RateLimiter::new(cfg.rate_limit_per_min)),
+        metrics: Arc::default(),
+        ready: Arc::new(AtomicBool::new(false)),
     };
 
-    // Pre-cache all embedding databases
-    info!("Pre-loading all embedding databases...");
-    preload_embedding_databases(&app_state).await;
-    info!("Finished pre-loading embedding databases");
+    // Pre-cache all embedding databases in the background; /api/health/ready reports 503 until done
+    let warm_state = app_state.clone();
+    tokio::spawn(async move {
+        info!("Pre-loading all embedding databases...");
+        warm_then_ready(&warm_state, preload_embedding_databases(&warm_state)).await;
+        info!("Finished pre-loading embedding databases");
+    });
 
 
-    let app = Router::new()
-        .route("/api/chat", post(chat))
-        .route("/api/episodes/search", post(episodes_search))
-        .route("/api/episodes/latest", post(episodes_latest))
-        .route("/api/speakers", axum::routing::get(speakers_list))
-        .route("/api/analytics/track", post(track))
-        .route("/api/analytics/track-episode-play", post(track_episode_play))
-        .route("/api/analytics/stats", axum::routing::get(stats))
-        .route("/api/analytics/test-data", axum::routing::get(insert_test_data_endpoint))
-        .route("/api/health", axum::routing::get(health))
-        .layer(cors)
-        .layer(TraceLayer::new_for_http())
-        .with_state(app_state);
+    if let Some(metrics_addr) = cfg.metrics_bind_addr {
+        let metrics_listener = tokio::net::TcpListener::bind(metrics_addr).await?;
+        let metrics_app = metrics_router(app_state.clone());
+        info!("Metrics listening on http://{}/metrics", metrics_addr);
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(metrics_listener, metrics_app).await {
+                tracing::error!("Metrics listener failed: {}", e);
+            }
+        });
+    }
+
+    let ready = app_state.ready.clone();
+    let app = build_router(app_state);
 
     info!("RAG backend listening on http://{}", cfg.bind_addr);
     let listener = tokio::net::TcpListener::bind(cfg.bind_addr).await?;
-    axum::serve(listener, app).await?;
+    serve(listener, app, async move {
+        shutdown_signal().await;
+        // Let load balancers stop routing here while the open requests finish
+        ready.store(false, Ordering::Release);
+    })
+    .await?;
+    Ok(())
+}
+
+/// Span around each request; its fields end up in every log line of the request
+fn request_span<B>(req: &axum::http::Request<B>) -> tracing::Span {
+    // Path only: query strings may carry tokens
+    tracing::info_span!("request", method = %req.method(), path = %req.uri().path())
+}
+
+fn log_response<B>(res: &axum::http::Response<B>, latency: Duration, _span: &tracing::Span) {
+    info!(status = res.status().as_u16(), latency_ms = latency.as_millis() as u64, "request finished");
+}
+
+/// Serve until `shutdown` resolves, then stop accepting connections and wait for in-flight
+/// requests (including chat streams) to complete
+async fn serve<F>(listener: tokio::net::TcpListener, app: Router, shutdown: F) -> std::io::Result<()>
+where
+    F: std::future::Future<Output = ()> + Send + 'static,
+{
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move {
+            shutdown.await;
+            info!("Shutting down, draining in-flight requests...");
+        })
+        .await?;
+    info!("Shutdown complete");
     Ok(())
 }
 
+/// Resolves on SIGINT (Ctrl+C) or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("SIGINT received"),
+        _ = terminate => info!("SIGTERM received"),
+    }
+}
+
 /// Pre-load all embedding databases found in the db/ directory
 async fn preload_embedding_databases(app_state: &AppState) {
     let db_dir = PathBuf::from("db");
@@ -194,9 +388,12 @@ async fn preload_embedding_databases(app_state: &AppState) {
         let path = entry.path();
         if path.is_dir() {
             if let Some(podcast_id) = path.file_name().and_then(|n| n.to_str()) {
-                // Check if rag-embeddings.json exists
-                let rag_path = path.join("rag-embeddings.json");
-                if tokio::fs::metadata(&rag_path).await.is_ok() {
+                // Check if rag-embeddings.json (or an indices.json manifest) exists
+                let rag_path = gzip::prefer_gz(path.join("rag-embeddings.json")).await;
+                let manifest_path = path.join("indices.json");
+                if tokio::fs::metadata(&rag_path).await.is_ok()
+                    || tokio::fs::metadata(&manifest_path).await.is_ok()
+                {
                     podcast_ids.push(podcast_id.to_string());
                 }
             }
@@ -232,3 +429,148 @@ async fn preload_embedding_databases(app_state: &AppState) {
         let _ = task.await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::spawn_app;
+
+    #[tokio::test]
+    async fn test_health_needs_no_token_when_auth_configured() {
+        let mut cfg = AppConfig::for_tests();
+        cfg.auth_token = Some("secret".to_string());
+        cfg.stats_auth_token = Some("stats-secret".to_string());
+        let app = build_router(AppState::for_tests(cfg));
+
+        let addr = spawn_app(app).await;
+
+        let http = Client::new();
+        // No RAG index loaded, but must not be rejected by auth
+        let resp = http.get(format!("http://{addr}/api/health")).send().await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+
+        // Readiness is still warming up, but must not be rejected by auth
+        let resp = http.get(format!("http://{addr}/api/health/ready")).send().await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+
+        // Protected endpoints still reject requests without a token
+        let resp = http.get(format!("http://{addr}/api/analytics/stats")).send().await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_degraded_mode_without_key() {
+        let mut cfg = AppConfig::for_tests();
+        cfg.llm_api_key = String::new();
+        cfg.llm_available = false;
+        let app = build_router(AppState::for_tests(cfg));
+
+        let addr = spawn_app(app).await;
+
+        let http = Client::new();
+        let resp = http
+            .post(format!("http://{addr}/api/chat"))
+            .json(&serde_json::json!({ "query": "Universal Control" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+
+        // Endpoints that don't need the LLM keep working
+        let resp = http.get(format!("http://{addr}/api/health/embeddings")).send().await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let resp = http.get(format!("http://{addr}/api/analytics/stats")).send().await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        // No data behind them, but not refused for the missing key
+        for endpoint in ["similar", "transcript.vtt", "transcript"] {
+            let resp = http.get(format!("http://{addr}/api/episodes/freakshow/1/{endpoint}")).send().await.unwrap();
+            assert_ne!(resp.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE, "{endpoint}");
+        }
+
+        // Admin endpoints stay closed on a server without tokens
+        let resp = http
+            .post(format!("http://{addr}/api/chat/prompt"))
+            .json(&serde_json::json!({ "query": "Universal Control" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::FORBIDDEN);
+        let resp = http.delete(format!("http://{addr}/api/analytics/stats/user/abc")).send().await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_protected_podcast_episode_endpoints_need_token() {
+        let mut cfg = AppConfig::for_tests();
+        cfg.podcast_auth_tokens.insert("lnp".to_string(), "lnp-secret".to_string());
+        let app = build_router(AppState::for_tests(cfg));
+
+        let addr = spawn_app(app).await;
+
+        let http = Client::new();
+        for endpoint in ["similar", "transcript.vtt", "transcript"] {
+            let url = format!("http://{addr}/api/episodes/lnp/1/{endpoint}");
+            let resp = http.get(&url).send().await.unwrap();
+            assert_eq!(resp.status(), reqwest::StatusCode::FORBIDDEN, "{endpoint}");
+            // With the token the request gets past auth (there is no data behind it)
+            let resp = http.get(&url).header("x-auth-token", "lnp-secret").send().await.unwrap();
+            assert_ne!(resp.status(), reqwest::StatusCode::FORBIDDEN, "{endpoint}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_graceful_shutdown_drains_in_flight_requests() {
+        let started = std::sync::Arc::new(tokio::sync::Notify::new());
+        let handler_started = started.clone();
+        let app = Router::new().route(
+            "/slow",
+            axum::routing::get(move || async move {
+                handler_started.notify_one();
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                "done"
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(serve(listener, app, async {
+            let _ = stop_rx.await;
+        }));
+
+        let request = tokio::spawn(async move { Client::new().get(format!("http://{addr}/slow")).send().await });
+        started.notified().await;
+        stop_tx.send(()).unwrap();
+
+        // The request started before shutdown still gets its answer
+        let resp = request.await.unwrap().unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        assert_eq!(resp.text().await.unwrap(), "done");
+        tokio::time::timeout(Duration::from_secs(5), server).await.unwrap().unwrap().unwrap();
+
+        // New connections are refused afterwards
+        assert!(Client::new().get(format!("http://{addr}/slow")).send().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_metrics_count_routes_without_auth() {
+        let mut cfg = AppConfig::for_tests();
+        cfg.auth_token = Some("secret".to_string());
+        let app = build_router(AppState::for_tests(cfg.clone()));
+        let addr = spawn_app(app).await;
+
+        let http = Client::new();
+        http.get(format!("http://{addr}/api/health/ready")).send().await.unwrap();
+        let resp = http.get(format!("http://{addr}/metrics")).send().await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let body = resp.text().await.unwrap();
+        assert!(body.contains(r#"http_requests_total{route="/api/health/ready",method="GET",status="503"} 1"#), "{body}");
+        assert!(body.contains("rag_index_cache_hits_total 0"));
+
+        // With a separate metrics address the public router doesn't expose it
+        cfg.metrics_bind_addr = Some("127.0.0.1:0".parse().unwrap());
+        let app = build_router(AppState::for_tests(cfg));
+        let addr = spawn_app(app).await;
+        let resp = http.get(format!("http://{addr}/metrics")).send().await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+}
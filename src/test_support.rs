@@ -0,0 +1,16 @@
+//! Helpers shared by the unit tests of the binaries
+
+use std::net::SocketAddr;
+
+use axum::Router;
+
+/// Serve `app` on an ephemeral local port for the rest of the test. Handlers can extract the
+/// peer address (`ConnectInfo`) like behind the real listener.
+pub async fn spawn_app(app: Router) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
+    });
+    addr
+}
@@ -1,21 +1,152 @@
-use std::{path::{Path, PathBuf}, sync::Arc};
+use std::{fmt, io::Read, path::Path, sync::Arc};
 
-use anyhow::{anyhow, Context, Result};
-use serde::Deserialize;
+use anyhow::{Context, Result};
+use serde::de::{Deserializer, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
 
 use crate::config::AppState;
-use crate::utils::{hms_to_seconds, seconds_to_hms};
+use crate::utils::{hms_to_seconds, read_sidecar, seconds_to_hms, tokenize, write_sidecar, SourceStamp};
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct TranscriptFile {
     pub transcript: Vec<TranscriptEntry>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct TranscriptEntry {
     pub speaker: Option<String>,
     pub time: String,
     pub text: String,
+    /// Detected language of `text` ("de"/"en"), filled once at load; None if undetectable
+    #[serde(skip)]
+    pub lang: Option<&'static str>,
+}
+
+const GERMAN_STOPWORDS: &[&str] = &[
+    "der", "die", "das", "und", "ist", "nicht", "ich", "ein", "eine", "es", "zu", "mit", "auf",
+    "sich", "auch", "wir", "aber", "was", "dass", "den", "von", "noch", "ja", "halt",
+];
+const ENGLISH_STOPWORDS: &[&str] = &[
+    "the", "and", "is", "not", "i", "a", "to", "of", "it", "that", "with", "you", "we", "but",
+    "what", "this", "are", "was", "have", "for", "just", "so",
+];
+
+/// Cheap stopword-based language guess for a transcript line (German vs English).
+/// Returns None for short or ambiguous lines.
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let (mut de, mut en) = (0usize, 0usize);
+    for word in text.split(|c: char| !c.is_alphabetic()).filter(|w| !w.is_empty()) {
+        let word = word.to_lowercase();
+        if GERMAN_STOPWORDS.contains(&word.as_str()) {
+            de += 1;
+        }
+        if ENGLISH_STOPWORDS.contains(&word.as_str()) {
+            en += 1;
+        }
+    }
+    match de.max(en) {
+        n if n < 2 => None,
+        _ if de > en => Some("de"),
+        _ if en > de => Some("en"),
+        _ => None,
+    }
+}
+
+/// Transcript file variant that skips entries which fail to deserialize
+#[derive(Debug, Deserialize)]
+struct LenientTranscriptFile {
+    transcript: LenientEntries,
+}
+
+#[derive(Debug, Default)]
+struct LenientEntries {
+    entries: Vec<TranscriptEntry>,
+    skipped: usize,
+}
+
+impl<'de> Deserialize<'de> for LenientEntries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = LenientEntries;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a list of transcript entries")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error> {
+                let mut out = LenientEntries::default();
+                // Read each element as a generic value first so a bad entry doesn't abort the list
+                while let Some(value) = seq.next_element::<serde_json::Value>()? {
+                    match TranscriptEntry::deserialize(value) {
+                        Ok(entry) => out.entries.push(entry),
+                        Err(_) => out.skipped += 1,
+                    }
+                }
+                Ok(out)
+            }
+        }
+
+        deserializer.deserialize_seq(EntriesVisitor)
+    }
+}
+
+/// Parse a transcript JSON document.
+///
+/// In strict mode any malformed entry fails the whole file. In lenient mode malformed
+/// entries are dropped; the number of skipped entries is returned alongside the rest.
+pub fn parse_transcript<R: Read>(reader: R, lenient: bool) -> Result<(Vec<TranscriptEntry>, usize)> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    if lenient {
+        let tf = LenientTranscriptFile::deserialize(&mut deserializer)?;
+        Ok((tf.transcript.entries, tf.transcript.skipped))
+    } else {
+        let tf = TranscriptFile::deserialize(&mut deserializer)?;
+        Ok((tf.transcript, 0))
+    }
+}
+
+/// Layout of the transcript sidecar; bump when the format or `TranscriptEntry` changes
+const TRANSCRIPT_SIDECAR_VERSION: u8 = 2;
+const TRANSCRIPT_SIDECAR_MAGIC: [u8; 4] = *b"TRSB";
+
+/// Sidecar payload: the parse result and the strictness mode it was parsed in
+#[derive(Serialize, Deserialize)]
+struct TranscriptSidecar {
+    lenient: bool,
+    skipped: usize,
+    entries: Vec<TranscriptEntry>,
+}
+
+/// Parse a transcript JSON file through its binary sidecar (`<name>.bin`, bincode). The sidecar
+/// is (re)written when missing, built from a different version of the JSON or parsed in the
+/// other strictness mode; failing to write it only costs the speedup.
+fn read_transcript_file(path: &Path, lenient: bool) -> Result<(Vec<TranscriptEntry>, usize)> {
+    let bin_path = path.with_extension("bin");
+    let source = SourceStamp::of(path)?;
+    match read_transcript_sidecar(&bin_path, source, lenient) {
+        Ok(Some(parsed)) => return Ok(parsed),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Ignoring unreadable {}: {:#}", bin_path.display(), e),
+    }
+
+    let reader = crate::gzip::open_maybe_gzip(path)?;
+    let (entries, skipped) = parse_transcript(reader, lenient)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let sidecar = TranscriptSidecar { lenient, skipped, entries };
+    if let Err(e) = write_sidecar(&bin_path, TRANSCRIPT_SIDECAR_MAGIC, TRANSCRIPT_SIDECAR_VERSION, source, &sidecar) {
+        tracing::warn!("Could not write {}: {:#}", bin_path.display(), e);
+    }
+    Ok((sidecar.entries, sidecar.skipped))
+}
+
+/// Read a sidecar written by `read_transcript_file`; `None` if it is missing, of another
+/// version, built from a different source file or in the other strictness mode.
+fn read_transcript_sidecar(path: &Path, source: SourceStamp, lenient: bool) -> Result<Option<(Vec<TranscriptEntry>, usize)>> {
+    let sidecar: Option<TranscriptSidecar> =
+        read_sidecar(path, TRANSCRIPT_SIDECAR_MAGIC, TRANSCRIPT_SIDECAR_VERSION, source)?;
+    Ok(sidecar.filter(|s| s.lenient == lenient).map(|s| (s.entries, s.skipped)))
 }
 
 pub async fn load_transcript_entries(
@@ -31,30 +162,21 @@ pub async fn load_transcript_entries(
     }
 
     let fname = format!("{episode_number}-ts.json");
-    let path = episodes_dir.join(fname);
+    let path = crate::gzip::prefer_gz(episodes_dir.join(fname)).await;
     
     // Use streaming deserialization - open file directly in blocking task
     let path_clone = path.clone();
-    let tf: TranscriptFile = match tokio::task::spawn_blocking(move || {
-        use serde_json::Deserializer;
-        use std::fs::File;
-        use std::io::BufReader;
-        
+    let lenient = st.cfg.transcript_lenient;
+    let (entries, skipped) = match tokio::task::spawn_blocking(move || {
         // Check if file exists first to avoid unnecessary error context wrapping
         if !path_clone.exists() {
             return Err(anyhow::anyhow!("File not found: {}", path_clone.display()));
         }
-        
-        let file = File::open(&path_clone)
-            .with_context(|| format!("Failed to open {}", path_clone.display()))?;
-        let reader = BufReader::new(file);
-        let mut deserializer = Deserializer::from_reader(reader);
-        serde::Deserialize::deserialize(&mut deserializer)
-            .with_context(|| format!("Failed to parse {}", path_clone.display()))
+        read_transcript_file(&path_clone, lenient)
     }).await
         .with_context(|| "Failed to spawn blocking task")?
     {
-        Ok(tf) => tf,
+        Ok(parsed) => parsed,
         Err(e) => {
             // Check if it's a "file not found" error by checking the entire error chain
             let error_msg = format!("{}", e);
@@ -91,17 +213,133 @@ pub async fn load_transcript_entries(
         }
     };
 
-    let arc = Arc::new(tf.transcript);
+    if skipped > 0 {
+        tracing::warn!("Skipped {} malformed entries in transcript {}", skipped, path.display());
+    }
+
+    let mut entries = match st.cfg.transcript_merge_gap_sec {
+        Some(max_gap) => merge_speaker_runs(entries, max_gap),
+        None => entries,
+    };
+
+    // Detect per-line language once; the result is cached with the transcript
+    for e in &mut entries {
+        e.lang = detect_language(&e.text);
+    }
+
+    let arc = Arc::new(entries);
     st.transcript_cache.insert(cache_key, arc.clone()).await;
     Ok(arc)
 }
 
+/// Merge consecutive lines of the same speaker into one entry when each starts at most
+/// `max_gap_sec` after the previous one. The merged entry keeps the first line's time.
+/// Lines without a speaker or a parseable time are never merged.
+pub fn merge_speaker_runs(entries: Vec<TranscriptEntry>, max_gap_sec: f64) -> Vec<TranscriptEntry> {
+    let mut merged: Vec<TranscriptEntry> = Vec::with_capacity(entries.len());
+    // Start time of the last line folded into the current merged entry
+    let mut last_sec: Option<f64> = None;
+    for e in entries {
+        let t = hms_to_seconds(&e.time);
+        if let (Some(prev), Some(prev_sec), Some(t)) = (merged.last_mut(), last_sec, t) {
+            if e.speaker.is_some() && prev.speaker == e.speaker && t - prev_sec <= max_gap_sec {
+                let text = e.text.trim();
+                if !text.is_empty() {
+                    if !prev.text.is_empty() {
+                        prev.text.push(' ');
+                    }
+                    prev.text.push_str(text);
+                }
+                last_sec = Some(t);
+                continue;
+            }
+        }
+        last_sec = t;
+        merged.push(e);
+    }
+    merged
+}
+
+/// Whether a transcript speaker label names the requested speaker: case-insensitive, ignoring
+/// punctuation ("tim pritlove:"), and one name's words may be a subset of the other's
+/// ("Tim" vs "Tim Pritlove")
+pub fn speaker_matches(entry_speaker: &str, filter: &str) -> bool {
+    let entry = tokenize(entry_speaker);
+    let wanted = tokenize(filter);
+    if entry.is_empty() || wanted.is_empty() {
+        return false;
+    }
+    let (shorter, longer) = if entry.len() <= wanted.len() { (&entry, &wanted) } else { (&wanted, &entry) };
+    shorter.iter().all(|w| longer.contains(w))
+}
+
+/// Entries starting within `[from_sec, to_sec]`; without bounds everything is kept, otherwise
+/// entries whose time does not parse are left out
+pub fn transcript_window(entries: &[TranscriptEntry], from_sec: Option<f64>, to_sec: Option<f64>) -> Vec<TranscriptEntry> {
+    if from_sec.is_none() && to_sec.is_none() {
+        return entries.to_vec();
+    }
+    entries
+        .iter()
+        .filter(|e| {
+            hms_to_seconds(&e.time).is_some_and(|t| {
+                from_sec.is_none_or(|from| t >= from) && to_sec.is_none_or(|to| t <= to)
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+/// Length of the last WebVTT cue, which has no following line to end at
+const VTT_LAST_CUE_SEC: f64 = 5.0;
+
+/// WebVTT captions: one cue per line with a parseable time, ending where the next line starts.
+/// Speakers become voice spans (`<v Tim>`); lines without text are left out.
+pub fn transcript_to_vtt(entries: &[TranscriptEntry]) -> String {
+    let timed: Vec<(f64, &TranscriptEntry)> = entries
+        .iter()
+        .filter(|e| !e.text.trim().is_empty())
+        .filter_map(|e| Some((hms_to_seconds(&e.time)?, e)))
+        .collect();
+
+    let mut out = String::from("WEBVTT\n");
+    for (i, &(start, e)) in timed.iter().enumerate() {
+        // Cue end must come after its start, also for lines sharing a timestamp
+        let end = match timed.get(i + 1) {
+            Some(&(next, _)) if next > start => next,
+            Some(_) => start + 1.0,
+            None => start + VTT_LAST_CUE_SEC,
+        };
+        out.push_str(&format!("\n{} --> {}\n", vtt_timestamp(start), vtt_timestamp(end)));
+        if let Some(speaker) = e.speaker.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            out.push_str(&format!("<v {}>", vtt_escape(speaker)));
+        }
+        // A blank line would end the cue early
+        let text: Vec<String> = e.text.lines().map(str::trim).filter(|l| !l.is_empty()).map(vtt_escape).collect();
+        out.push_str(&text.join("\n"));
+        out.push('\n');
+    }
+    out
+}
+
+/// `HH:MM:SS.mmm`
+fn vtt_timestamp(sec: f64) -> String {
+    let ms = (sec.max(0.0) * 1000.0).round() as u64;
+    format!("{:02}:{:02}:{:02}.{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000)
+}
+
+/// Escape cue text; this also keeps "-->" out of the payload
+fn vtt_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
 pub fn excerpt_for_window(
     transcript: &[TranscriptEntry],
     start_sec: f64,
     end_sec: f64,
     max_chars: usize,
     speaker_filter: Option<&str>,
+    language_filter: Option<&str>,
 ) -> String {
     let mut out = String::new();
     let mut first = true;
@@ -119,8 +357,14 @@ pub fn excerpt_for_window(
 
         // Filter by speaker if requested
         if let Some(filter_speaker) = speaker_filter {
-            let entry_speaker = e.speaker.as_deref().unwrap_or("").trim();
-            if !entry_speaker.eq_ignore_ascii_case(filter_speaker) {
+            if !speaker_matches(e.speaker.as_deref().unwrap_or(""), filter_speaker) {
+                continue;
+            }
+        }
+
+        // Filter by language if requested; lines without a detected language are kept
+        if let (Some(want), Some(lang)) = (language_filter, e.lang) {
+            if !lang.eq_ignore_ascii_case(want) {
                 continue;
             }
         }
@@ -166,3 +410,194 @@ pub fn excerpt_for_window(
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONE_MALFORMED: &str = r#"{
+        "transcript": [
+            { "speaker": "Tim", "time": "0:00", "text": "Hallo" },
+            { "speaker": "Tim", "time": 12, "text": null },
+            { "speaker": null, "time": "0:10", "text": "Willkommen" }
+        ]
+    }"#;
+
+    #[test]
+    fn test_lenient_parse_skips_malformed_entry() {
+        let (entries, skipped) = parse_transcript(ONE_MALFORMED.as_bytes(), true).unwrap();
+        assert_eq!(skipped, 1);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].text, "Hallo");
+        assert_eq!(entries[1].text, "Willkommen");
+    }
+
+    #[test]
+    fn test_strict_parse_rejects_malformed_entry() {
+        assert!(parse_transcript(ONE_MALFORMED.as_bytes(), false).is_err());
+    }
+
+    #[test]
+    fn test_transcript_sidecar_roundtrip_and_invalidation() {
+        let dir = std::env::temp_dir().join(format!("transcript-sidecar-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("281-ts.json");
+        let bin_path = dir.join("281-ts.bin");
+        std::fs::write(&path, ONE_MALFORMED).unwrap();
+        let _ = std::fs::remove_file(&bin_path);
+
+        let (entries, skipped) = read_transcript_file(&path, true).unwrap();
+        assert!(bin_path.exists());
+        let source = SourceStamp::of(&path).unwrap();
+        let (cached, cached_skipped) = read_transcript_sidecar(&bin_path, source, true).unwrap().unwrap();
+        assert_eq!(cached_skipped, skipped);
+        let fields = |v: &[TranscriptEntry]| -> Vec<(Option<String>, String, String)> {
+            v.iter().map(|e| (e.speaker.clone(), e.time.clone(), e.text.clone())).collect()
+        };
+        assert_eq!(fields(&cached), fields(&entries));
+        assert_eq!(cached[1].speaker, None);
+
+        // Strict mode doesn't reuse the lenient parse (and fails on the malformed entry)
+        assert!(read_transcript_sidecar(&bin_path, source, false).unwrap().is_none());
+        assert!(read_transcript_file(&path, false).is_err());
+
+        // Editing the JSON regenerates the sidecar
+        std::fs::write(&path, r#"{ "transcript": [{ "speaker": "Roddi", "time": "0:01", "text": "Neu" }] }"#).unwrap();
+        assert!(read_transcript_sidecar(&bin_path, SourceStamp::of(&path).unwrap(), true).unwrap().is_none());
+        let (entries, skipped) = read_transcript_file(&path, true).unwrap();
+        assert_eq!((entries.len(), skipped), (1, 0));
+        let (cached, _) = read_transcript_sidecar(&bin_path, SourceStamp::of(&path).unwrap(), true).unwrap().unwrap();
+        assert_eq!(cached[0].text, "Neu");
+
+        // A truncated sidecar is ignored and rewritten
+        let bytes = std::fs::read(&bin_path).unwrap();
+        std::fs::write(&bin_path, &bytes[..bytes.len() - 2]).unwrap();
+        assert_eq!(read_transcript_file(&path, true).unwrap().0[0].text, "Neu");
+        assert_eq!(std::fs::read(&bin_path).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_speaker_matches_short_and_decorated_names() {
+        assert!(speaker_matches("Tim", "Tim Pritlove"));
+        assert!(speaker_matches("Tim Pritlove", "tim"));
+        assert!(speaker_matches("tim pritlove:", "Tim Pritlove"));
+        assert!(speaker_matches(" Tim: ", "Tim Pritlove"));
+        assert!(speaker_matches("Pritlove", "Tim Pritlove"));
+        assert!(!speaker_matches("Timo", "Tim Pritlove"));
+        assert!(!speaker_matches("Roddi", "Tim Pritlove"));
+        assert!(!speaker_matches("Tim Cook", "Tim Pritlove"));
+        assert!(!speaker_matches("", "Tim"));
+        assert!(!speaker_matches(":", "Tim"));
+    }
+
+    #[test]
+    fn test_transcript_to_vtt_cues_end_at_next_line() {
+        let entries: Vec<TranscriptEntry> = [
+            (Some("Tim"), "0:00:01", "Hallo <alle>"),
+            (None, "kaputt", "ohne Zeit"),
+            (Some("Roddi"), "0:01:05", "Moin.\n\nZweite Zeile"),
+            (Some("Tim"), "1:00:00", "Tschüss & bis bald"),
+        ]
+        .into_iter()
+        .map(|(speaker, time, text)| TranscriptEntry {
+            speaker: speaker.map(str::to_string),
+            time: time.to_string(),
+            text: text.to_string(),
+            lang: None,
+        })
+        .collect();
+
+        assert_eq!(
+            transcript_to_vtt(&entries),
+            "WEBVTT\n\
+             \n00:00:01.000 --> 00:01:05.000\n<v Tim>Hallo &lt;alle&gt;\n\
+             \n00:01:05.000 --> 01:00:00.000\n<v Roddi>Moin.\nZweite Zeile\n\
+             \n01:00:00.000 --> 01:00:05.000\n<v Tim>Tschüss &amp; bis bald\n"
+        );
+    }
+
+    #[test]
+    fn test_transcript_window_filters_by_start_time() {
+        let entries: Vec<TranscriptEntry> = [("0:00:01", "Hallo"), ("kaputt", "ohne Zeit"), ("0:01:05", "Moin"), ("1:00:00", "Tschüss")]
+            .into_iter()
+            .map(|(time, text)| TranscriptEntry { speaker: None, time: time.to_string(), text: text.to_string(), lang: None })
+            .collect();
+        let texts = |from, to| -> Vec<String> { transcript_window(&entries, from, to).into_iter().map(|e| e.text).collect() };
+
+        assert_eq!(texts(None, None).len(), 4);
+        assert_eq!(texts(Some(60.0), None), ["Moin", "Tschüss"]);
+        assert_eq!(texts(None, Some(65.0)), ["Hallo", "Moin"]);
+        assert_eq!(texts(Some(2.0), Some(3599.0)), ["Moin"]);
+        assert!(texts(Some(10.0), Some(5.0)).is_empty());
+    }
+
+    #[test]
+    fn test_merge_consecutive_same_speaker_lines() {
+        let entries: Vec<TranscriptEntry> = [
+            ("Tim", "0:00:01", "Also"),
+            ("Tim", "0:00:03", "das ist"),
+            ("Tim", "0:00:04", "spannend."),
+            ("Roddi", "0:00:05", "Finde ich auch."),
+            ("Tim", "0:00:06", "Genau."),
+            ("Tim", "0:01:00", "Nächstes Thema."),
+        ]
+        .into_iter()
+        .map(|(speaker, time, text)| TranscriptEntry {
+            speaker: Some(speaker.to_string()),
+            time: time.to_string(),
+            text: text.to_string(),
+            lang: None,
+        })
+        .collect();
+
+        let merged = merge_speaker_runs(entries, 5.0);
+        let lines: Vec<(&str, &str)> = merged.iter().map(|e| (e.time.as_str(), e.text.as_str())).collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("0:00:01", "Also das ist spannend."),
+                // Speaker change breaks the run
+                ("0:00:05", "Finde ich auch."),
+                ("0:00:06", "Genau."),
+                // Same speaker, but the gap is too large
+                ("0:01:00", "Nächstes Thema."),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_excerpt_language_filter() {
+        let mut entries: Vec<TranscriptEntry> = [
+            ("0:00:01", "Ich glaube, das ist nicht so einfach, aber wir machen das."),
+            ("0:00:05", "I think that is just the way it works with this setup."),
+            ("0:00:09", "Ja, und das war auch der Grund."),
+            ("0:00:12", "Okay."),
+        ]
+        .into_iter()
+        .map(|(time, text)| TranscriptEntry {
+            speaker: Some("Tim".to_string()),
+            time: time.to_string(),
+            text: text.to_string(),
+            lang: detect_language(text),
+        })
+        .collect();
+
+        let english = excerpt_for_window(&entries, 0.0, 60.0, 4000, None, Some("en"));
+        assert!(english.contains("the way it works"));
+        assert!(!english.contains("nicht so einfach"));
+        assert!(!english.contains("der Grund"));
+        // Undetectable lines are kept
+        assert!(english.contains("Okay."));
+
+        let german = excerpt_for_window(&entries, 0.0, 60.0, 4000, None, Some("de"));
+        assert!(german.contains("nicht so einfach") && german.contains("der Grund"));
+        assert!(!german.contains("the way it works"));
+
+        // No detection available: everything is included
+        for e in &mut entries {
+            e.lang = None;
+        }
+        let all = excerpt_for_window(&entries, 0.0, 60.0, 4000, None, Some("en"));
+        assert_eq!(all.lines().count(), 4);
+    }
+}
@@ -1,7 +1,18 @@
 // Utility functions for vector operations and string normalization
 
+/// Dot product over the common prefix of `a` and `b`.
+/// With the `simd` feature, AVX is used when the CPU supports it.
 #[inline]
 pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if let Some(sum) = simd::dot(a, b) {
+        return sum;
+    }
+    dot_scalar(a, b)
+}
+
+#[inline]
+fn dot_scalar(a: &[f32], b: &[f32]) -> f32 {
     let n = a.len().min(b.len());
     // Use chunked iteration for better cache locality and potential SIMD optimization
     // by the compiler
@@ -26,6 +37,14 @@ pub fn dot(a: &[f32], b: &[f32]) -> f32 {
 }
 
 pub fn l2_norm(v: &[f32]) -> f32 {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if let Some(sum) = simd::dot(v, v) {
+        return sum.sqrt();
+    }
+    l2_norm_scalar(v)
+}
+
+fn l2_norm_scalar(v: &[f32]) -> f32 {
     let mut s = 0.0f32;
     for &x in v {
         s += x * x;
@@ -33,6 +52,42 @@ pub fn l2_norm(v: &[f32]) -> f32 {
     s.sqrt()
 }
 
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod simd {
+    use std::arch::x86_64::{_mm256_add_ps, _mm256_loadu_ps, _mm256_mul_ps, _mm256_setzero_ps, _mm256_storeu_ps};
+
+    /// `None` (use the scalar path) without AVX or for vectors shorter than one lane group
+    pub fn dot(a: &[f32], b: &[f32]) -> Option<f32> {
+        let n = a.len().min(b.len());
+        if n < 8 || !is_x86_feature_detected!("avx") {
+            return None;
+        }
+        // SAFETY: AVX support was checked above
+        Some(unsafe { dot_avx(&a[..n], &b[..n]) })
+    }
+
+    /// 8 lanes per step; the tail that doesn't fill a lane group is summed scalar.
+    /// Callers pass slices of equal length.
+    #[target_feature(enable = "avx")]
+    unsafe fn dot_avx(a: &[f32], b: &[f32]) -> f32 {
+        let chunks = a.len() / 8;
+        let mut acc = _mm256_setzero_ps();
+        for i in 0..chunks {
+            // SAFETY: i * 8 + 7 < a.len() == b.len(); loadu has no alignment requirement
+            let (va, vb) = unsafe { (_mm256_loadu_ps(a.as_ptr().add(i * 8)), _mm256_loadu_ps(b.as_ptr().add(i * 8))) };
+            acc = _mm256_add_ps(acc, _mm256_mul_ps(va, vb));
+        }
+        let mut lanes = [0.0f32; 8];
+        // SAFETY: `lanes` holds exactly 8 f32
+        unsafe { _mm256_storeu_ps(lanes.as_mut_ptr(), acc) };
+        let mut sum: f32 = lanes.iter().sum();
+        for i in chunks * 8..a.len() {
+            sum += a[i] * b[i];
+        }
+        sum
+    }
+}
+
 pub fn normalize_for_match(s: &str) -> String {
     s.to_lowercase()
         .replace(|c: char| !c.is_alphanumeric() && !c.is_whitespace(), " ")
@@ -41,6 +96,11 @@ pub fn normalize_for_match(s: &str) -> String {
         .join(" ")
 }
 
+/// Words of `s` after `normalize_for_match`; shared by keyword matching and BM25
+pub fn tokenize(s: &str) -> Vec<String> {
+    normalize_for_match(s).split_whitespace().map(str::to_string).collect()
+}
+
 pub fn hms_to_seconds(s: &str) -> Option<f64> {
     let s = s.trim();
     if s.is_empty() {
@@ -74,5 +134,200 @@ pub fn seconds_to_hms(sec: f64) -> String {
     }
 }
 
+/// Trim a search query and reject it if empty or shorter than `min_len` characters
+pub fn validate_query(query: &str, min_len: usize) -> anyhow::Result<&str> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err(anyhow::anyhow!("query must not be empty"));
+    }
+    if query.chars().count() < min_len {
+        return Err(anyhow::anyhow!("query must be at least {} characters long", min_len));
+    }
+    Ok(query)
+}
+
+/// Strip common markdown (headers, bullets, quotes, bold/italic, code, links) for plain-text clients.
+/// Parenthesised citations like "(Episode 281, 12:38-17:19)" are left untouched.
+pub fn strip_markdown(s: &str) -> String {
+    s.lines()
+        .map(|line| strip_inline_markdown(strip_block_markdown(line)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
 
+fn strip_block_markdown(line: &str) -> &str {
+    let trimmed = line.trim_start();
+    let without_header = trimmed.trim_start_matches('#');
+    if without_header.len() < trimmed.len() && without_header.starts_with(' ') {
+        return without_header.trim_start();
+    }
+    for marker in ["- ", "* ", "+ ", "> "] {
+        if let Some(rest) = trimmed.strip_prefix(marker) {
+            return rest;
+        }
+    }
+    line
+}
 
+fn strip_inline_markdown(line: &str) -> String {
+    let line = line.replace("**", "").replace("__", "").replace('`', "");
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::with_capacity(line.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            // Single-asterisk emphasis: drop unless it stands alone (e.g. "2 * 3")
+            '*' => {
+                let prev_space = i == 0 || chars[i - 1].is_whitespace();
+                let next_space = i + 1 == chars.len() || chars[i + 1].is_whitespace();
+                if prev_space && next_space {
+                    out.push(c);
+                }
+            }
+            // [text](url) -> text (url)
+            '[' => {
+                let rest: String = chars[i..].iter().collect();
+                if let Some(close) = rest.find("](") {
+                    if let Some(end) = rest[close..].find(')') {
+                        let text = &rest[1..close];
+                        let url = &rest[close + 2..close + end];
+                        out.push_str(text);
+                        out.push_str(" (");
+                        out.push_str(url);
+                        out.push(')');
+                        i += rest[..close + end + 1].chars().count();
+                        continue;
+                    }
+                }
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Modification time and size of a source file a binary sidecar was built from.
+/// A sidecar whose stamp differs from its source is stale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SourceStamp {
+    mtime_nanos: u128,
+    len: u64,
+}
+
+impl SourceStamp {
+    pub fn of(path: &std::path::Path) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let meta = std::fs::metadata(path).with_context(|| format!("Failed to stat {}", path.display()))?;
+        let mtime_nanos = meta
+            .modified()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        Ok(Self { mtime_nanos, len: meta.len() })
+    }
+}
+
+/// Leads every binary sidecar, so one of another format, version or source is skipped
+/// before its payload is decoded
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct SidecarHeader {
+    magic: [u8; 4],
+    version: u8,
+    source: SourceStamp,
+}
+
+/// Write `payload` as a bincode sidecar behind its header. Goes through a temp file, so readers
+/// never see a partial sidecar.
+pub fn write_sidecar<T: serde::Serialize>(
+    path: &std::path::Path,
+    magic: [u8; 4],
+    version: u8,
+    source: SourceStamp,
+    payload: &T,
+) -> anyhow::Result<()> {
+    use anyhow::Context;
+    use std::io::Write;
+
+    let tmp_path = path.with_extension("bin.tmp");
+    let file = std::fs::File::create(&tmp_path).with_context(|| format!("Failed to create {}", tmp_path.display()))?;
+    let mut w = std::io::BufWriter::new(file);
+    bincode::serialize_into(&mut w, &SidecarHeader { magic, version, source })?;
+    bincode::serialize_into(&mut w, payload)?;
+    w.flush()?;
+    w.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    std::fs::rename(&tmp_path, path).with_context(|| format!("Failed to rename {}", tmp_path.display()))?;
+    Ok(())
+}
+
+/// Read a sidecar written by `write_sidecar`; `None` if it is missing, of another format or
+/// version, or built from a different source file.
+pub fn read_sidecar<T: serde::de::DeserializeOwned>(
+    path: &std::path::Path,
+    magic: [u8; 4],
+    version: u8,
+    source: SourceStamp,
+) -> anyhow::Result<Option<T>> {
+    use anyhow::Context;
+
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let mut rest: &[u8] = &bytes;
+    let header: SidecarHeader = bincode::deserialize_from(&mut rest)?;
+    if header != (SidecarHeader { magic, version, source }) {
+        return Ok(None);
+    }
+    let payload = bincode::deserialize_from(&mut rest)?;
+    if !rest.is_empty() {
+        return Err(anyhow::anyhow!("{} trailing bytes", rest.len()));
+    }
+    Ok(Some(payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dot_and_norm_match_scalar_on_random_vectors() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(42);
+        // Embedding size, lengths with a tail, shorter than one lane group
+        for len in [1536, 1537, 13, 5] {
+            for _ in 0..50 {
+                let a: Vec<f32> = (0..len).map(|_| rng.gen_range(-1.0f32..1.0)).collect();
+                let b: Vec<f32> = (0..len).map(|_| rng.gen_range(-1.0f32..1.0)).collect();
+                let (fast, scalar) = (dot(&a, &b), dot_scalar(&a, &b));
+                assert!((fast - scalar).abs() <= 1e-4 * scalar.abs().max(1.0), "dot {fast} vs {scalar} (len {len})");
+                let (fast, scalar) = (l2_norm(&a), l2_norm_scalar(&a));
+                assert!((fast - scalar).abs() <= 1e-4 * scalar.max(1.0), "norm {fast} vs {scalar} (len {len})");
+            }
+        }
+        // Mismatched lengths use the common prefix
+        assert_eq!(dot(&[1.0; 20], &[2.0; 9]), 18.0);
+    }
+
+    #[test]
+    fn test_strip_markdown_keeps_citations() {
+        let answer = "## Fazit\n\
+            Das ist **wichtig** und *ziemlich* klar (Episode 281, 12:38-17:19).\n\
+            - erster Punkt mit `code`\n\
+            * zweiter Punkt, siehe [Shownotes](https://freakshow.fm/fs281)";
+        assert_eq!(
+            strip_markdown(answer),
+            "Fazit\n\
+             Das ist wichtig und ziemlich klar (Episode 281, 12:38-17:19).\n\
+             erster Punkt mit code\n\
+             zweiter Punkt, siehe Shownotes (https://freakshow.fm/fs281)"
+        );
+        assert_eq!(strip_markdown("**bold**"), "bold");
+        assert_eq!(strip_markdown("2 * 3 = 6"), "2 * 3 = 6");
+    }
+}